@@ -1,3 +1,9 @@
+pub mod multisig;
+pub use multisig::*;
+
+pub mod token;
+pub use token::*;
+
 use snarkvm::console::{
     account::{Address, PrivateKey},
     network::Network,
@@ -650,7 +656,7 @@ mod test {
     }
 
     // A helper function to construct the next block.
-    fn construct_next_block<C: ConsensusStorage<CurrentNetwork>, R: Rng + CryptoRng>(
+    pub(crate) fn construct_next_block<C: ConsensusStorage<CurrentNetwork>, R: Rng + CryptoRng>(
         vm: &VM<CurrentNetwork, C>,
         private_key: &PrivateKey<CurrentNetwork>,
         transactions: &[Transaction<CurrentNetwork>],
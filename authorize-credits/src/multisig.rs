@@ -0,0 +1,531 @@
+use super::*;
+
+use snarkvm::circuit::Aleo;
+use snarkvm::prelude::{Process, Program};
+
+/// The source of the reference multisig program.
+/// Its initializer is `MULTISIG_INITIALIZER_PLACEHOLDER`, so use `multisig_program` to deploy it.
+pub const MULTISIG_PROGRAM: &str = include_str!("../programs/multisig.aleo");
+/// The address that may initialize `MULTISIG_PROGRAM`. No one holds its key, so it must be replaced before the program is deployed.
+pub const MULTISIG_INITIALIZER_PLACEHOLDER: &str =
+    "aleo1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq3ljyzc";
+
+// The base fees are estimated from that of `credits.aleo/transfer_public`, 263388 microcredits, rounded up.
+// The finalize blocks of `propose` and `approve` read and write a few mappings, as that of `transfer_public` does,
+// and `execute` calls `transfer_public`, so its fee covers both finalize blocks. `test_authorize_multisig_payout`
+// executes each function with its fee, which the VM rejects if it is below the cost of the execution.
+const MULTISIG_PROPOSE_FEE_IN_MICROCREDITS: u64 = 300000;
+const MULTISIG_APPROVE_FEE_IN_MICROCREDITS: u64 = 300000;
+const MULTISIG_EXECUTE_FEE_IN_MICROCREDITS: u64 = 600000;
+
+/// Returns the multisig program, which only `initializer` may initialize.
+/// The initializer should be the address that deploys the program, so that no one else can set its owners before the deployer does.
+pub fn multisig_program<N: Network>(initializer: &str) -> Result<Program<N>> {
+    // Initialize the initializer.
+    let initializer = Address::<N>::from_str(initializer)?;
+    Program::from_str(
+        &MULTISIG_PROGRAM.replace(MULTISIG_INITIALIZER_PLACEHOLDER, &initializer.to_string()),
+    )
+}
+
+/// Authorizes a proposal to pay `amount_in_microcredits` to `recipient` from a multisig wallet.
+/// The proposal counts as the proposer's approval.
+pub fn authorize_multisig_propose<N: Network>(
+    private_key: &str,
+    program_id: &str,
+    proposal_id: &str,
+    recipient: &str,
+    amount_in_microcredits: u64,
+    priority_fee_in_microcredits: u64,
+    rng: &mut (impl Rng + CryptoRng),
+) -> Result<(Authorization<N>, Authorization<N>)> {
+    // Initialize the private key.
+    let private_key = PrivateKey::<N>::from_str(private_key)?;
+    // Initialize the proposal ID.
+    let proposal_id = Field::<N>::from_str(proposal_id)?;
+    // Initialize the recipient.
+    let recipient = Address::<N>::from_str(recipient)?;
+    // Initialize the amount in microcredits.
+    let amount_in_microcredits = U64::<N>::new(amount_in_microcredits);
+
+    // Construct the function name.
+    let function_name = "propose";
+    // Construct the inputs.
+    let inputs = vec![
+        Value::<N>::from(Literal::Field(proposal_id)),
+        Value::from(Literal::Address(recipient)),
+        Value::from(Literal::U64(amount_in_microcredits)),
+    ];
+    // Construct the input types.
+    let input_types = vec![
+        ValueType::from_str("field.public")?,
+        ValueType::from_str("address.public")?,
+        ValueType::from_str("u64.public")?,
+    ];
+
+    // Construct the request.
+    let request = request(
+        &private_key,
+        program_id,
+        function_name,
+        inputs,
+        input_types,
+        rng,
+    )?;
+
+    // Construct the outputs.
+    let outputs = vec![Value::Future(Future::new(
+        ProgramID::from_str(program_id)?,
+        Identifier::from_str(function_name)?,
+        vec![
+            Argument::Plaintext(Plaintext::from(Literal::Address(Address::try_from(
+                private_key,
+            )?))),
+            Argument::Plaintext(Plaintext::from(Literal::Field(proposal_id))),
+            Argument::Plaintext(Plaintext::from(Literal::Address(recipient))),
+            Argument::Plaintext(Plaintext::from(Literal::U64(amount_in_microcredits))),
+        ],
+    ))];
+    // Construct the output types.
+    let output_types = vec![ValueType::from_str(&format!(
+        "{program_id}/{function_name}.future"
+    ))?];
+    // Construct the output registers.
+    let output_registers = vec![Some(Register::from_str("r3")?)];
+
+    // Construct the authorization.
+    let authorization = authorize(request, outputs, output_types, output_registers)?;
+    // Get the execution ID.
+    let execution_id = authorization.to_execution_id()?;
+    // Authorize the fee.
+    let fee_authorization = authorize_public_fee(
+        &private_key,
+        MULTISIG_PROPOSE_FEE_IN_MICROCREDITS,
+        priority_fee_in_microcredits,
+        execution_id,
+        rng,
+    )?;
+
+    // Return the authorizations.
+    Ok((authorization, fee_authorization))
+}
+
+/// Authorizes an owner's approval of a multisig proposal.
+pub fn authorize_multisig_approve<N: Network>(
+    private_key: &str,
+    program_id: &str,
+    proposal_id: &str,
+    priority_fee_in_microcredits: u64,
+    rng: &mut (impl Rng + CryptoRng),
+) -> Result<(Authorization<N>, Authorization<N>)> {
+    // Initialize the private key.
+    let private_key = PrivateKey::<N>::from_str(private_key)?;
+    // Initialize the proposal ID.
+    let proposal_id = Field::<N>::from_str(proposal_id)?;
+
+    // Construct the function name.
+    let function_name = "approve";
+    // Construct the inputs.
+    let inputs = vec![Value::<N>::from(Literal::Field(proposal_id))];
+    // Construct the input types.
+    let input_types = vec![ValueType::from_str("field.public")?];
+
+    // Construct the request.
+    let request = request(
+        &private_key,
+        program_id,
+        function_name,
+        inputs,
+        input_types,
+        rng,
+    )?;
+
+    // Construct the outputs.
+    let outputs = vec![Value::Future(Future::new(
+        ProgramID::from_str(program_id)?,
+        Identifier::from_str(function_name)?,
+        vec![
+            Argument::Plaintext(Plaintext::from(Literal::Address(Address::try_from(
+                private_key,
+            )?))),
+            Argument::Plaintext(Plaintext::from(Literal::Field(proposal_id))),
+        ],
+    ))];
+    // Construct the output types.
+    let output_types = vec![ValueType::from_str(&format!(
+        "{program_id}/{function_name}.future"
+    ))?];
+    // Construct the output registers.
+    let output_registers = vec![Some(Register::from_str("r1")?)];
+
+    // Construct the authorization.
+    let authorization = authorize(request, outputs, output_types, output_registers)?;
+    // Get the execution ID.
+    let execution_id = authorization.to_execution_id()?;
+    // Authorize the fee.
+    let fee_authorization = authorize_public_fee(
+        &private_key,
+        MULTISIG_APPROVE_FEE_IN_MICROCREDITS,
+        priority_fee_in_microcredits,
+        execution_id,
+        rng,
+    )?;
+
+    // Return the authorizations.
+    Ok((authorization, fee_authorization))
+}
+
+/// Authorizes the execution of a multisig proposal that has reached its threshold.
+/// The payout is made from the public balance of the multisig program.
+///
+/// Unlike the other builders, `execute` calls `credits.aleo/transfer_public`, so the function
+/// authorization is constructed by a `Process` that contains the multisig program.
+/// The fee authorization is still constructed by hand.
+#[allow(clippy::too_many_arguments)]
+pub fn authorize_multisig_execute<N: Network, A: Aleo<Network = N>>(
+    process: &Process<N>,
+    private_key: &str,
+    program_id: &str,
+    proposal_id: &str,
+    recipient: &str,
+    amount_in_microcredits: u64,
+    priority_fee_in_microcredits: u64,
+    rng: &mut (impl Rng + CryptoRng),
+) -> Result<(Authorization<N>, Authorization<N>)> {
+    // Initialize the private key.
+    let private_key = PrivateKey::<N>::from_str(private_key)?;
+    // Initialize the proposal ID.
+    let proposal_id = Field::<N>::from_str(proposal_id)?;
+    // Initialize the recipient.
+    let recipient = Address::<N>::from_str(recipient)?;
+    // Initialize the amount in microcredits.
+    let amount_in_microcredits = U64::<N>::new(amount_in_microcredits);
+
+    // Construct the inputs.
+    let inputs = vec![
+        Value::<N>::from(Literal::Field(proposal_id)),
+        Value::from(Literal::Address(recipient)),
+        Value::from(Literal::U64(amount_in_microcredits)),
+    ];
+
+    // Construct the authorization.
+    let authorization =
+        process.authorize::<A, _>(&private_key, program_id, "execute", inputs.iter(), rng)?;
+    // Get the execution ID.
+    let execution_id = authorization.to_execution_id()?;
+    // Authorize the fee.
+    let fee_authorization = authorize_public_fee(
+        &private_key,
+        MULTISIG_EXECUTE_FEE_IN_MICROCREDITS,
+        priority_fee_in_microcredits,
+        execution_id,
+        rng,
+    )?;
+
+    // Return the authorizations.
+    Ok((authorization, fee_authorization))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::construct_next_block;
+    use snarkvm::circuit::AleoV0;
+    use snarkvm::ledger::store::ConsensusStore;
+    use snarkvm::prelude::block::Transaction;
+    use snarkvm::prelude::store::helpers::memory::ConsensusMemory;
+    use snarkvm::prelude::store::ConsensusStorage;
+    use snarkvm::prelude::{Testnet3, ViewKey, VM};
+    use snarkvm::utilities::TestRng;
+
+    type CurrentNetwork = Testnet3;
+    type CurrentAleo = AleoV0;
+
+    // This tests that the multisig builders produce valid authorizations for a 2-of-3 credits payout.
+    // The test is split into the following steps:
+    //   1. Initialize a VM with a `genesis_private_key` and fund the first two owners publicly.
+    //   2. Deploy the multisig program, check that only its deployer may initialize it, and only with distinct owners,
+    //      then initialize it with three owners and a threshold of two, and fund it.
+    //   3. Authorize a `propose` by the first owner and check that the proposal cannot be executed yet.
+    //   4. Authorize an `approve` by the second owner.
+    //   5. Authorize an `execute` and check that the recipient received the payout.
+    #[test]
+    fn test_authorize_multisig_payout() {
+        // Initialize an RNG.
+        let rng = &mut TestRng::default();
+        // Initialize a VM.
+        let vm = VM::<CurrentNetwork, ConsensusMemory<CurrentNetwork>>::from(
+            ConsensusStore::open(None).unwrap(),
+        )
+        .unwrap();
+        // Initialize the genesis private key.
+        let genesis_private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        // Initialize private keys for the three owners.
+        let owners = (0..3)
+            .map(|_| PrivateKey::<CurrentNetwork>::new(rng).unwrap())
+            .collect::<Vec<_>>();
+        let owner_addresses = owners
+            .iter()
+            .map(|private_key| Address::try_from(private_key).unwrap())
+            .collect::<Vec<_>>();
+        // Initialize a private key for the recipient.
+        let recipient_private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        let recipient_address = Address::try_from(&recipient_private_key).unwrap();
+
+        // Create the genesis block.
+        let genesis_block = vm.genesis_beacon(&genesis_private_key, rng).unwrap();
+        // Add the genesis block to the VM.
+        vm.add_next_block(&genesis_block).unwrap();
+
+        // Get two valid records, the first is transferred to the first owner, the second is used for the fee.
+        let mut records = genesis_block.records();
+        let (_, record) = records.next().unwrap();
+        let first_record = record
+            .decrypt(&ViewKey::try_from(&genesis_private_key).unwrap())
+            .unwrap();
+        let (_, record) = records.next().unwrap();
+        let second_record = record
+            .decrypt(&ViewKey::try_from(&genesis_private_key).unwrap())
+            .unwrap();
+        let record_microcredits = match first_record
+            .data()
+            .get(&Identifier::from_str("microcredits").unwrap())
+            .unwrap()
+        {
+            Entry::Private(Plaintext::Literal(Literal::U64(amount), _)) => *amount,
+            _ => panic!("Invalid amount"),
+        };
+
+        // Transfer the first record to the first owner, publicly.
+        let inputs = vec![
+            Value::Record(first_record),
+            Value::from(Literal::Address(owner_addresses[0])),
+            Value::from(Literal::U64(U64::new(*record_microcredits))),
+        ];
+        let transaction = vm
+            .execute(
+                &genesis_private_key,
+                ("credits.aleo", "transfer_private_to_public"),
+                inputs.iter(),
+                Some(second_record),
+                0u64,
+                None,
+                rng,
+            )
+            .unwrap();
+        let next_block =
+            construct_next_block(&vm, &genesis_private_key, &[transaction], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+
+        // Fund the second owner and deploy the multisig program, using the first owner's public balance.
+        let program = multisig_program::<CurrentNetwork>(&owner_addresses[0].to_string()).unwrap();
+        let program_id = program.id().to_string();
+        let transfer = transfer_public(&vm, &owners[0], owner_addresses[1], 10_000_000, rng);
+        let deployment = vm
+            .deploy(&owners[0], &program, None, 0u64, None, rng)
+            .unwrap();
+        let next_block =
+            construct_next_block(&vm, &owners[0], &[transfer, deployment], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+
+        // Only the deployer may initialize the multisig, and only with distinct owners.
+        let initialize_inputs = |owners: [Address<CurrentNetwork>; 3]| {
+            let mut inputs = owners
+                .into_iter()
+                .map(|owner| Value::<CurrentNetwork>::from(Literal::Address(owner)))
+                .collect::<Vec<_>>();
+            inputs.push(Value::from_str("2u8").unwrap());
+            inputs
+        };
+        let inputs =
+            initialize_inputs([owner_addresses[0], owner_addresses[1], owner_addresses[2]]);
+        assert!(vm
+            .execute(
+                &owners[1],
+                (program_id.as_str(), "initialize"),
+                inputs.iter(),
+                None,
+                0u64,
+                None,
+                rng,
+            )
+            .is_err());
+        let duplicates =
+            initialize_inputs([owner_addresses[0], owner_addresses[0], owner_addresses[1]]);
+        assert!(vm
+            .execute(
+                &owners[0],
+                (program_id.as_str(), "initialize"),
+                duplicates.iter(),
+                None,
+                0u64,
+                None,
+                rng,
+            )
+            .is_err());
+        // The deployed program names its deployer in place of the placeholder.
+        assert!(!program
+            .to_string()
+            .contains(MULTISIG_INITIALIZER_PLACEHOLDER));
+
+        // Initialize the multisig with a threshold of two, and fund it.
+        let initialize = vm
+            .execute(
+                &owners[0],
+                (program_id.as_str(), "initialize"),
+                inputs.iter(),
+                None,
+                0u64,
+                None,
+                rng,
+            )
+            .unwrap();
+        let multisig_address = program.id().to_address().unwrap();
+        let funding = transfer_public(&vm, &owners[0], multisig_address, 5_000_000, rng);
+        let next_block =
+            construct_next_block(&vm, &owners[0], &[initialize, funding], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+
+        // The first owner proposes a payout to the recipient.
+        let proposal_id = "1field";
+        let amount_in_microcredits = 1_000_000;
+        let (authorization, fee_authorization) = authorize_multisig_propose::<CurrentNetwork>(
+            &owners[0].to_string(),
+            &program_id,
+            proposal_id,
+            &recipient_address.to_string(),
+            amount_in_microcredits,
+            10,
+            rng,
+        )
+        .unwrap();
+        let transaction = vm
+            .execute_authorization(authorization, Some(fee_authorization), None, rng)
+            .unwrap();
+        let next_block = construct_next_block(&vm, &owners[0], &[transaction], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+
+        // The proposal only has one approval, so executing it is rejected.
+        let (authorization, fee_authorization) = authorize_execute(
+            &vm,
+            &owners[0],
+            &program_id,
+            proposal_id,
+            recipient_address,
+            amount_in_microcredits,
+            rng,
+        );
+        let transaction = vm
+            .execute_authorization(authorization, Some(fee_authorization), None, rng)
+            .unwrap();
+        let next_block = construct_next_block(&vm, &owners[0], &[transaction], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+        assert_eq!(public_balance(&vm, recipient_address), None);
+
+        // The second owner approves the proposal.
+        let (authorization, fee_authorization) = authorize_multisig_approve::<CurrentNetwork>(
+            &owners[1].to_string(),
+            &program_id,
+            proposal_id,
+            10,
+            rng,
+        )
+        .unwrap();
+        let transaction = vm
+            .execute_authorization(authorization, Some(fee_authorization), None, rng)
+            .unwrap();
+        let next_block = construct_next_block(&vm, &owners[1], &[transaction], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+
+        // The proposal has reached the threshold, so executing it pays out to the recipient.
+        let (authorization, fee_authorization) = authorize_execute(
+            &vm,
+            &owners[1],
+            &program_id,
+            proposal_id,
+            recipient_address,
+            amount_in_microcredits,
+            rng,
+        );
+        let transaction = vm
+            .execute_authorization(authorization, Some(fee_authorization), None, rng)
+            .unwrap();
+        let next_block = construct_next_block(&vm, &owners[1], &[transaction], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+        assert_eq!(
+            public_balance(&vm, recipient_address),
+            Some(amount_in_microcredits)
+        );
+        assert_eq!(
+            public_balance(&vm, multisig_address),
+            Some(5_000_000 - amount_in_microcredits)
+        );
+    }
+
+    // A helper function to authorize a multisig `execute` with the VM's process.
+    fn authorize_execute<C: ConsensusStorage<CurrentNetwork>>(
+        vm: &VM<CurrentNetwork, C>,
+        private_key: &PrivateKey<CurrentNetwork>,
+        program_id: &str,
+        proposal_id: &str,
+        recipient: Address<CurrentNetwork>,
+        amount_in_microcredits: u64,
+        rng: &mut TestRng,
+    ) -> (Authorization<CurrentNetwork>, Authorization<CurrentNetwork>) {
+        authorize_multisig_execute::<CurrentNetwork, CurrentAleo>(
+            &vm.process().read(),
+            &private_key.to_string(),
+            program_id,
+            proposal_id,
+            &recipient.to_string(),
+            amount_in_microcredits,
+            10,
+            rng,
+        )
+        .unwrap()
+    }
+
+    // A helper function to construct a public transfer, paying the fee publicly.
+    fn transfer_public<C: ConsensusStorage<CurrentNetwork>>(
+        vm: &VM<CurrentNetwork, C>,
+        private_key: &PrivateKey<CurrentNetwork>,
+        recipient: Address<CurrentNetwork>,
+        amount_in_microcredits: u64,
+        rng: &mut TestRng,
+    ) -> Transaction<CurrentNetwork> {
+        let inputs = vec![
+            Value::<CurrentNetwork>::from(Literal::Address(recipient)),
+            Value::from(Literal::U64(U64::new(amount_in_microcredits))),
+        ];
+        vm.execute(
+            private_key,
+            ("credits.aleo", "transfer_public"),
+            inputs.iter(),
+            None,
+            0u64,
+            None,
+            rng,
+        )
+        .unwrap()
+    }
+
+    // A helper function to get the public balance of an address.
+    fn public_balance<C: ConsensusStorage<CurrentNetwork>>(
+        vm: &VM<CurrentNetwork, C>,
+        address: Address<CurrentNetwork>,
+    ) -> Option<u64> {
+        let balance = vm
+            .finalize_store()
+            .get_value_speculative(
+                ProgramID::from_str("credits.aleo").unwrap(),
+                Identifier::from_str("account").unwrap(),
+                &Plaintext::from(Literal::Address(address)),
+            )
+            .unwrap();
+        match balance {
+            Some(Value::Plaintext(Plaintext::Literal(Literal::U64(amount), _))) => Some(*amount),
+            None => None,
+            _ => panic!("Invalid balance"),
+        }
+    }
+}
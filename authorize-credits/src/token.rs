@@ -0,0 +1,715 @@
+use super::*;
+
+use anyhow::{bail, ensure};
+use snarkvm::prelude::{CastType, EntryType, FinalizeType, Instruction, Operand, Program};
+
+// The base fee of `transfer_public` is estimated from that of `credits.aleo/transfer_public`, 263388 microcredits,
+// rounded up, as their finalize blocks read and write the same number of mappings. `transfer_private` has no
+// finalize block, so its fee only covers the size of its execution. The tests execute both functions with their fees,
+// which the VM rejects if they are below the cost of the execution.
+const TOKEN_TRANSFER_PUBLIC_FEE_IN_MICROCREDITS: u64 = 300000;
+const TOKEN_TRANSFER_PRIVATE_FEE_IN_MICROCREDITS: u64 = 50000;
+
+/// A fungible token program (ARC-20 style), whose `transfer_public` and `transfer_private`
+/// functions have been checked against the standard signatures:
+///
+/// ```text
+/// function transfer_public:
+///     input r0 as address.public;
+///     input r1 as u64.public;
+///     async transfer_public self.caller r0 r1 into r2;
+///     output r2 as {program_id}/transfer_public.future;
+///
+/// finalize transfer_public:
+///     input r0 as address.public;
+///     input r1 as address.public;
+///     input r2 as u64.public;
+///     ...
+///
+/// function transfer_private:
+///     input r0 as {record}.record;
+///     input r1 as address.private;
+///     input r2 as u64.private;
+///     sub r0.amount r2 into r3;
+///     cast r1 r2 into r4 as {record}.record;
+///     cast r0.owner r3 into r5 as {record}.record;
+///     output r4 as {record}.record;
+///     output r5 as {record}.record;
+/// ```
+///
+/// where `{record}` has a private owner and a single `amount as u64.private` entry.
+/// The first output of `transfer_private` must be the record of the recipient, and the second must be
+/// the change, owned by the owner of the spent record. Only the registers after the inputs may differ.
+pub struct TokenProgram<N: Network> {
+    program: Program<N>,
+    record_name: Identifier<N>,
+}
+
+impl<N: Network> TokenProgram<N> {
+    /// Parses the program source and checks the signatures of its transfer functions.
+    pub fn from_source(source: &str) -> Result<Self> {
+        // Parse the program.
+        let program = Program::<N>::from_str(source)?;
+        // Get the program ID.
+        let program_id = program.id();
+
+        // Check the signature of `transfer_public`.
+        let function = program.get_function(&Identifier::from_str("transfer_public")?)?;
+        ensure!(
+            function.input_types()
+                == vec![
+                    ValueType::from_str("address.public")?,
+                    ValueType::from_str("u64.public")?,
+                ],
+            "'{program_id}/transfer_public' must take an 'address.public' and a 'u64.public'"
+        );
+        ensure!(
+            function.output_types()
+                == vec![ValueType::from_str(&format!(
+                    "{program_id}/transfer_public.future"
+                ))?],
+            "'{program_id}/transfer_public' must output a single future"
+        );
+        let finalize_input_types = match function.finalize_logic() {
+            Some(finalize) => finalize.input_types(),
+            None => bail!("'{program_id}/transfer_public' must have a finalize block"),
+        };
+        ensure!(
+            finalize_input_types
+                == vec![
+                    FinalizeType::from_str("address.public")?,
+                    FinalizeType::from_str("address.public")?,
+                    FinalizeType::from_str("u64.public")?,
+                ],
+            "'{program_id}/transfer_public' must finalize the caller, the recipient, and the amount"
+        );
+        // Check that the caller, the recipient, and the amount are passed to the finalize block, in that order.
+        let future = match function.outputs().first().map(|output| output.operand()) {
+            Some(Operand::Register(register)) => register.clone(),
+            _ => bail!("'{program_id}/transfer_public' must output its future from a register"),
+        };
+        let operands = vec![
+            Operand::from_str("self.caller")?,
+            Operand::from_str("r0")?,
+            Operand::from_str("r1")?,
+        ];
+        let asyncs = function
+            .instructions()
+            .iter()
+            .filter(|instruction| matches!(instruction, Instruction::Async(_)))
+            .collect::<Vec<_>>();
+        ensure!(
+            matches!(asyncs.as_slice(), [instruction]
+                if instruction.operands() == operands.as_slice()
+                    && instruction.destinations() == vec![future]),
+            "'{program_id}/transfer_public' must call 'async transfer_public self.caller r0 r1' into its output"
+        );
+
+        // Check the signature of `transfer_private`.
+        let function = program.get_function(&Identifier::from_str("transfer_private")?)?;
+        let input_types = function.input_types();
+        let record_name = match input_types.first() {
+            Some(ValueType::Record(record_name)) => *record_name,
+            _ => bail!("'{program_id}/transfer_private' must take a record as its first input"),
+        };
+        ensure!(
+            input_types
+                == vec![
+                    ValueType::Record(record_name),
+                    ValueType::from_str("address.private")?,
+                    ValueType::from_str("u64.private")?,
+                ],
+            "'{program_id}/transfer_private' must take a record, an 'address.private' and a 'u64.private'"
+        );
+        ensure!(
+            function.output_types()
+                == vec![
+                    ValueType::Record(record_name),
+                    ValueType::Record(record_name),
+                ],
+            "'{program_id}/transfer_private' must output two '{record_name}' records"
+        );
+
+        // Check that the first output is the record of the recipient, and the second is the change for the owner of the spent record.
+        let instructions = function.instructions();
+        // Returns the instruction that assigns the output at the given index.
+        let assigns =
+            |index: usize| match function.outputs().get(index).map(|output| output.operand()) {
+                Some(Operand::Register(register)) => instructions
+                    .iter()
+                    .find(|instruction| instruction.destinations().contains(register)),
+                _ => None,
+            };
+        // Returns `true` if the instruction casts the given operands into a record.
+        let casts_record = |instruction: Option<&Instruction<N>>, operands: &[Operand<N>]| {
+            match instruction {
+                Some(instruction) => {
+                    matches!(instruction, Instruction::Cast(cast) if cast.cast_type() == &CastType::Record(record_name))
+                        && instruction.operands() == operands
+                }
+                None => false,
+            }
+        };
+        ensure!(
+            casts_record(assigns(0), &[Operand::from_str("r1")?, Operand::from_str("r2")?]),
+            "'{program_id}/transfer_private' must output the record of the recipient first, as 'cast r1 r2'"
+        );
+        let subtraction = [Operand::from_str("r0.amount")?, Operand::from_str("r2")?];
+        let change = instructions
+            .iter()
+            .find(|instruction| {
+                matches!(instruction, Instruction::Sub(_)) && instruction.operands() == subtraction
+            })
+            .and_then(|instruction| instruction.destinations().pop());
+        let change = match change {
+            Some(change) => change,
+            None => bail!(
+                "'{program_id}/transfer_private' must compute the change as 'sub r0.amount r2'"
+            ),
+        };
+        ensure!(
+            casts_record(
+                assigns(1),
+                &[Operand::from_str("r0.owner")?, Operand::Register(change)]
+            ),
+            "'{program_id}/transfer_private' must output the change second, owned by 'r0.owner'"
+        );
+
+        // Check the record type.
+        let record_type = program.get_record(&record_name)?;
+        ensure!(
+            record_type.owner().is_private(),
+            "The owner of '{record_name}' must be private"
+        );
+        ensure!(
+            record_type.entries().len() == 1
+                && record_type.entries().get(&Identifier::from_str("amount")?)
+                    == Some(&EntryType::from_str("u64.private")?),
+            "'{record_name}' must have a single 'amount as u64.private' entry"
+        );
+
+        Ok(Self {
+            program,
+            record_name,
+        })
+    }
+
+    /// Returns the program ID.
+    pub fn program_id(&self) -> &ProgramID<N> {
+        self.program.id()
+    }
+
+    /// Authorizes a public token transfer.
+    pub fn authorize_transfer_public(
+        &self,
+        private_key: &str,
+        recipient: &str,
+        amount: u64,
+        priority_fee_in_microcredits: u64,
+        rng: &mut (impl Rng + CryptoRng),
+    ) -> Result<(Authorization<N>, Authorization<N>)> {
+        // Initialize the private key.
+        let private_key = PrivateKey::<N>::from_str(private_key)?;
+        // Initialize the recipient.
+        let recipient = Address::<N>::from_str(recipient)?;
+        // Initialize the amount.
+        let amount = U64::<N>::new(amount);
+
+        // Construct the program ID and function name.
+        let (program_id, function_name) = (self.program_id().to_string(), "transfer_public");
+        // Construct the inputs.
+        let inputs = vec![
+            Value::<N>::from(Literal::Address(recipient)),
+            Value::from(Literal::U64(amount)),
+        ];
+        // Construct the input types.
+        let input_types = vec![
+            ValueType::from_str("address.public")?,
+            ValueType::from_str("u64.public")?,
+        ];
+
+        // Construct the request.
+        let request = request(
+            &private_key,
+            &program_id,
+            function_name,
+            inputs,
+            input_types,
+            rng,
+        )?;
+
+        // Construct the outputs.
+        let outputs = vec![Value::Future(Future::new(
+            *self.program_id(),
+            Identifier::from_str(function_name)?,
+            vec![
+                Argument::Plaintext(Plaintext::from(Literal::Address(Address::try_from(
+                    private_key,
+                )?))),
+                Argument::Plaintext(Plaintext::from(Literal::Address(recipient))),
+                Argument::Plaintext(Plaintext::from(Literal::U64(amount))),
+            ],
+        ))];
+        // Construct the output types.
+        let output_types = vec![ValueType::from_str(&format!(
+            "{program_id}/{function_name}.future"
+        ))?];
+        // Construct the output registers.
+        let output_registers = self.output_registers(function_name)?;
+
+        // Construct the authorization.
+        let authorization = authorize(request, outputs, output_types, output_registers)?;
+        // Get the execution ID.
+        let execution_id = authorization.to_execution_id()?;
+        // Authorize the fee.
+        let fee_authorization = authorize_public_fee(
+            &private_key,
+            TOKEN_TRANSFER_PUBLIC_FEE_IN_MICROCREDITS,
+            priority_fee_in_microcredits,
+            execution_id,
+            rng,
+        )?;
+
+        // Return the authorizations.
+        Ok((authorization, fee_authorization))
+    }
+
+    /// Authorizes a private token transfer, spending the given record.
+    /// The record is given as plaintext, and must be owned by the private key.
+    pub fn authorize_transfer_private(
+        &self,
+        private_key: &str,
+        record: &str,
+        recipient: &str,
+        amount: u64,
+        priority_fee_in_microcredits: u64,
+        rng: &mut (impl Rng + CryptoRng),
+    ) -> Result<(Authorization<N>, Authorization<N>)> {
+        // Initialize the private key.
+        let private_key = PrivateKey::<N>::from_str(private_key)?;
+        // Initialize the sender.
+        let sender = Address::try_from(private_key)?;
+        // Initialize the record.
+        let record = Record::<N, Plaintext<N>>::from_str(record)?;
+        ensure!(
+            **record.owner() == sender,
+            "The record is not owned by the sender"
+        );
+        // Get the amount in the record.
+        let record_amount = match record.data().get(&Identifier::from_str("amount")?) {
+            Some(Entry::Private(Plaintext::Literal(Literal::U64(amount), _))) => **amount,
+            _ => bail!("The record does not have a private 'amount' entry"),
+        };
+        // Compute the change.
+        let change = match record_amount.checked_sub(amount) {
+            Some(change) => change,
+            None => bail!("The record has {record_amount} tokens, which is less than {amount}"),
+        };
+        // Initialize the recipient.
+        let recipient = Address::<N>::from_str(recipient)?;
+
+        // Construct the program ID and function name.
+        let (program_id, function_name) = (self.program_id().to_string(), "transfer_private");
+        // Construct the inputs.
+        let inputs = vec![
+            Value::Record(record),
+            Value::from(Literal::Address(recipient)),
+            Value::from(Literal::U64(U64::new(amount))),
+        ];
+        // Construct the input types.
+        let input_types = vec![
+            ValueType::Record(self.record_name),
+            ValueType::from_str("address.private")?,
+            ValueType::from_str("u64.private")?,
+        ];
+
+        // Construct the request.
+        let request = request(
+            &private_key,
+            &program_id,
+            function_name,
+            inputs,
+            input_types,
+            rng,
+        )?;
+
+        // Construct the output registers.
+        let output_registers = self.output_registers(function_name)?;
+        // Construct the outputs, a record for the recipient followed by the change for the sender.
+        let mut outputs = Vec::with_capacity(2);
+        for (owner, amount, register) in [
+            (recipient, amount, &output_registers[0]),
+            (sender, change, &output_registers[1]),
+        ] {
+            let register = match register {
+                Some(register) => register,
+                None => bail!("'{program_id}/{function_name}' must output registers"),
+            };
+            outputs.push(Value::Record(Record::<_, Plaintext<N>>::from_plaintext(
+                Owner::Private(Plaintext::from(Literal::Address(owner))),
+                IndexMap::from([(
+                    Identifier::from_str("amount")?,
+                    Entry::Private(Plaintext::from(Literal::U64(U64::new(amount)))),
+                )]),
+                {
+                    // Prepare the index as a field element.
+                    let index = Field::from_u64(register.locator());
+                    // Compute the randomizer as `HashToScalar(tvk || index)`.
+                    let randomizer = N::hash_to_scalar_psd2(&[*request.tvk(), index])?;
+                    // Compute the nonce from the randomizer.
+                    N::g_scalar_multiply(&randomizer)
+                },
+            )?));
+        }
+        // Construct the output types.
+        let output_types = vec![
+            ValueType::Record(self.record_name),
+            ValueType::Record(self.record_name),
+        ];
+
+        // Construct the authorization.
+        let authorization = authorize(request, outputs, output_types, output_registers)?;
+        // Get the execution ID.
+        let execution_id = authorization.to_execution_id()?;
+        // Authorize the fee.
+        let fee_authorization = authorize_public_fee(
+            &private_key,
+            TOKEN_TRANSFER_PRIVATE_FEE_IN_MICROCREDITS,
+            priority_fee_in_microcredits,
+            execution_id,
+            rng,
+        )?;
+
+        // Return the authorizations.
+        Ok((authorization, fee_authorization))
+    }
+
+    // Returns the output registers of a function, as declared in the program.
+    fn output_registers(&self, function_name: &str) -> Result<Vec<Option<Register<N>>>> {
+        let function = self
+            .program
+            .get_function(&Identifier::from_str(function_name)?)?;
+        function
+            .outputs()
+            .iter()
+            .map(|output| match output.operand() {
+                Operand::Register(register) => Ok(Some(register.clone())),
+                operand => bail!("Unsupported output operand '{operand}' in '{function_name}'"),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::construct_next_block;
+    use snarkvm::ledger::store::ConsensusStore;
+    use snarkvm::prelude::store::helpers::memory::ConsensusMemory;
+    use snarkvm::prelude::store::ConsensusStorage;
+    use snarkvm::prelude::{Testnet3, ViewKey, VM};
+    use snarkvm::utilities::TestRng;
+
+    type CurrentNetwork = Testnet3;
+
+    // The source of the sample token program.
+    const TOKEN_PROGRAM: &str = include_str!("../programs/token.aleo");
+
+    #[test]
+    fn test_token_program_signatures() {
+        // The sample program has the standard signatures.
+        assert!(TokenProgram::<CurrentNetwork>::from_source(TOKEN_PROGRAM).is_ok());
+
+        // A `transfer_public` that takes its inputs in a different order is rejected.
+        let source = TOKEN_PROGRAM.replace(
+            "function transfer_public:\n    input r0 as address.public;\n    input r1 as u64.public;",
+            "function transfer_public:\n    input r0 as u64.public;\n    input r1 as address.public;",
+        );
+        assert!(TokenProgram::<CurrentNetwork>::from_source(&source).is_err());
+
+        // A `transfer_public` that passes the caller and the recipient in a different order is rejected.
+        let source = TOKEN_PROGRAM.replace(
+            "async transfer_public self.caller r0 r1 into r2;",
+            "async transfer_public r0 self.caller r1 into r2;",
+        );
+        assert!(TokenProgram::<CurrentNetwork>::from_source(&source).is_err());
+
+        // A `transfer_private` that outputs a single record is rejected.
+        let source = TOKEN_PROGRAM.replace("    output r5 as token.record;\n", "");
+        assert!(TokenProgram::<CurrentNetwork>::from_source(&source).is_err());
+
+        // A `transfer_private` that outputs the change first is rejected.
+        let source = TOKEN_PROGRAM.replace(
+            "    output r4 as token.record;\n    output r5 as token.record;",
+            "    output r5 as token.record;\n    output r4 as token.record;",
+        );
+        assert!(TokenProgram::<CurrentNetwork>::from_source(&source).is_err());
+
+        // A `transfer_private` that gives the change to the recipient is rejected.
+        let source = TOKEN_PROGRAM.replace(
+            "cast r0.owner r3 into r5 as token.record;",
+            "cast r1 r3 into r5 as token.record;",
+        );
+        assert!(TokenProgram::<CurrentNetwork>::from_source(&source).is_err());
+
+        // A program without the transfer functions is rejected.
+        let source = "program empty.aleo;\n\nfunction main:\n    input r0 as u64.public;\n    output r0 as u64.public;\n";
+        assert!(TokenProgram::<CurrentNetwork>::from_source(source).is_err());
+    }
+
+    // This tests that `TokenProgram::authorize_transfer_public` produces a valid authorization, which can be executed and accepted by the VM.
+    // The test is split into the following steps:
+    //   1. Initialize a VM, fund the `sender`, and deploy the token program.
+    //   2. Mint public tokens to the `sender`.
+    //   3. Authorize a `transfer_public` from the `sender` to the `recipient`.
+    //   4. Execute the authorization and check the balances.
+    #[test]
+    fn test_authorize_token_transfer_public() {
+        // Initialize an RNG.
+        let rng = &mut TestRng::default();
+        // Initialize the VM and the token program.
+        let (vm, sender_private_key, token) = initialize_token_program(rng);
+        let sender_address = Address::try_from(&sender_private_key).unwrap();
+        // Initialize a private key for the recipient.
+        let recipient_private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        let recipient_address = Address::try_from(&recipient_private_key).unwrap();
+
+        // Mint public tokens to the sender.
+        let inputs = vec![
+            Value::<CurrentNetwork>::from(Literal::Address(sender_address)),
+            Value::from(Literal::U64(U64::new(1000))),
+        ];
+        let transaction = vm
+            .execute(
+                &sender_private_key,
+                (token.program_id().to_string().as_str(), "mint_public"),
+                inputs.iter(),
+                None,
+                0u64,
+                None,
+                rng,
+            )
+            .unwrap();
+        let next_block =
+            construct_next_block(&vm, &sender_private_key, &[transaction], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+
+        // Initialize an authorization.
+        let (authorization, fee_authorization) = token
+            .authorize_transfer_public(
+                &sender_private_key.to_string(),
+                &recipient_address.to_string(),
+                100,
+                10,
+                rng,
+            )
+            .unwrap();
+
+        // Execute the authorization, producing a transaction.
+        let transaction = vm
+            .execute_authorization(authorization, Some(fee_authorization), None, rng)
+            .unwrap();
+
+        // Construct the next block.
+        let next_block =
+            construct_next_block(&vm, &sender_private_key, &[transaction], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+
+        // Check the balances.
+        assert_eq!(token_balance(&vm, &token, sender_address), Some(900));
+        assert_eq!(token_balance(&vm, &token, recipient_address), Some(100));
+    }
+
+    // This tests that `TokenProgram::authorize_transfer_private` produces a valid authorization, which can be executed and accepted by the VM.
+    // The test is split into the following steps:
+    //   1. Initialize a VM, fund the `sender`, and deploy the token program.
+    //   2. Mint a private token record to the `sender`.
+    //   3. Authorize a `transfer_private` of the record from the `sender` to the `recipient`.
+    //   4. Execute the authorization and check the records received by both parties.
+    #[test]
+    fn test_authorize_token_transfer_private() {
+        // Initialize an RNG.
+        let rng = &mut TestRng::default();
+        // Initialize the VM and the token program.
+        let (vm, sender_private_key, token) = initialize_token_program(rng);
+        let sender_address = Address::try_from(&sender_private_key).unwrap();
+        let sender_view_key = ViewKey::try_from(&sender_private_key).unwrap();
+        // Initialize a private key for the recipient.
+        let recipient_private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        let recipient_address = Address::try_from(&recipient_private_key).unwrap();
+        let recipient_view_key = ViewKey::try_from(&recipient_private_key).unwrap();
+
+        // Mint a private token record to the sender.
+        let inputs = vec![
+            Value::<CurrentNetwork>::from(Literal::Address(sender_address)),
+            Value::from(Literal::U64(U64::new(1000))),
+        ];
+        let transaction = vm
+            .execute(
+                &sender_private_key,
+                (token.program_id().to_string().as_str(), "mint_private"),
+                inputs.iter(),
+                None,
+                0u64,
+                None,
+                rng,
+            )
+            .unwrap();
+        let next_block =
+            construct_next_block(&vm, &sender_private_key, &[transaction], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+
+        // Get the minted record.
+        let (_, record) = next_block.records().next().unwrap();
+        let record = record.decrypt(&sender_view_key).unwrap();
+
+        // Initialize an authorization.
+        let (authorization, fee_authorization) = token
+            .authorize_transfer_private(
+                &sender_private_key.to_string(),
+                &record.to_string(),
+                &recipient_address.to_string(),
+                100,
+                10,
+                rng,
+            )
+            .unwrap();
+
+        // Execute the authorization, producing a transaction.
+        let transaction = vm
+            .execute_authorization(authorization, Some(fee_authorization), None, rng)
+            .unwrap();
+
+        // Construct the next block.
+        let next_block =
+            construct_next_block(&vm, &sender_private_key, &[transaction], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+
+        // Check the records received by the recipient and the sender.
+        let amounts = |view_key: &ViewKey<CurrentNetwork>| {
+            next_block
+                .records()
+                .filter_map(|(_, record)| record.decrypt(view_key).ok())
+                .map(
+                    |record| match record.data().get(&Identifier::from_str("amount").unwrap()) {
+                        Some(Entry::Private(Plaintext::Literal(Literal::U64(amount), _))) => {
+                            **amount
+                        }
+                        _ => panic!("Invalid amount"),
+                    },
+                )
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(amounts(&recipient_view_key), vec![100]);
+        assert_eq!(amounts(&sender_view_key), vec![900]);
+
+        // A record with too few tokens cannot be spent.
+        assert!(token
+            .authorize_transfer_private(
+                &sender_private_key.to_string(),
+                &record.to_string(),
+                &recipient_address.to_string(),
+                1001,
+                10,
+                rng,
+            )
+            .is_err());
+    }
+
+    // A helper function to initialize a VM with a publicly funded `sender` and a deployed token program.
+    fn initialize_token_program(
+        rng: &mut TestRng,
+    ) -> (
+        VM<CurrentNetwork, ConsensusMemory<CurrentNetwork>>,
+        PrivateKey<CurrentNetwork>,
+        TokenProgram<CurrentNetwork>,
+    ) {
+        // Initialize a VM.
+        let vm = VM::<CurrentNetwork, ConsensusMemory<CurrentNetwork>>::from(
+            ConsensusStore::open(None).unwrap(),
+        )
+        .unwrap();
+        // Initialize the genesis private key.
+        let genesis_private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        // Initialize a private key for the sender.
+        let sender_private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        let sender_address = Address::try_from(&sender_private_key).unwrap();
+
+        // Create the genesis block.
+        let genesis_block = vm.genesis_beacon(&genesis_private_key, rng).unwrap();
+        // Add the genesis block to the VM.
+        vm.add_next_block(&genesis_block).unwrap();
+
+        // Get two valid records, the first is transferred to the sender, the second is used for the fee.
+        let mut records = genesis_block.records();
+        let (_, record) = records.next().unwrap();
+        let first_record = record
+            .decrypt(&ViewKey::try_from(&genesis_private_key).unwrap())
+            .unwrap();
+        let (_, record) = records.next().unwrap();
+        let second_record = record
+            .decrypt(&ViewKey::try_from(&genesis_private_key).unwrap())
+            .unwrap();
+        let record_microcredits = match first_record
+            .data()
+            .get(&Identifier::from_str("microcredits").unwrap())
+            .unwrap()
+        {
+            Entry::Private(Plaintext::Literal(Literal::U64(amount), _)) => *amount,
+            _ => panic!("Invalid amount"),
+        };
+
+        // Transfer the first record to the sender, publicly.
+        let inputs = vec![
+            Value::Record(first_record),
+            Value::from(Literal::Address(sender_address)),
+            Value::from(Literal::U64(U64::new(*record_microcredits))),
+        ];
+        let transaction = vm
+            .execute(
+                &genesis_private_key,
+                ("credits.aleo", "transfer_private_to_public"),
+                inputs.iter(),
+                Some(second_record),
+                0u64,
+                None,
+                rng,
+            )
+            .unwrap();
+        let next_block =
+            construct_next_block(&vm, &genesis_private_key, &[transaction], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+
+        // Deploy the token program.
+        let token = TokenProgram::<CurrentNetwork>::from_source(TOKEN_PROGRAM).unwrap();
+        let transaction = vm
+            .deploy(&sender_private_key, &token.program, None, 0u64, None, rng)
+            .unwrap();
+        let next_block =
+            construct_next_block(&vm, &sender_private_key, &[transaction], rng).unwrap();
+        vm.add_next_block(&next_block).unwrap();
+
+        (vm, sender_private_key, token)
+    }
+
+    // A helper function to get the public token balance of an address.
+    fn token_balance<C: ConsensusStorage<CurrentNetwork>>(
+        vm: &VM<CurrentNetwork, C>,
+        token: &TokenProgram<CurrentNetwork>,
+        address: Address<CurrentNetwork>,
+    ) -> Option<u64> {
+        let balance = vm
+            .finalize_store()
+            .get_value_speculative(
+                *token.program_id(),
+                Identifier::from_str("account").unwrap(),
+                &Plaintext::from(Literal::Address(address)),
+            )
+            .unwrap();
+        match balance {
+            Some(Value::Plaintext(Plaintext::Literal(Literal::U64(amount), _))) => Some(*amount),
+            None => None,
+            _ => panic!("Invalid balance"),
+        }
+    }
+}
@@ -7,7 +7,7 @@ use snarkvm::prelude::{Address, Literal, PrivateKey, Process, Value, U64};
 use std::str::FromStr;
 
 use authorize_service::{
-    authorize_transfer_public, private_key_from_seed, AuthorizeRequest, CurrentNetwork,
+    authorize_transfer_public, private_key_from_seed, AuthorizeRequest, CurrentNetwork, Signer,
 };
 
 fn bench_private_key_from_seed(c: &mut Criterion) {
@@ -18,10 +18,10 @@ fn bench_private_key_from_seed(c: &mut Criterion) {
 
 fn bench_authorize_transfer_public(c: &mut Criterion) {
     let request = AuthorizeRequest {
-        private_key: PrivateKey::from_str(
-            "APrivateKey1zkpCE9rCw9SixY82xaDrW2Hwxc2f3VjeuR2oZHR81zcuUDV",
-        )
-        .unwrap(),
+        signer: Signer::PrivateKey(
+            PrivateKey::from_str("APrivateKey1zkpCE9rCw9SixY82xaDrW2Hwxc2f3VjeuR2oZHR81zcuUDV")
+                .unwrap(),
+        ),
         recipient: Address::from_str(
             "aleo1zcsyu7wfrdp4n6gq752p3np45sat9d6zun2uhjer2h4skccsgsgq7ndrnj",
         )
@@ -0,0 +1,545 @@
+use super::*;
+
+use anyhow::{ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snarkvm::prelude::Address;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The previous hash of the first entry in an audit log.
+pub const GENESIS_AUDIT_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// What a caller asked the service to sign. It never holds a secret, such as the private key or a record.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Intent {
+    /// The address of the signer.
+    pub signer: String,
+    pub program: String,
+    pub function: String,
+    /// The address that receives the amount, or that the function acts on, if any.
+    pub recipient: Option<String>,
+    pub amount_in_microcredits: Option<u64>,
+    pub base_fee_in_microcredits: u64,
+    pub priority_fee_in_microcredits: u64,
+}
+
+impl Intent {
+    /// Returns the intent of a request, whose signer must be resolved to its private key.
+    pub fn of<R: CreditsRequest<CurrentNetwork>>(request: &R) -> Result<Self> {
+        Ok(Self {
+            signer: Address::try_from(request.private_key()?)?.to_string(),
+            program: "credits.aleo".to_string(),
+            function: R::FUNCTION_NAME.to_string(),
+            recipient: request.recipient().map(|address| address.to_string()),
+            amount_in_microcredits: request.amount_in_microcredits(),
+            base_fee_in_microcredits: R::BASE_FEE_IN_MICROCREDITS,
+            priority_fee_in_microcredits: request.priority_fee_in_microcredits(),
+        })
+    }
+
+    /// Returns the amount that leaves the signer, in microcredits, without the fees.
+    pub fn spent_amount_in_microcredits(&self) -> u64 {
+        if self.program == "credits.aleo"
+            && UNSPENT_AMOUNT_FUNCTIONS.contains(&self.function.as_str())
+        {
+            return 0;
+        }
+        self.amount_in_microcredits.unwrap_or_default()
+    }
+}
+
+/// An entry in the audit log, which is chained to the entry before it by its hash.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// The time of the authorization, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The caller that requested the authorization.
+    pub caller: String,
+    #[serde(flatten)]
+    pub intent: Intent,
+    /// The execution ID of the function authorization, which the fee is bound to.
+    pub execution_id: String,
+    /// The hash of the entry before this one, or `GENESIS_AUDIT_HASH` for the first entry.
+    pub previous_hash: String,
+    /// The hex-encoded SHA-256 of the entry, with an empty hash.
+    pub hash: String,
+}
+
+impl AuditEntry {
+    /// Returns the hash of the entry, over every field but the hash itself.
+    pub fn compute_hash(&self) -> Result<String> {
+        let unhashed = Self {
+            hash: String::new(),
+            ..self.clone()
+        };
+        Ok(hex::encode(Sha256::digest(serde_json::to_vec(&unhashed)?)))
+    }
+}
+
+/// Selects entries of an audit log. Unset fields match every entry.
+#[derive(Clone, Debug, Default)]
+pub struct AuditFilter {
+    pub caller: Option<String>,
+    pub signer: Option<String>,
+    pub function: Option<String>,
+    pub recipient: Option<String>,
+    pub execution_id: Option<String>,
+    /// The earliest timestamp, inclusive.
+    pub since: Option<u64>,
+    /// The latest timestamp, inclusive.
+    pub until: Option<u64>,
+}
+
+impl AuditFilter {
+    /// Returns `true` if the entry matches every set field.
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        let equals = |filter: &Option<String>, value: &str| {
+            filter.as_deref().is_none_or(|filter| filter == value)
+        };
+        equals(&self.caller, &entry.caller)
+            && equals(&self.signer, &entry.intent.signer)
+            && equals(&self.function, &entry.intent.function)
+            && equals(
+                &self.recipient,
+                entry.intent.recipient.as_deref().unwrap_or_default(),
+            )
+            && equals(&self.execution_id, &entry.execution_id)
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+    }
+}
+
+// An open audit log, and its head.
+struct OpenAuditLog {
+    file: File,
+    head: AuditSummary,
+    head_path: PathBuf,
+}
+
+impl OpenAuditLog {
+    // Appends an entry to the log, and then writes the head of the log.
+    fn append(
+        &mut self,
+        caller: String,
+        intent: Intent,
+        execution_id: String,
+    ) -> Result<AuditEntry, ServiceError> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|error| ServiceError::Internal(error.to_string()))?
+            .as_secs();
+        let mut entry = AuditEntry {
+            timestamp,
+            caller,
+            intent,
+            execution_id,
+            previous_hash: self.head.last_hash.clone(),
+            hash: String::new(),
+        };
+        let length = self
+            .file
+            .metadata()
+            .map_err(|error| ServiceError::Internal(error.to_string()))?
+            .len();
+        let write = |file: &mut File, entry: &mut AuditEntry| -> Result<()> {
+            entry.hash = entry.compute_hash()?;
+            let mut line = serde_json::to_vec(entry)?;
+            line.push(b'\n');
+            file.write_all(&line)?;
+            file.sync_data()?;
+            Ok(())
+        };
+        if let Err(error) = write(&mut self.file, &mut entry) {
+            // Drop a partial line, so that the next entry extends the chain.
+            let _ = self.file.set_len(length);
+            return Err(ServiceError::Internal(format!(
+                "Failed to write the audit log: {error}"
+            )));
+        }
+        self.head = AuditSummary {
+            entries: self.head.entries + 1,
+            last_hash: entry.hash.clone(),
+        };
+        // The entry is in the log, so a head that fails to write is written again with the next entry.
+        write_audit_head(&self.head_path, &self.head).map_err(|error| {
+            ServiceError::Internal(format!("Failed to write the audit log head: {error:#}"))
+        })?;
+        Ok(entry)
+    }
+}
+
+/// An append-only log of every authorization that the service signs.
+/// Each entry holds the hash of the entry before it, so that an edit, insertion or deletion breaks the chain.
+/// The number of entries and the hash of the last one, its head, are written to a file beside the log after every entry, so that
+/// removing entries from the end of the log is caught too. The head is also logged when the log is opened, so that it can be kept
+/// where the host of the log cannot change it, and passed to `verify_audit_log`.
+pub struct AuditLog(Option<Arc<Mutex<OpenAuditLog>>>);
+
+impl AuditLog {
+    /// Returns an audit log that is not configured, which records nothing.
+    pub fn disabled() -> Self {
+        Self(None)
+    }
+
+    /// Opens the audit log at the given path, or creates it if it does not exist.
+    /// Returns an error if the hash chain of the existing entries is broken, or if it does not match the head file.
+    pub fn open(path: &Path) -> Result<Self> {
+        let head = match path.exists() {
+            true => verify_audit_log(path, None)?,
+            false => AuditSummary {
+                entries: 0,
+                last_hash: GENESIS_AUDIT_HASH.to_string(),
+            },
+        };
+        let mut options = OpenOptions::new();
+        options.append(true).create(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options
+            .open(path)
+            .with_context(|| format!("Failed to open '{}'", path.display()))?;
+        // The log may be ahead of its head, if the service stopped between writing the two.
+        let head_path = audit_head_path(path);
+        write_audit_head(&head_path, &head)?;
+        log::info!(
+            "The audit log has {} entries, the last hash is {}",
+            head.entries,
+            head.last_hash
+        );
+        Ok(Self(Some(Arc::new(Mutex::new(OpenAuditLog {
+            file,
+            head,
+            head_path,
+        })))))
+    }
+
+    /// Appends an entry for an authorization, and returns it.
+    /// The authorization must not be returned to the caller unless this succeeds.
+    /// The entry is written and synced to disk on a blocking thread, so that it does not hold up the runtime.
+    pub async fn record(
+        &self,
+        caller: &Caller,
+        intent: Intent,
+        execution_id: String,
+    ) -> Result<Option<AuditEntry>, ServiceError> {
+        let log = match &self.0 {
+            Some(log) => log.clone(),
+            None => return Ok(None),
+        };
+        let caller = caller.to_string();
+        tokio::task::spawn_blocking(move || {
+            let mut log = log
+                .lock()
+                .map_err(|_| ServiceError::Internal("The audit log is poisoned".to_string()))?;
+            log.append(caller, intent, execution_id).map(Some)
+        })
+        .await
+        .map_err(|error| ServiceError::Internal(error.to_string()))?
+    }
+}
+
+/// The result of verifying an audit log, which is also its head.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditSummary {
+    /// The number of entries in the log.
+    pub entries: usize,
+    /// The hash of the last entry, or `GENESIS_AUDIT_HASH` if the log is empty.
+    pub last_hash: String,
+}
+
+/// Returns the path of the head file of the audit log at the given path.
+pub fn audit_head_path(path: &Path) -> PathBuf {
+    let mut head_path = path.as_os_str().to_owned();
+    head_path.push(".head");
+    PathBuf::from(head_path)
+}
+
+// Writes the head of an audit log, replacing the previous head in one step.
+fn write_audit_head(head_path: &Path, head: &AuditSummary) -> Result<()> {
+    let mut temporary_path = head_path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    let temporary_path = PathBuf::from(temporary_path);
+    let mut file = File::create(&temporary_path)
+        .with_context(|| format!("Failed to create '{}'", temporary_path.display()))?;
+    file.write_all(&serde_json::to_vec(head)?)?;
+    file.sync_data()?;
+    std::fs::rename(&temporary_path, head_path)
+        .with_context(|| format!("Failed to write '{}'", head_path.display()))?;
+    Ok(())
+}
+
+/// Verifies the hash chain of the audit log at the given path.
+/// The chain must match the head file beside the log, if there is one, and the given head, which was kept elsewhere, if any.
+/// A log may hold more entries than a head, which was written before them, but never fewer.
+/// Returns an error that names the first line that does not verify.
+pub fn verify_audit_log(path: &Path, head: Option<&AuditSummary>) -> Result<AuditSummary> {
+    let mut heads = Vec::new();
+    let head_path = audit_head_path(path);
+    if head_path.exists() {
+        let contents = std::fs::read(&head_path)
+            .with_context(|| format!("Failed to read '{}'", head_path.display()))?;
+        let head_file = serde_json::from_slice::<AuditSummary>(&contents)
+            .with_context(|| format!("'{}' is not a valid head", head_path.display()))?;
+        heads.push(("the head file", head_file));
+    }
+    if let Some(head) = head {
+        heads.push(("the given head", head.clone()));
+    }
+    let check_heads = |summary: &AuditSummary| -> Result<()> {
+        for (name, head) in &heads {
+            ensure!(
+                head.entries != summary.entries || head.last_hash == summary.last_hash,
+                "Entry {}: the hash does not match {name}",
+                summary.entries
+            );
+        }
+        Ok(())
+    };
+
+    let mut summary = AuditSummary {
+        entries: 0,
+        last_hash: GENESIS_AUDIT_HASH.to_string(),
+    };
+    check_heads(&summary)?;
+    for (index, entry) in read_audit_log(path)?.enumerate() {
+        let line = index + 1;
+        let entry = entry?;
+        ensure!(
+            entry.previous_hash == summary.last_hash,
+            "Line {line}: the previous hash does not match the entry before it"
+        );
+        ensure!(
+            entry.hash == entry.compute_hash()?,
+            "Line {line}: the hash does not match the entry"
+        );
+        summary.entries += 1;
+        summary.last_hash = entry.hash;
+        check_heads(&summary)?;
+    }
+    for (name, head) in &heads {
+        ensure!(
+            summary.entries >= head.entries,
+            "The log has {} entries, but {name} has {}, so entries were removed from its end",
+            summary.entries,
+            head.entries
+        );
+    }
+    Ok(summary)
+}
+
+/// Returns the entries of the audit log at the given path that match the filter.
+pub fn search_audit_log(path: &Path, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+    let mut entries = Vec::new();
+    for entry in read_audit_log(path)? {
+        let entry = entry?;
+        if filter.matches(&entry) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+// Returns an iterator over the entries of the audit log at the given path.
+fn read_audit_log(path: &Path) -> Result<impl Iterator<Item = Result<AuditEntry>>> {
+    let file = File::open(path).with_context(|| format!("Failed to open '{}'", path.display()))?;
+    Ok(BufReader::new(file)
+        .lines()
+        .enumerate()
+        .map(|(index, contents)| {
+            let line = index + 1;
+            let contents = contents.with_context(|| format!("Line {line}: failed to read"))?;
+            serde_json::from_str(&contents).with_context(|| format!("Line {line}: invalid entry"))
+        }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use snarkvm::prelude::U64;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    const PRIVATE_KEY: &str = "APrivateKey1zkpCE9rCw9SixY82xaDrW2Hwxc2f3VjeuR2oZHR81zcuUDV";
+    const RECIPIENT: &str = "aleo1zcsyu7wfrdp4n6gq752p3np45sat9d6zun2uhjer2h4skccsgsgq7ndrnj";
+
+    // Returns a path for an audit log that does not exist yet.
+    fn audit_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "authorize-service-audit-{name}-{}.jsonl",
+            rand::random::<u64>()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn intent(function: &str, amount_in_microcredits: u64) -> Intent {
+        Intent {
+            signer: "aleo1signer".to_string(),
+            program: "credits.aleo".to_string(),
+            function: function.to_string(),
+            recipient: Some(RECIPIENT.to_string()),
+            amount_in_microcredits: Some(amount_in_microcredits),
+            base_fee_in_microcredits: 1,
+            priority_fee_in_microcredits: 2,
+        }
+    }
+
+    #[test]
+    fn test_intent_holds_no_secrets() {
+        let request = AuthorizeRequest::<CurrentNetwork> {
+            signer: Signer::PrivateKey(PrivateKey::from_str(PRIVATE_KEY).unwrap()),
+            recipient: Address::from_str(RECIPIENT).unwrap(),
+            amount_in_microcredits: U64::new(1000),
+            priority_fee_in_microcredits: U64::new(10),
+        };
+        let intent = Intent::of(&request).unwrap();
+        assert_eq!(intent.function, "transfer_public");
+        assert_eq!(intent.recipient.as_deref(), Some(RECIPIENT));
+        assert_eq!(intent.amount_in_microcredits, Some(1000));
+        assert_eq!(intent.priority_fee_in_microcredits, 10);
+        assert!(!serde_json::to_string(&intent)
+            .unwrap()
+            .contains(PRIVATE_KEY));
+    }
+
+    #[tokio::test]
+    async fn test_hash_chain() {
+        let path = audit_path("chain");
+        let caller = Caller::Hmac("wallet".to_string());
+
+        // Entries are chained, across reopening the log.
+        let log = AuditLog::open(&path).unwrap();
+        let first = log
+            .record(&caller, intent("transfer_public", 1), "1field".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.previous_hash, GENESIS_AUDIT_HASH);
+        drop(log);
+        let log = AuditLog::open(&path).unwrap();
+        let second = log
+            .record(&caller, intent("split", 2), "2field".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.previous_hash, first.hash);
+        let summary = verify_audit_log(&path, None).unwrap();
+        assert_eq!(summary.entries, 2);
+        assert_eq!(summary.last_hash, second.hash);
+
+        // The log is searched by any field.
+        let filter = AuditFilter {
+            function: Some("split".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(search_audit_log(&path, &filter).unwrap(), [second]);
+        let filter = AuditFilter {
+            caller: Some("hmac:other".to_string()),
+            ..Default::default()
+        };
+        assert!(search_audit_log(&path, &filter).unwrap().is_empty());
+
+        // Editing an entry breaks the chain, and the log no longer opens.
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(
+            &path,
+            contents.replacen(
+                "\"amount_in_microcredits\":1",
+                "\"amount_in_microcredits\":9",
+                1,
+            ),
+        )
+        .unwrap();
+        let error = verify_audit_log(&path, None).unwrap_err().to_string();
+        assert!(error.starts_with("Line 1"), "{error}");
+        assert!(AuditLog::open(&path).is_err());
+
+        // Deleting an entry breaks the chain too.
+        let second_line = contents.lines().nth(1).unwrap();
+        std::fs::write(&path, format!("{second_line}\n")).unwrap();
+        let error = verify_audit_log(&path, None).unwrap_err().to_string();
+        assert!(error.contains("previous hash"), "{error}");
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(audit_head_path(&path)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_removed_entries_are_caught_by_the_head() {
+        let path = audit_path("head");
+        let caller = Caller::Anonymous;
+        let log = AuditLog::open(&path).unwrap();
+        let mut entries = Vec::new();
+        for amount in 1..=3 {
+            let entry = log
+                .record(
+                    &caller,
+                    intent("transfer_public", amount),
+                    format!("{amount}field"),
+                )
+                .await
+                .unwrap()
+                .unwrap();
+            entries.push(entry);
+        }
+        drop(log);
+        let contents = std::fs::read_to_string(&path).unwrap();
+
+        // The head file follows the log.
+        let head = verify_audit_log(&path, None).unwrap();
+        assert_eq!(head.entries, 3);
+        assert_eq!(head.last_hash, entries[2].hash);
+        let head_file = std::fs::read(audit_head_path(&path)).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<AuditSummary>(&head_file).unwrap(),
+            head
+        );
+
+        // Removing the last entry leaves a valid chain, which the head file catches.
+        let first_two: String = contents
+            .lines()
+            .take(2)
+            .map(|line| format!("{line}\n"))
+            .collect();
+        std::fs::write(&path, &first_two).unwrap();
+        let error = verify_audit_log(&path, None).unwrap_err().to_string();
+        assert!(error.contains("entries were removed"), "{error}");
+        assert!(AuditLog::open(&path).is_err());
+
+        // Replacing the head file too is caught by a head that was kept elsewhere.
+        std::fs::remove_file(audit_head_path(&path)).unwrap();
+        assert_eq!(verify_audit_log(&path, None).unwrap().entries, 2);
+        let error = verify_audit_log(&path, Some(&head))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("the given head"), "{error}");
+
+        // An older head is a prefix of the log, and a different entry at its position is caught.
+        let older = AuditSummary {
+            entries: 1,
+            last_hash: entries[0].hash.clone(),
+        };
+        assert_eq!(verify_audit_log(&path, Some(&older)).unwrap().entries, 2);
+        let forged = AuditSummary {
+            entries: 1,
+            last_hash: entries[1].hash.clone(),
+        };
+        let error = verify_audit_log(&path, Some(&forged))
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("Entry 1"), "{error}");
+
+        // A log that is ahead of its head, as after a crash between writing the two, opens and rewrites the head.
+        std::fs::write(&path, &contents).unwrap();
+        write_audit_head(&audit_head_path(&path), &older).unwrap();
+        AuditLog::open(&path).unwrap();
+        assert_eq!(verify_audit_log(&path, Some(&head)).unwrap(), head);
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(audit_head_path(&path)).unwrap();
+    }
+}
@@ -0,0 +1,1014 @@
+use super::*;
+
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snarkvm::prelude::{Address, Signature};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use subtle::ConstantTimeEq;
+use warp::http::{HeaderMap, Method};
+use warp::hyper::body::Bytes;
+use warp::path::FullPath;
+use warp::{Filter, Rejection};
+
+/// The header that carries a static API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+/// The header that carries the ID of the HMAC secret.
+pub const AUTH_KEY_ID_HEADER: &str = "x-auth-key-id";
+/// The header that carries the time at which the request was signed, in seconds since the Unix epoch.
+pub const AUTH_TIMESTAMP_HEADER: &str = "x-auth-timestamp";
+/// The header that carries the hex-encoded HMAC-SHA256 signature of the request.
+pub const AUTH_SIGNATURE_HEADER: &str = "x-auth-signature";
+/// The header that carries the Aleo address of the caller.
+pub const ALEO_ADDRESS_HEADER: &str = "x-aleo-address";
+/// The header that carries the challenge nonce that was signed.
+pub const ALEO_NONCE_HEADER: &str = "x-aleo-nonce";
+/// The header that carries the Aleo signature of the request.
+pub const ALEO_SIGNATURE_HEADER: &str = "x-aleo-signature";
+
+/// The name of the policy of the authorize routes, which applies to the credits functions that have no policy of their own.
+pub const AUTHORIZE_ROUTE: &str = "authorize";
+
+// The maximum number of challenges that may be outstanding at once.
+// When the store is full, the oldest challenge is evicted.
+const MAX_OUTSTANDING_CHALLENGES: usize = 100_000;
+// The maximum number of challenges that may be outstanding for each client at once.
+const MAX_CHALLENGES_PER_CLIENT: usize = 16;
+
+/// The authentication configuration, loaded from a TOML file.
+///
+/// ```toml
+/// max_skew_secs = 300
+///
+/// [api_keys]
+/// operator = "a-long-random-string"
+///
+/// [hmac_secrets]
+/// wallet = "hex-encoded-secret"
+///
+/// [routes.keygen]
+/// methods = ["api_key"]
+///
+/// [routes.authorize]
+/// methods = ["hmac", "aleo_signature"]
+/// keys = ["wallet", "aleo1..."]
+///
+/// [routes.bond_public]
+/// methods = ["hmac"]
+/// keys = ["wallet"]
+/// ```
+///
+/// The authorize routes are named by their credits function, such as `transfer_public`.
+/// A function that is not listed in `routes` falls back to the policy of `authorize`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct AuthConfig {
+    /// The static API keys, by key ID.
+    #[serde(default)]
+    pub api_keys: HashMap<String, String>,
+    /// The hex-encoded HMAC secrets, by key ID.
+    #[serde(default)]
+    pub hmac_secrets: HashMap<String, String>,
+    /// The maximum difference between the timestamp of a signed request and the local clock, in seconds.
+    #[serde(default = "default_max_skew_secs")]
+    pub max_skew_secs: u64,
+    /// The number of seconds for which a challenge can be signed.
+    #[serde(default = "default_challenge_ttl_secs")]
+    pub challenge_ttl_secs: u64,
+    /// The policy of routes that are not listed in `routes`.
+    /// If unset, these routes accept any configured key.
+    #[serde(default)]
+    pub default: Option<RoutePolicy>,
+    /// The policies, by route name.
+    #[serde(default)]
+    pub routes: HashMap<String, RoutePolicy>,
+}
+
+fn default_max_skew_secs() -> u64 {
+    300
+}
+
+fn default_challenge_ttl_secs() -> u64 {
+    60
+}
+
+/// The authentication policy of a route.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RoutePolicy {
+    /// The accepted authentication methods.
+    pub methods: Vec<AuthMethod>,
+    /// The key IDs or Aleo addresses that may call the route. If unset, every caller may call the route.
+    #[serde(default)]
+    pub keys: Option<Vec<String>>,
+}
+
+impl Default for RoutePolicy {
+    fn default() -> Self {
+        Self {
+            methods: vec![
+                AuthMethod::ApiKey,
+                AuthMethod::Hmac,
+                AuthMethod::AleoSignature,
+            ],
+            keys: None,
+        }
+    }
+}
+
+/// An authentication method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    /// The route is open to everyone.
+    None,
+    /// A static API key in the `X-Api-Key` header.
+    ApiKey,
+    /// An HMAC-signed request.
+    Hmac,
+    /// A request signed with an Aleo private key over a challenge nonce.
+    AleoSignature,
+}
+
+/// The authenticated caller of a route.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Caller {
+    /// Authentication is disabled, or the route is open.
+    Anonymous,
+    /// A caller with a static API key, identified by the key ID.
+    ApiKey(String),
+    /// A caller with an HMAC secret, identified by the key ID.
+    Hmac(String),
+    /// A caller with an Aleo private key, identified by its address.
+    Aleo(Address<CurrentNetwork>),
+}
+
+impl Caller {
+    /// Returns the key ID or the Aleo address of the caller, if any.
+    pub fn key_id(&self) -> Option<String> {
+        match self {
+            Self::Anonymous => None,
+            Self::ApiKey(key_id) | Self::Hmac(key_id) => Some(key_id.clone()),
+            Self::Aleo(address) => Some(address.to_string()),
+        }
+    }
+
+    // Returns the authentication method of the caller.
+    fn method(&self) -> AuthMethod {
+        match self {
+            Self::Anonymous => AuthMethod::None,
+            Self::ApiKey(_) => AuthMethod::ApiKey,
+            Self::Hmac(_) => AuthMethod::Hmac,
+            Self::Aleo(_) => AuthMethod::AleoSignature,
+        }
+    }
+}
+
+impl fmt::Display for Caller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Anonymous => write!(f, "anonymous"),
+            Self::ApiKey(key_id) => write!(f, "api_key:{key_id}"),
+            Self::Hmac(key_id) => write!(f, "hmac:{key_id}"),
+            Self::Aleo(address) => write!(f, "aleo:{address}"),
+        }
+    }
+}
+
+/// Authenticates requests against an `AuthConfig`.
+pub struct Authenticator {
+    config: Option<AuthConfig>,
+    hmac_secrets: HashMap<String, Vec<u8>>,
+    // The signatures that were already used, with the time at which they expire.
+    used_signatures: Mutex<HashMap<String, u64>>,
+    // The challenges that were issued and not yet used.
+    challenges: Mutex<Challenges>,
+}
+
+// A challenge that was issued and not yet used.
+struct IssuedChallenge {
+    client: String,
+    expires_at: u64,
+}
+
+// The challenges that were issued and not yet used.
+#[derive(Default)]
+struct Challenges {
+    // The challenges, by nonce.
+    issued: HashMap<String, IssuedChallenge>,
+    // The nonces in the order they were issued, which is the order in which they expire.
+    // Nonces that were already used are skipped when they reach the front.
+    order: VecDeque<String>,
+    // The number of outstanding challenges of each client.
+    per_client: HashMap<String, usize>,
+}
+
+impl Challenges {
+    // Removes the challenge with the given nonce, and returns the time at which it expires.
+    fn remove(&mut self, nonce: &str) -> Option<u64> {
+        let challenge = self.issued.remove(nonce)?;
+        if let Some(count) = self.per_client.get_mut(&challenge.client) {
+            *count -= 1;
+            if *count == 0 {
+                self.per_client.remove(&challenge.client);
+            }
+        }
+        Some(challenge.expires_at)
+    }
+
+    // Removes the expired challenges, and the oldest challenges while there are more than `max`.
+    fn prune(&mut self, now: u64, max: usize) {
+        while let Some(nonce) = self.order.front() {
+            match self.issued.get(nonce) {
+                Some(challenge) if challenge.expires_at >= now && self.issued.len() < max => break,
+                _ => {
+                    if let Some(nonce) = self.order.pop_front() {
+                        self.remove(&nonce);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A challenge nonce, to be signed by the caller's Aleo private key.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Challenge {
+    /// The hex-encoded nonce.
+    pub nonce: String,
+    /// The time at which the nonce expires, in seconds since the Unix epoch.
+    pub expires_at: u64,
+}
+
+impl Authenticator {
+    /// Returns an authenticator that lets every request through.
+    pub fn disabled() -> Self {
+        Self {
+            config: None,
+            hmac_secrets: HashMap::new(),
+            used_signatures: Mutex::new(HashMap::new()),
+            challenges: Mutex::new(Challenges::default()),
+        }
+    }
+
+    /// Returns an authenticator for the given configuration.
+    pub fn new(config: AuthConfig) -> Result<Self> {
+        let hmac_secrets = config
+            .hmac_secrets
+            .iter()
+            .map(|(key_id, secret)| {
+                let secret = hex::decode(secret)
+                    .with_context(|| format!("The HMAC secret '{key_id}' is not valid hex"))?;
+                Ok((key_id.clone(), secret))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            config: Some(config),
+            hmac_secrets,
+            used_signatures: Mutex::new(HashMap::new()),
+            challenges: Mutex::new(Challenges::default()),
+        })
+    }
+
+    /// Loads an authenticator from a TOML configuration file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        let config = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse '{}'", path.display()))?;
+        Self::new(config)
+    }
+
+    /// Authenticates a request to the named route.
+    /// Returns `Unauthorized` if the credentials are missing or invalid,
+    /// and `Forbidden` if the caller may not call the route.
+    pub fn authenticate(
+        &self,
+        route: &str,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+        now: u64,
+    ) -> Result<Caller, ServiceError> {
+        // If authentication is disabled, let the request through.
+        let config = match &self.config {
+            Some(config) => config,
+            None => return Ok(Caller::Anonymous),
+        };
+        // Get the policy of the route, falling back to that of `authorize` for the credits functions.
+        let policy = config
+            .routes
+            .get(route)
+            .or_else(|| match CREDITS_FUNCTIONS.contains(&route) {
+                true => config.routes.get(AUTHORIZE_ROUTE),
+                false => None,
+            })
+            .or(config.default.as_ref())
+            .cloned()
+            .unwrap_or_default();
+
+        // Authenticate the caller with the credentials in the request.
+        let caller = if let Some(api_key) = header(headers, API_KEY_HEADER)? {
+            self.authenticate_api_key(config, api_key)?
+        } else if let Some(key_id) = header(headers, AUTH_KEY_ID_HEADER)? {
+            self.authenticate_hmac(config, key_id, method, path, headers, body, now)?
+        } else if let Some(address) = header(headers, ALEO_ADDRESS_HEADER)? {
+            self.authenticate_aleo(address, method, path, headers, body, now)?
+        } else if policy.methods.contains(&AuthMethod::None) {
+            return Ok(Caller::Anonymous);
+        } else {
+            return Err(ServiceError::Unauthorized(
+                "The request is missing credentials".to_string(),
+            ));
+        };
+
+        // Check that the caller may call the route.
+        if !policy.methods.contains(&caller.method()) {
+            return Err(ServiceError::Forbidden(format!(
+                "The route '{route}' does not accept this authentication method"
+            )));
+        }
+        if let (Some(keys), Some(key_id)) = (&policy.keys, caller.key_id()) {
+            if !keys.iter().any(|key| *key == key_id) {
+                return Err(ServiceError::Forbidden(format!(
+                    "The key '{key_id}' may not call the route '{route}'"
+                )));
+            }
+        }
+        Ok(caller)
+    }
+
+    // Authenticates a static API key, comparing it to every configured key in constant time.
+    fn authenticate_api_key(
+        &self,
+        config: &AuthConfig,
+        api_key: &str,
+    ) -> Result<Caller, ServiceError> {
+        config
+            .api_keys
+            .iter()
+            .find(|(_, key)| bool::from(key.as_bytes().ct_eq(api_key.as_bytes())))
+            .map(|(key_id, _)| Caller::ApiKey(key_id.clone()))
+            .ok_or_else(|| ServiceError::Unauthorized("The API key is not valid".to_string()))
+    }
+
+    // Authenticates an HMAC-signed request, rejecting stale and replayed requests.
+    #[allow(clippy::too_many_arguments)]
+    fn authenticate_hmac(
+        &self,
+        config: &AuthConfig,
+        key_id: &str,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+        now: u64,
+    ) -> Result<Caller, ServiceError> {
+        let unauthorized = |message: &str| ServiceError::Unauthorized(message.to_string());
+
+        // Get the secret, the timestamp and the signature.
+        let secret = self
+            .hmac_secrets
+            .get(key_id)
+            .ok_or_else(|| unauthorized("The HMAC key ID is not valid"))?;
+        let timestamp = header(headers, AUTH_TIMESTAMP_HEADER)?
+            .ok_or_else(|| unauthorized("The request is missing a timestamp"))?
+            .parse::<u64>()
+            .map_err(|_| unauthorized("The timestamp is not valid"))?;
+        let signature = header(headers, AUTH_SIGNATURE_HEADER)?
+            .ok_or_else(|| unauthorized("The request is missing a signature"))?;
+        let signature =
+            hex::decode(signature).map_err(|_| unauthorized("The signature is not valid hex"))?;
+
+        // Check that the timestamp is recent.
+        if now.abs_diff(timestamp) > config.max_skew_secs {
+            return Err(unauthorized(
+                "The timestamp is too far from the server time",
+            ));
+        }
+        // Check the signature.
+        hmac_for_request(secret, method, path, timestamp, body)
+            .verify_slice(&signature)
+            .map_err(|_| unauthorized("The signature is not valid"))?;
+
+        // Check that the signature was not used before, and remember it until it expires.
+        let mut used_signatures = self
+            .used_signatures
+            .lock()
+            .map_err(|_| ServiceError::Internal("The replay cache is poisoned".to_string()))?;
+        used_signatures.retain(|_, expires_at| *expires_at >= now);
+        if used_signatures
+            .insert(hex::encode(&signature), timestamp + config.max_skew_secs)
+            .is_some()
+        {
+            return Err(unauthorized("The request was already used"));
+        }
+
+        Ok(Caller::Hmac(key_id.to_string()))
+    }
+
+    /// Issues a new challenge nonce to a client, which can be signed once before it expires.
+    /// Returns `TooManyRequests` if the client has too many outstanding challenges.
+    /// If there are too many outstanding challenges in total, the oldest one is evicted.
+    pub fn issue_challenge(&self, client: &str, now: u64) -> Result<Challenge, ServiceError> {
+        let ttl_secs = self
+            .config
+            .as_ref()
+            .map(|config| config.challenge_ttl_secs)
+            .unwrap_or_else(default_challenge_ttl_secs);
+        let mut challenges = self
+            .challenges
+            .lock()
+            .map_err(|_| ServiceError::Internal("The challenge store is poisoned".to_string()))?;
+        // Remove the expired challenges, and make room for a new one.
+        challenges.prune(now, MAX_OUTSTANDING_CHALLENGES);
+        if challenges
+            .per_client
+            .get(client)
+            .copied()
+            .unwrap_or_default()
+            >= MAX_CHALLENGES_PER_CLIENT
+        {
+            // The oldest challenge of the client expires first, after at most the TTL.
+            return Err(ServiceError::TooManyRequests(
+                "There are too many outstanding challenges for this client".to_string(),
+                ttl_secs.max(1),
+            ));
+        }
+        // Sample the nonce.
+        let nonce = hex::encode(rand::thread_rng().gen::<[u8; 32]>());
+        let expires_at = now + ttl_secs;
+        challenges.issued.insert(
+            nonce.clone(),
+            IssuedChallenge {
+                client: client.to_string(),
+                expires_at,
+            },
+        );
+        challenges.order.push_back(nonce.clone());
+        *challenges.per_client.entry(client.to_string()).or_default() += 1;
+        Ok(Challenge { nonce, expires_at })
+    }
+
+    // Authenticates a request signed with an Aleo private key over a challenge nonce.
+    // The nonce is consumed, so that the signature cannot be replayed.
+    fn authenticate_aleo(
+        &self,
+        address: &str,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+        now: u64,
+    ) -> Result<Caller, ServiceError> {
+        let unauthorized = |message: &str| ServiceError::Unauthorized(message.to_string());
+
+        // Get the address, the nonce and the signature.
+        let address = Address::<CurrentNetwork>::from_str(address)
+            .map_err(|_| unauthorized("The Aleo address is not valid"))?;
+        let nonce = header(headers, ALEO_NONCE_HEADER)?
+            .ok_or_else(|| unauthorized("The request is missing a challenge nonce"))?;
+        let signature = header(headers, ALEO_SIGNATURE_HEADER)?
+            .ok_or_else(|| unauthorized("The request is missing a signature"))?;
+        let signature = Signature::<CurrentNetwork>::from_str(signature)
+            .map_err(|_| unauthorized("The signature is not valid"))?;
+
+        // Consume the nonce before the signature is checked, so that every nonce is tried at most once.
+        let expires_at = self
+            .challenges
+            .lock()
+            .map_err(|_| ServiceError::Internal("The challenge store is poisoned".to_string()))?
+            .remove(nonce);
+        match expires_at {
+            Some(expires_at) if expires_at >= now => (),
+            Some(_) => return Err(unauthorized("The challenge has expired")),
+            None => {
+                return Err(unauthorized(
+                    "The challenge is not valid, or was already used",
+                ))
+            }
+        }
+
+        // Check the signature.
+        let message = aleo_signature_message(method, path, nonce, body);
+        if !signature.verify_bytes(&address, message.as_bytes()) {
+            return Err(unauthorized("The signature is not valid"));
+        }
+        Ok(Caller::Aleo(address))
+    }
+}
+
+/// Returns the message that is signed with an Aleo private key to authenticate a request.
+/// The message is `METHOD\nPATH\nNONCE\nHEX(SHA256(BODY))`.
+pub fn aleo_signature_message(method: &Method, path: &str, nonce: &str, body: &[u8]) -> String {
+    format!(
+        "{method}\n{path}\n{nonce}\n{}",
+        hex::encode(Sha256::digest(body))
+    )
+}
+
+/// Signs a request with an Aleo private key over a challenge nonce.
+/// This function is to be invoked by the client.
+pub fn sign_aleo_request<N: Network>(
+    private_key: &PrivateKey<N>,
+    method: &Method,
+    path: &str,
+    nonce: &str,
+    body: &[u8],
+    rng: &mut (impl Rng + rand::CryptoRng),
+) -> Result<Signature<N>> {
+    Signature::sign_bytes(
+        private_key,
+        aleo_signature_message(method, path, nonce, body).as_bytes(),
+        rng,
+    )
+}
+
+/// Returns the hex-encoded HMAC-SHA256 signature of a request.
+/// The signed message is `METHOD\nPATH\nTIMESTAMP\nHEX(SHA256(BODY))`.
+pub fn hmac_signature(
+    secret: &[u8],
+    method: &Method,
+    path: &str,
+    timestamp: u64,
+    body: &[u8],
+) -> String {
+    hex::encode(
+        hmac_for_request(secret, method, path, timestamp, body)
+            .finalize()
+            .into_bytes(),
+    )
+}
+
+// Returns the HMAC of a request, ready to be finalized or verified.
+fn hmac_for_request(
+    secret: &[u8],
+    method: &Method,
+    path: &str,
+    timestamp: u64,
+    body: &[u8],
+) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(
+        format!(
+            "{method}\n{path}\n{timestamp}\n{}",
+            hex::encode(Sha256::digest(body))
+        )
+        .as_bytes(),
+    );
+    mac
+}
+
+// Returns the value of a header, if it is present.
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<Option<&'a str>, ServiceError> {
+    headers
+        .get(name)
+        .map(|value| {
+            value.to_str().map_err(|_| {
+                ServiceError::Unauthorized(format!("The header '{name}' is not valid"))
+            })
+        })
+        .transpose()
+}
+
+// Returns the number of seconds since the Unix epoch.
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Authenticates a request to the named route, extracting the caller and the body of at most `max_body_bytes`.
+pub fn with_auth(
+    authenticator: Arc<Authenticator>,
+    route: &'static str,
+    max_body_bytes: u64,
+) -> impl Filter<Extract = (Caller, Bytes), Error = Rejection> + Clone {
+    warp::method()
+        .and(warp::path::full())
+        .and(warp::header::headers_cloned())
+        .and(limited_body(max_body_bytes))
+        .and_then(
+            move |method: Method, path: FullPath, headers: HeaderMap, body: Bytes| {
+                let authenticator = authenticator.clone();
+                async move {
+                    match authenticator.authenticate(
+                        route,
+                        &method,
+                        path.as_str(),
+                        &headers,
+                        &body,
+                        unix_now(),
+                    ) {
+                        Ok(caller) => Ok((caller, body)),
+                        Err(error) => Err(warp::reject::custom(error)),
+                    }
+                }
+            },
+        )
+        .untuple_one()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use warp::http::StatusCode;
+
+    const CONFIG: &str = r#"
+        [api_keys]
+        operator = "operator-key"
+        reader = "reader-key"
+
+        [hmac_secrets]
+        wallet = "00112233445566778899aabbccddeeff"
+
+        [routes.keygen]
+        methods = ["api_key", "hmac"]
+        keys = ["operator", "wallet"]
+    "#;
+
+    fn authenticator() -> Arc<Authenticator> {
+        Arc::new(Authenticator::new(toml::from_str(CONFIG).unwrap()).unwrap())
+    }
+
+    fn signed_request(timestamp: u64) -> warp::test::RequestBuilder {
+        let secret = hex::decode("00112233445566778899aabbccddeeff").unwrap();
+        let signature = hmac_signature(&secret, &Method::GET, "/keygen/1234", timestamp, b"");
+        warp::test::request()
+            .method("GET")
+            .path("/keygen/1234")
+            .header(AUTH_KEY_ID_HEADER, "wallet")
+            .header(AUTH_TIMESTAMP_HEADER, timestamp.to_string())
+            .header(AUTH_SIGNATURE_HEADER, signature)
+    }
+
+    #[tokio::test]
+    async fn test_missing_credentials() {
+        let route =
+            keygen_route(authenticator(), Arc::new(Limits::unlimited())).recover(handle_rejection);
+        let response = warp::test::request()
+            .method("GET")
+            .path("/keygen/1234")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "unauthorized");
+    }
+
+    #[tokio::test]
+    async fn test_api_key() {
+        let route =
+            keygen_route(authenticator(), Arc::new(Limits::unlimited())).recover(handle_rejection);
+
+        // A valid key that may call the route.
+        let response = warp::test::request()
+            .method("GET")
+            .path("/keygen/1234")
+            .header(API_KEY_HEADER, "operator-key")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // A valid key that may not call the route.
+        let response = warp::test::request()
+            .method("GET")
+            .path("/keygen/1234")
+            .header(API_KEY_HEADER, "reader-key")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "forbidden");
+
+        // An invalid key.
+        let response = warp::test::request()
+            .method("GET")
+            .path("/keygen/1234")
+            .header(API_KEY_HEADER, "operator-key-2")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_hmac() {
+        let route =
+            keygen_route(authenticator(), Arc::new(Limits::unlimited())).recover(handle_rejection);
+
+        // A valid signature.
+        let response = signed_request(unix_now()).reply(&route).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // A stale signature.
+        let response = signed_request(unix_now() - 3600).reply(&route).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // A signature over a different path.
+        let response = signed_request(unix_now())
+            .path("/keygen/5678")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_hmac_replay() {
+        let route =
+            keygen_route(authenticator(), Arc::new(Limits::unlimited())).recover(handle_rejection);
+        let timestamp = unix_now();
+
+        let response = signed_request(timestamp).reply(&route).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = signed_request(timestamp).reply(&route).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_disabled() {
+        let route = keygen_route(
+            Arc::new(Authenticator::disabled()),
+            Arc::new(Limits::unlimited()),
+        )
+        .recover(handle_rejection);
+        let response = warp::test::request()
+            .method("GET")
+            .path("/keygen/1234")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Fetches a challenge and signs a request to `/keygen/1234` with the given private key.
+    async fn aleo_signed_request(
+        route: &(impl Filter<Extract = impl warp::Reply, Error = std::convert::Infallible>
+              + Clone
+              + 'static),
+        private_key: &PrivateKey<CurrentNetwork>,
+    ) -> warp::test::RequestBuilder {
+        let response = warp::test::request()
+            .method("POST")
+            .path("/auth/challenge")
+            .reply(route)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let challenge: Challenge = serde_json::from_slice(response.body()).unwrap();
+
+        let signature = sign_aleo_request(
+            private_key,
+            &Method::GET,
+            "/keygen/1234",
+            &challenge.nonce,
+            b"",
+            &mut rand::thread_rng(),
+        )
+        .unwrap();
+        warp::test::request()
+            .method("GET")
+            .path("/keygen/1234")
+            .header(
+                ALEO_ADDRESS_HEADER,
+                Address::try_from(private_key).unwrap().to_string(),
+            )
+            .header(ALEO_NONCE_HEADER, challenge.nonce)
+            .header(ALEO_SIGNATURE_HEADER, signature.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_aleo_signature() {
+        let rng = &mut rand::thread_rng();
+        let private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        let other_private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        let address = Address::try_from(&private_key).unwrap();
+
+        // Only the first address may call the keygen route.
+        let config: AuthConfig = toml::from_str(&format!(
+            r#"
+            [routes.keygen]
+            methods = ["aleo_signature"]
+            keys = ["{address}"]
+            "#
+        ))
+        .unwrap();
+        let authenticator = Arc::new(Authenticator::new(config).unwrap());
+        let limits = Arc::new(Limits::unlimited());
+        let route = challenge_route(authenticator.clone(), limits.clone())
+            .or(keygen_route(authenticator, limits))
+            .recover(handle_rejection);
+
+        // A valid signature by an allowed address.
+        let request = aleo_signed_request(&route, &private_key).await;
+        let response = request.reply(&route).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // A valid signature by an address that may not call the route.
+        let request = aleo_signed_request(&route, &other_private_key).await;
+        let response = request.reply(&route).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // A signature that claims another address.
+        let request = aleo_signed_request(&route, &other_private_key)
+            .await
+            .header(ALEO_ADDRESS_HEADER, address.to_string());
+        let response = request.reply(&route).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_aleo_challenge_is_single_use() {
+        let rng = &mut rand::thread_rng();
+        let private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        let address = Address::try_from(&private_key).unwrap();
+        let authenticator = Authenticator::new(AuthConfig::default()).unwrap();
+
+        // Returns the headers of a request to `/authorize` with the body, signed over a new challenge.
+        let mut signed_headers = |body: &[u8]| {
+            let challenge = authenticator.issue_challenge("client", 0).unwrap();
+            let signature = sign_aleo_request(
+                &private_key,
+                &Method::POST,
+                "/authorize",
+                &challenge.nonce,
+                body,
+                rng,
+            )
+            .unwrap();
+            let mut headers = HeaderMap::new();
+            headers.insert(ALEO_ADDRESS_HEADER, address.to_string().parse().unwrap());
+            headers.insert(ALEO_NONCE_HEADER, challenge.nonce.parse().unwrap());
+            headers.insert(
+                ALEO_SIGNATURE_HEADER,
+                signature.to_string().parse().unwrap(),
+            );
+            headers
+        };
+        let authenticate = |headers: &HeaderMap, body: &[u8]| {
+            authenticator.authenticate("authorize", &Method::POST, "/authorize", headers, body, 0)
+        };
+
+        // The signature covers the body, and a failed attempt consumes the challenge.
+        let headers = signed_headers(b"body");
+        let error = authenticate(&headers, b"other").unwrap_err();
+        assert_eq!(error.code(), "unauthorized");
+        let error = authenticate(&headers, b"body").unwrap_err();
+        assert_eq!(error.code(), "unauthorized");
+
+        // The first use succeeds, and the second fails.
+        let headers = signed_headers(b"body");
+        assert_eq!(
+            authenticate(&headers, b"body").unwrap(),
+            Caller::Aleo(address)
+        );
+        let error = authenticate(&headers, b"body").unwrap_err();
+        assert_eq!(error.code(), "unauthorized");
+    }
+
+    #[test]
+    fn test_challenge_limits() {
+        let authenticator = Authenticator::new(AuthConfig::default()).unwrap();
+
+        // Each client may only have a few outstanding challenges.
+        for _ in 0..MAX_CHALLENGES_PER_CLIENT {
+            authenticator.issue_challenge("mallory", 0).unwrap();
+        }
+        let error = authenticator.issue_challenge("mallory", 0).unwrap_err();
+        assert_eq!(error.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(error.retry_after(), Some(default_challenge_ttl_secs()));
+        authenticator.issue_challenge("alice", 0).unwrap();
+
+        // Expired challenges are removed, which makes room for new ones.
+        authenticator
+            .issue_challenge("mallory", default_challenge_ttl_secs() + 1)
+            .unwrap();
+
+        // When the store is full, the oldest challenges are evicted.
+        let mut challenges = Challenges::default();
+        for (client, nonce) in [("a", "1"), ("b", "2"), ("c", "3")] {
+            challenges.issued.insert(
+                nonce.to_string(),
+                IssuedChallenge {
+                    client: client.to_string(),
+                    expires_at: 10,
+                },
+            );
+            challenges.order.push_back(nonce.to_string());
+            challenges.per_client.insert(client.to_string(), 1);
+        }
+        challenges.prune(0, 2);
+        assert!(!challenges.issued.contains_key("1"));
+        assert!(!challenges.issued.contains_key("2"));
+        assert!(challenges.issued.contains_key("3"));
+        assert_eq!(challenges.per_client.len(), 1);
+    }
+
+    #[test]
+    fn test_open_route() {
+        let config: AuthConfig = toml::from_str(
+            r#"
+            [routes.keygen]
+            methods = ["none"]
+            "#,
+        )
+        .unwrap();
+        let authenticator = Authenticator::new(config).unwrap();
+        let caller = authenticator
+            .authenticate(
+                "keygen",
+                &Method::GET,
+                "/keygen/1",
+                &HeaderMap::new(),
+                b"",
+                0,
+            )
+            .unwrap();
+        assert_eq!(caller, Caller::Anonymous);
+        let error = authenticator
+            .authenticate(
+                "authorize",
+                &Method::POST,
+                "/authorize",
+                &HeaderMap::new(),
+                b"",
+                0,
+            )
+            .unwrap_err();
+        assert_eq!(error.code(), "unauthorized");
+    }
+
+    #[test]
+    fn test_function_routes() {
+        let config: AuthConfig = toml::from_str(
+            r#"
+            [api_keys]
+            operator = "operator-key"
+            staker = "staker-key"
+
+            [routes.authorize]
+            methods = ["api_key"]
+            keys = ["operator"]
+
+            [routes.bond_public]
+            methods = ["api_key"]
+            keys = ["staker"]
+            "#,
+        )
+        .unwrap();
+        let authenticator = Authenticator::new(config).unwrap();
+        let authenticate = |route: &str, key: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(API_KEY_HEADER, key.parse().unwrap());
+            authenticator.authenticate(route, &Method::POST, "/authorize", &headers, b"", 0)
+        };
+
+        // A function without its own policy falls back to the `authorize` policy.
+        assert!(authenticate("transfer_public", "operator-key").is_ok());
+        assert_eq!(
+            authenticate("transfer_public", "staker-key")
+                .unwrap_err()
+                .code(),
+            "forbidden"
+        );
+        // A function with its own policy uses only that policy.
+        assert!(authenticate("bond_public", "staker-key").is_ok());
+        assert_eq!(
+            authenticate("bond_public", "operator-key")
+                .unwrap_err()
+                .code(),
+            "forbidden"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_body_limit() {
+        let route = with_auth(Arc::new(Authenticator::disabled()), "keygen", 16)
+            .map(|_: Caller, _: Bytes| warp::reply())
+            .recover(handle_rejection);
+
+        // A request without a body.
+        let response = warp::test::request()
+            .method("GET")
+            .path("/keys")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // A request with a body that is too large, even on a GET route.
+        let response = warp::test::request()
+            .method("GET")
+            .path("/keys")
+            .body([0u8; 17])
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "payload_too_large");
+    }
+}
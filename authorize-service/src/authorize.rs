@@ -10,6 +10,9 @@ use snarkvm::prelude::{
     Response, Value, ValueType, U64,
 };
 
+// TODO (@d0cd) Use table from `credits` crate once it is up to date with snarkVM.
+pub(crate) const TRANSFER_PUBLIC_FEE_IN_MICROCREDITS: u64 = 263388;
+
 pub fn authorize_transfer_public<N: Network>(
     request: AuthorizeRequest<N>,
 ) -> Result<AuthorizeResponse<N>> {
@@ -17,14 +20,13 @@ pub fn authorize_transfer_public<N: Network>(
     let rng = &mut rand::thread_rng();
 
     // Get the private key.
-    let private_key = request.private_key;
+    let private_key = *request.signer.private_key()?;
     // Get the recipient.
     let recipient = request.recipient;
     // Get the amount in microcredits.
     let amount_in_microcredits = request.amount_in_microcredits;
     // Get the fee in microcredits.
-    // TODO (@d0cd) Use table from `credits` crate once it is up to date with snarkVM.
-    let fee_in_microcredits = U64::new(263388);
+    let fee_in_microcredits = U64::new(TRANSFER_PUBLIC_FEE_IN_MICROCREDITS);
     // Get the priority fee in microcredits.
     let priority_fee_in_microcredits = request.priority_fee_in_microcredits;
 
@@ -0,0 +1,695 @@
+use super::*;
+
+use anyhow::{ensure, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use snarkvm::circuit::Aleo;
+use snarkvm::prelude::{Address, Literal, Plaintext, Process, Record, Value};
+
+// The base fees are upper bounds on the cost that snarkVM computes for an execution of each function:
+// a microcredit for each byte of the execution, plus the cost of the commands in its finalize block.
+// They are estimates, rounded up, and `test_base_fees_cover_execution_costs` checks that each covers the cost.
+// TODO: Compute the base fee of each request from its execution, rather than from a table.
+
+// These functions have no finalize block, so their fees only cover the size of their executions.
+pub(crate) const TRANSFER_PRIVATE_FEE_IN_MICROCREDITS: u64 = 5000;
+pub(crate) const JOIN_FEE_IN_MICROCREDITS: u64 = 5000;
+pub(crate) const SPLIT_FEE_IN_MICROCREDITS: u64 = 5000;
+// These functions finalize a read and a write of one account, like `transfer_public`.
+pub(crate) const TRANSFER_PRIVATE_TO_PUBLIC_FEE_IN_MICROCREDITS: u64 = 300000;
+pub(crate) const TRANSFER_PUBLIC_TO_PRIVATE_FEE_IN_MICROCREDITS: u64 = 300000;
+// These functions finalize reads and writes of the committee, the bonds, and the unbonding amounts.
+pub(crate) const BOND_PUBLIC_FEE_IN_MICROCREDITS: u64 = 1000000;
+pub(crate) const UNBOND_PUBLIC_FEE_IN_MICROCREDITS: u64 = 1000000;
+pub(crate) const UNBOND_DELEGATOR_AS_VALIDATOR_FEE_IN_MICROCREDITS: u64 = 1000000;
+// This function finalizes the removal of an unbonding amount, and a write of one account.
+pub(crate) const CLAIM_UNBOND_PUBLIC_FEE_IN_MICROCREDITS: u64 = 500000;
+
+/// The functions in `credits.aleo` that can be authorized.
+pub const CREDITS_FUNCTIONS: &[&str] = &[
+    "transfer_public",
+    "transfer_private",
+    "transfer_private_to_public",
+    "transfer_public_to_private",
+    "join",
+    "split",
+    "bond_public",
+    "unbond_public",
+    "unbond_delegator_as_validator",
+    "claim_unbond_public",
+];
+
+/// The functions in `credits.aleo` whose amount stays with the signer, so it does not count as spent.
+/// `split` divides a record of the signer, and `unbond_public` moves a bonded amount back to the signer.
+pub const UNSPENT_AMOUNT_FUNCTIONS: &[&str] = &["split", "unbond_public"];
+
+/// A request to authorize a function in `credits.aleo`.
+pub trait CreditsRequest<N: Network>:
+    Sized + Send + FromBytes + ToBytes + Serialize + DeserializeOwned + 'static
+{
+    /// The name of the function in `credits.aleo`.
+    const FUNCTION_NAME: &'static str;
+    /// The base fee of the function, in microcredits.
+    const BASE_FEE_IN_MICROCREDITS: u64;
+
+    /// Returns the signer.
+    fn signer(&self) -> &Signer<N>;
+
+    /// Returns the signer, so that a key ID can be resolved to its private key.
+    fn signer_mut(&mut self) -> &mut Signer<N>;
+
+    /// Returns the private key of the signer, or an error if its key ID was not resolved.
+    fn private_key(&self) -> Result<&PrivateKey<N>> {
+        self.signer().private_key()
+    }
+
+    /// Returns the priority fee, in microcredits.
+    fn priority_fee_in_microcredits(&self) -> u64;
+
+    /// Returns the amount that is moved by the function, in microcredits, if any.
+    fn amount_in_microcredits(&self) -> Option<u64> {
+        None
+    }
+
+    /// Returns the records that are spent by the function.
+    fn records(&self) -> Vec<&Record<N, Plaintext<N>>> {
+        vec![]
+    }
+
+    /// Returns the address that receives the amount, or that the function acts on, if any.
+    fn recipient(&self) -> Option<Address<N>> {
+        None
+    }
+
+    /// Returns the inputs to the function.
+    fn inputs(&self) -> Vec<Value<N>>;
+
+    /// Checks that the request can be authorized.
+    fn validate(&self) -> Result<()> {
+        // Check that the amount is nonzero.
+        if let Some(amount_in_microcredits) = self.amount_in_microcredits() {
+            ensure!(
+                amount_in_microcredits > 0,
+                "The amount must be greater than zero"
+            );
+        }
+        // Check that the fee does not overflow.
+        ensure!(
+            self.priority_fee_in_microcredits()
+                .checked_add(Self::BASE_FEE_IN_MICROCREDITS)
+                .is_some(),
+            "The priority fee is too large"
+        );
+        // Check that the records belong to the signer.
+        let signer = Address::try_from(self.private_key()?)?;
+        for record in self.records() {
+            ensure!(
+                **record.owner() == signer,
+                "The record does not belong to the signer"
+            );
+        }
+        Ok(())
+    }
+
+    /// Authorizes the function and its fee.
+    fn authorize<A: Aleo<Network = N>>(self, process: &Process<N>) -> Result<AuthorizeResponse<N>> {
+        // Initialize the RNG.
+        let rng = &mut rand::thread_rng();
+
+        // Get the private key.
+        let private_key = self.private_key()?;
+
+        // Construct the function authorization.
+        let function_authorization = process.authorize::<A, _>(
+            private_key,
+            "credits.aleo",
+            Self::FUNCTION_NAME,
+            self.inputs().into_iter(),
+            rng,
+        )?;
+        // Construct the fee authorization.
+        let fee_authorization = process.authorize_fee_public::<A, _>(
+            private_key,
+            Self::BASE_FEE_IN_MICROCREDITS,
+            self.priority_fee_in_microcredits(),
+            function_authorization.to_execution_id()?,
+            rng,
+        )?;
+
+        // Construct the response.
+        Ok(AuthorizeResponse {
+            function_authorization,
+            fee_authorization,
+        })
+    }
+}
+
+impl<N: Network> CreditsRequest<N> for AuthorizeRequest<N> {
+    const FUNCTION_NAME: &'static str = "transfer_public";
+    const BASE_FEE_IN_MICROCREDITS: u64 = TRANSFER_PUBLIC_FEE_IN_MICROCREDITS;
+
+    fn signer(&self) -> &Signer<N> {
+        &self.signer
+    }
+
+    fn signer_mut(&mut self) -> &mut Signer<N> {
+        &mut self.signer
+    }
+
+    fn priority_fee_in_microcredits(&self) -> u64 {
+        *self.priority_fee_in_microcredits
+    }
+
+    fn amount_in_microcredits(&self) -> Option<u64> {
+        Some(*self.amount_in_microcredits)
+    }
+
+    fn recipient(&self) -> Option<Address<N>> {
+        Some(self.recipient)
+    }
+
+    fn inputs(&self) -> Vec<Value<N>> {
+        vec![
+            Value::from(Literal::Address(self.recipient)),
+            Value::from(Literal::U64(self.amount_in_microcredits)),
+        ]
+    }
+
+    // The public transfer is authorized by hand, and does not need the process.
+    fn authorize<A: Aleo<Network = N>>(self, _: &Process<N>) -> Result<AuthorizeResponse<N>> {
+        authorize_transfer_public(self)
+    }
+}
+
+impl<N: Network> CreditsRequest<N> for TransferPrivateRequest<N> {
+    const FUNCTION_NAME: &'static str = "transfer_private";
+    const BASE_FEE_IN_MICROCREDITS: u64 = TRANSFER_PRIVATE_FEE_IN_MICROCREDITS;
+
+    fn signer(&self) -> &Signer<N> {
+        &self.signer
+    }
+
+    fn signer_mut(&mut self) -> &mut Signer<N> {
+        &mut self.signer
+    }
+
+    fn priority_fee_in_microcredits(&self) -> u64 {
+        *self.priority_fee_in_microcredits
+    }
+
+    fn amount_in_microcredits(&self) -> Option<u64> {
+        Some(*self.amount_in_microcredits)
+    }
+
+    fn records(&self) -> Vec<&Record<N, Plaintext<N>>> {
+        vec![&self.record]
+    }
+
+    fn recipient(&self) -> Option<Address<N>> {
+        Some(self.recipient)
+    }
+
+    fn inputs(&self) -> Vec<Value<N>> {
+        vec![
+            Value::Record(self.record.clone()),
+            Value::from(Literal::Address(self.recipient)),
+            Value::from(Literal::U64(self.amount_in_microcredits)),
+        ]
+    }
+}
+
+impl<N: Network> CreditsRequest<N> for TransferPrivateToPublicRequest<N> {
+    const FUNCTION_NAME: &'static str = "transfer_private_to_public";
+    const BASE_FEE_IN_MICROCREDITS: u64 = TRANSFER_PRIVATE_TO_PUBLIC_FEE_IN_MICROCREDITS;
+
+    fn signer(&self) -> &Signer<N> {
+        &self.signer
+    }
+
+    fn signer_mut(&mut self) -> &mut Signer<N> {
+        &mut self.signer
+    }
+
+    fn priority_fee_in_microcredits(&self) -> u64 {
+        *self.priority_fee_in_microcredits
+    }
+
+    fn amount_in_microcredits(&self) -> Option<u64> {
+        Some(*self.amount_in_microcredits)
+    }
+
+    fn records(&self) -> Vec<&Record<N, Plaintext<N>>> {
+        vec![&self.record]
+    }
+
+    fn recipient(&self) -> Option<Address<N>> {
+        Some(self.recipient)
+    }
+
+    fn inputs(&self) -> Vec<Value<N>> {
+        vec![
+            Value::Record(self.record.clone()),
+            Value::from(Literal::Address(self.recipient)),
+            Value::from(Literal::U64(self.amount_in_microcredits)),
+        ]
+    }
+}
+
+impl<N: Network> CreditsRequest<N> for TransferPublicToPrivateRequest<N> {
+    const FUNCTION_NAME: &'static str = "transfer_public_to_private";
+    const BASE_FEE_IN_MICROCREDITS: u64 = TRANSFER_PUBLIC_TO_PRIVATE_FEE_IN_MICROCREDITS;
+
+    fn signer(&self) -> &Signer<N> {
+        &self.signer
+    }
+
+    fn signer_mut(&mut self) -> &mut Signer<N> {
+        &mut self.signer
+    }
+
+    fn priority_fee_in_microcredits(&self) -> u64 {
+        *self.priority_fee_in_microcredits
+    }
+
+    fn amount_in_microcredits(&self) -> Option<u64> {
+        Some(*self.amount_in_microcredits)
+    }
+
+    fn recipient(&self) -> Option<Address<N>> {
+        Some(self.recipient)
+    }
+
+    fn inputs(&self) -> Vec<Value<N>> {
+        vec![
+            Value::from(Literal::Address(self.recipient)),
+            Value::from(Literal::U64(self.amount_in_microcredits)),
+        ]
+    }
+}
+
+impl<N: Network> CreditsRequest<N> for JoinRequest<N> {
+    const FUNCTION_NAME: &'static str = "join";
+    const BASE_FEE_IN_MICROCREDITS: u64 = JOIN_FEE_IN_MICROCREDITS;
+
+    fn signer(&self) -> &Signer<N> {
+        &self.signer
+    }
+
+    fn signer_mut(&mut self) -> &mut Signer<N> {
+        &mut self.signer
+    }
+
+    fn priority_fee_in_microcredits(&self) -> u64 {
+        *self.priority_fee_in_microcredits
+    }
+
+    fn records(&self) -> Vec<&Record<N, Plaintext<N>>> {
+        vec![&self.first_record, &self.second_record]
+    }
+
+    fn inputs(&self) -> Vec<Value<N>> {
+        vec![
+            Value::Record(self.first_record.clone()),
+            Value::Record(self.second_record.clone()),
+        ]
+    }
+}
+
+impl<N: Network> CreditsRequest<N> for SplitRequest<N> {
+    const FUNCTION_NAME: &'static str = "split";
+    const BASE_FEE_IN_MICROCREDITS: u64 = SPLIT_FEE_IN_MICROCREDITS;
+
+    fn signer(&self) -> &Signer<N> {
+        &self.signer
+    }
+
+    fn signer_mut(&mut self) -> &mut Signer<N> {
+        &mut self.signer
+    }
+
+    fn priority_fee_in_microcredits(&self) -> u64 {
+        *self.priority_fee_in_microcredits
+    }
+
+    fn amount_in_microcredits(&self) -> Option<u64> {
+        Some(*self.amount_in_microcredits)
+    }
+
+    fn records(&self) -> Vec<&Record<N, Plaintext<N>>> {
+        vec![&self.record]
+    }
+
+    fn inputs(&self) -> Vec<Value<N>> {
+        vec![
+            Value::Record(self.record.clone()),
+            Value::from(Literal::U64(self.amount_in_microcredits)),
+        ]
+    }
+}
+
+impl<N: Network> CreditsRequest<N> for BondPublicRequest<N> {
+    const FUNCTION_NAME: &'static str = "bond_public";
+    const BASE_FEE_IN_MICROCREDITS: u64 = BOND_PUBLIC_FEE_IN_MICROCREDITS;
+
+    fn signer(&self) -> &Signer<N> {
+        &self.signer
+    }
+
+    fn signer_mut(&mut self) -> &mut Signer<N> {
+        &mut self.signer
+    }
+
+    fn priority_fee_in_microcredits(&self) -> u64 {
+        *self.priority_fee_in_microcredits
+    }
+
+    fn amount_in_microcredits(&self) -> Option<u64> {
+        Some(*self.amount_in_microcredits)
+    }
+
+    fn recipient(&self) -> Option<Address<N>> {
+        Some(self.validator)
+    }
+
+    fn inputs(&self) -> Vec<Value<N>> {
+        vec![
+            Value::from(Literal::Address(self.validator)),
+            Value::from(Literal::U64(self.amount_in_microcredits)),
+        ]
+    }
+}
+
+impl<N: Network> CreditsRequest<N> for UnbondPublicRequest<N> {
+    const FUNCTION_NAME: &'static str = "unbond_public";
+    const BASE_FEE_IN_MICROCREDITS: u64 = UNBOND_PUBLIC_FEE_IN_MICROCREDITS;
+
+    fn signer(&self) -> &Signer<N> {
+        &self.signer
+    }
+
+    fn signer_mut(&mut self) -> &mut Signer<N> {
+        &mut self.signer
+    }
+
+    fn priority_fee_in_microcredits(&self) -> u64 {
+        *self.priority_fee_in_microcredits
+    }
+
+    fn amount_in_microcredits(&self) -> Option<u64> {
+        Some(*self.amount_in_microcredits)
+    }
+
+    fn inputs(&self) -> Vec<Value<N>> {
+        vec![Value::from(Literal::U64(self.amount_in_microcredits))]
+    }
+}
+
+impl<N: Network> CreditsRequest<N> for UnbondDelegatorAsValidatorRequest<N> {
+    const FUNCTION_NAME: &'static str = "unbond_delegator_as_validator";
+    const BASE_FEE_IN_MICROCREDITS: u64 = UNBOND_DELEGATOR_AS_VALIDATOR_FEE_IN_MICROCREDITS;
+
+    fn signer(&self) -> &Signer<N> {
+        &self.signer
+    }
+
+    fn signer_mut(&mut self) -> &mut Signer<N> {
+        &mut self.signer
+    }
+
+    fn priority_fee_in_microcredits(&self) -> u64 {
+        *self.priority_fee_in_microcredits
+    }
+
+    fn recipient(&self) -> Option<Address<N>> {
+        Some(self.delegator)
+    }
+
+    fn inputs(&self) -> Vec<Value<N>> {
+        vec![Value::from(Literal::Address(self.delegator))]
+    }
+}
+
+impl<N: Network> CreditsRequest<N> for ClaimUnbondPublicRequest<N> {
+    const FUNCTION_NAME: &'static str = "claim_unbond_public";
+    const BASE_FEE_IN_MICROCREDITS: u64 = CLAIM_UNBOND_PUBLIC_FEE_IN_MICROCREDITS;
+
+    fn signer(&self) -> &Signer<N> {
+        &self.signer
+    }
+
+    fn signer_mut(&mut self) -> &mut Signer<N> {
+        &mut self.signer
+    }
+
+    fn priority_fee_in_microcredits(&self) -> u64 {
+        *self.priority_fee_in_microcredits
+    }
+
+    fn inputs(&self) -> Vec<Value<N>> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use snarkvm::circuit::AleoV0;
+    use snarkvm::ledger::store::ConsensusStore;
+    use snarkvm::prelude::store::helpers::memory::ConsensusMemory;
+    use snarkvm::prelude::{execution_cost, ViewKey, U64, VM};
+    use snarkvm::utilities::TestRng;
+    use std::str::FromStr;
+
+    const PRIVATE_KEY: &str = "APrivateKey1zkpCE9rCw9SixY82xaDrW2Hwxc2f3VjeuR2oZHR81zcuUDV";
+    const RECIPIENT: &str = "aleo1zcsyu7wfrdp4n6gq752p3np45sat9d6zun2uhjer2h4skccsgsgq7ndrnj";
+
+    // Returns an unspent record owned by the test private key.
+    fn record(microcredits: u64) -> Record<CurrentNetwork, Plaintext<CurrentNetwork>> {
+        let private_key = PrivateKey::<CurrentNetwork>::from_str(PRIVATE_KEY).unwrap();
+        let owner = Address::try_from(&private_key).unwrap();
+        Record::from_str(&format!(
+            "{{ owner: {owner}.private, microcredits: {microcredits}u64.private, _nonce: 0group.public }}"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_rejects_foreign_record() {
+        let request = SplitRequest::<CurrentNetwork> {
+            signer: Signer::PrivateKey(PrivateKey::new(&mut rand::thread_rng()).unwrap()),
+            record: record(100),
+            amount_in_microcredits: U64::new(50),
+            priority_fee_in_microcredits: U64::new(0),
+        };
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_amount() {
+        let request = TransferPublicToPrivateRequest::<CurrentNetwork> {
+            signer: Signer::PrivateKey(PrivateKey::from_str(PRIVATE_KEY).unwrap()),
+            recipient: Address::from_str(RECIPIENT).unwrap(),
+            amount_in_microcredits: U64::new(0),
+            priority_fee_in_microcredits: U64::new(0),
+        };
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_authorize_credits_functions() {
+        let process = Process::<CurrentNetwork>::load().unwrap();
+        let private_key = PrivateKey::<CurrentNetwork>::from_str(PRIVATE_KEY).unwrap();
+        let recipient = Address::from_str(RECIPIENT).unwrap();
+
+        // Authorize a private transfer.
+        let request = TransferPrivateRequest {
+            signer: Signer::PrivateKey(private_key),
+            record: record(100),
+            recipient,
+            amount_in_microcredits: U64::new(10),
+            priority_fee_in_microcredits: U64::new(0),
+        };
+        request.validate().unwrap();
+        request.authorize::<AleoV0>(&process).unwrap();
+
+        // Authorize a join.
+        let request = JoinRequest {
+            signer: Signer::PrivateKey(private_key),
+            first_record: record(100),
+            second_record: record(200),
+            priority_fee_in_microcredits: U64::new(0),
+        };
+        request.validate().unwrap();
+        request.authorize::<AleoV0>(&process).unwrap();
+
+        // Authorize a bond.
+        let request = BondPublicRequest {
+            signer: Signer::PrivateKey(private_key),
+            validator: recipient,
+            amount_in_microcredits: U64::new(1_000_000),
+            priority_fee_in_microcredits: U64::new(0),
+        };
+        request.validate().unwrap();
+        request.authorize::<AleoV0>(&process).unwrap();
+
+        // Authorize a claim.
+        let request = ClaimUnbondPublicRequest {
+            signer: Signer::PrivateKey(private_key),
+            priority_fee_in_microcredits: U64::new(0),
+        };
+        request.validate().unwrap();
+        request.authorize::<AleoV0>(&process).unwrap();
+    }
+
+    // Checks that the base fee of the request covers the cost that snarkVM computes for its execution.
+    fn check_base_fee<R: CreditsRequest<CurrentNetwork>>(
+        vm: &VM<CurrentNetwork, ConsensusMemory<CurrentNetwork>>,
+        process: &Process<CurrentNetwork>,
+        request: R,
+        rng: &mut TestRng,
+    ) {
+        let response = request.authorize::<AleoV0>(process).unwrap();
+        let transaction = vm
+            .execute_authorization(response.function_authorization, None, None, rng)
+            .unwrap();
+        let (cost, _) = execution_cost(vm, transaction.execution().unwrap()).unwrap();
+        assert!(
+            R::BASE_FEE_IN_MICROCREDITS >= cost,
+            "The base fee of '{}' is {}, but its execution costs {cost} microcredits",
+            R::FUNCTION_NAME,
+            R::BASE_FEE_IN_MICROCREDITS
+        );
+    }
+
+    #[test]
+    fn test_base_fees_cover_execution_costs() {
+        let rng = &mut TestRng::default();
+        let process = Process::<CurrentNetwork>::load().unwrap();
+        // Initialize a VM, whose genesis records are owned by the signer, so that they can be spent.
+        let vm = VM::<CurrentNetwork, ConsensusMemory<CurrentNetwork>>::from(
+            ConsensusStore::open(None).unwrap(),
+        )
+        .unwrap();
+        let private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        let genesis_block = vm.genesis_beacon(&private_key, rng).unwrap();
+        vm.add_next_block(&genesis_block).unwrap();
+        let view_key = ViewKey::try_from(&private_key).unwrap();
+        let mut records = genesis_block
+            .records()
+            .map(|(_, record)| record.decrypt(&view_key).unwrap());
+        let (first_record, second_record) = (records.next().unwrap(), records.next().unwrap());
+        let recipient = Address::from_str(RECIPIENT).unwrap();
+        let signer = Signer::PrivateKey(private_key);
+        let amount_in_microcredits = U64::new(1_000_000);
+        let priority_fee_in_microcredits = U64::new(0);
+
+        check_base_fee(
+            &vm,
+            &process,
+            AuthorizeRequest {
+                signer: signer.clone(),
+                recipient,
+                amount_in_microcredits,
+                priority_fee_in_microcredits,
+            },
+            rng,
+        );
+        check_base_fee(
+            &vm,
+            &process,
+            TransferPrivateRequest {
+                signer: signer.clone(),
+                record: first_record.clone(),
+                recipient,
+                amount_in_microcredits,
+                priority_fee_in_microcredits,
+            },
+            rng,
+        );
+        check_base_fee(
+            &vm,
+            &process,
+            TransferPrivateToPublicRequest {
+                signer: signer.clone(),
+                record: first_record.clone(),
+                recipient,
+                amount_in_microcredits,
+                priority_fee_in_microcredits,
+            },
+            rng,
+        );
+        check_base_fee(
+            &vm,
+            &process,
+            TransferPublicToPrivateRequest {
+                signer: signer.clone(),
+                recipient,
+                amount_in_microcredits,
+                priority_fee_in_microcredits,
+            },
+            rng,
+        );
+        check_base_fee(
+            &vm,
+            &process,
+            JoinRequest {
+                signer: signer.clone(),
+                first_record: first_record.clone(),
+                second_record,
+                priority_fee_in_microcredits,
+            },
+            rng,
+        );
+        check_base_fee(
+            &vm,
+            &process,
+            SplitRequest {
+                signer: signer.clone(),
+                record: first_record,
+                amount_in_microcredits,
+                priority_fee_in_microcredits,
+            },
+            rng,
+        );
+        check_base_fee(
+            &vm,
+            &process,
+            BondPublicRequest {
+                signer: signer.clone(),
+                validator: recipient,
+                amount_in_microcredits,
+                priority_fee_in_microcredits,
+            },
+            rng,
+        );
+        check_base_fee(
+            &vm,
+            &process,
+            UnbondPublicRequest {
+                signer: signer.clone(),
+                amount_in_microcredits,
+                priority_fee_in_microcredits,
+            },
+            rng,
+        );
+        check_base_fee(
+            &vm,
+            &process,
+            UnbondDelegatorAsValidatorRequest {
+                signer: signer.clone(),
+                delegator: recipient,
+                priority_fee_in_microcredits,
+            },
+            rng,
+        );
+        check_base_fee(
+            &vm,
+            &process,
+            ClaimUnbondPublicRequest {
+                signer,
+                priority_fee_in_microcredits,
+            },
+            rng,
+        );
+    }
+}
@@ -0,0 +1,436 @@
+use super::*;
+
+use serde::Serialize;
+use std::convert::Infallible;
+use std::fmt;
+use warp::http::header::{HeaderValue, RETRY_AFTER};
+use warp::http::StatusCode;
+use warp::reject::{
+    InvalidHeader, LengthRequired, MethodNotAllowed, MissingHeader, PayloadTooLarge, Reject,
+};
+use warp::{Rejection, Reply};
+
+/// An error that is returned to the client as a JSON body.
+/// The messages must never contain secrets, such as the private key.
+#[derive(Debug)]
+pub enum ServiceError {
+    /// The request body could not be decoded.
+    Decode(String),
+    /// The content type of the request body is not supported.
+    UnsupportedMediaType(String),
+    /// The request is missing credentials, or the credentials are not valid.
+    Unauthorized(String),
+    /// The caller may not call the route.
+    Forbidden(String),
+    /// The key ID does not name a key of the caller in the keystore.
+    KeyNotFound(String),
+    /// The request was decoded, but is not valid.
+    Validation(String),
+    /// The idempotency key was used with a different request, or its request is still in progress.
+    Conflict(String),
+    /// The caller exceeded a rate or concurrency limit, and may retry after the given number of seconds.
+    TooManyRequests(String, u64),
+    /// The authorization could not be constructed.
+    Authorization(String),
+    /// The response could not be constructed.
+    Internal(String),
+    /// The node that the service reads from failed, or returned an invalid response.
+    Upstream(String),
+    /// The service is not ready to handle the request yet.
+    NotReady(String),
+    /// The work did not complete in time, and was cancelled.
+    Timeout(String),
+    /// The request breaks a rule of the signing policy of its signer, and was not signed.
+    PolicyDenied(String, PolicyRule),
+    /// The request body is larger than the limit.
+    PayloadTooLarge(String),
+}
+
+impl ServiceError {
+    /// Returns the HTTP status code of the error.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::Decode(_) => StatusCode::BAD_REQUEST,
+            Self::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::KeyNotFound(_) => StatusCode::NOT_FOUND,
+            Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::TooManyRequests(..) => StatusCode::TOO_MANY_REQUESTS,
+            Self::Authorization(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Upstream(_) => StatusCode::BAD_GATEWAY,
+            Self::NotReady(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            Self::PolicyDenied(..) => StatusCode::FORBIDDEN,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+
+    /// Returns the stable error code of the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Decode(_) => "decode_error",
+            Self::UnsupportedMediaType(_) => "unsupported_media_type",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::Forbidden(_) => "forbidden",
+            Self::KeyNotFound(_) => "key_not_found",
+            Self::Validation(_) => "validation_error",
+            Self::Conflict(_) => "idempotency_conflict",
+            Self::TooManyRequests(..) => "too_many_requests",
+            Self::Authorization(_) => "authorization_error",
+            Self::Internal(_) => "internal_error",
+            Self::Upstream(_) => "upstream_error",
+            Self::NotReady(_) => "not_ready",
+            Self::Timeout(_) => "timeout",
+            Self::PolicyDenied(..) => "policy_denied",
+            Self::PayloadTooLarge(_) => "payload_too_large",
+        }
+    }
+
+    /// Returns the message of the error.
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Decode(message)
+            | Self::UnsupportedMediaType(message)
+            | Self::Unauthorized(message)
+            | Self::Forbidden(message)
+            | Self::KeyNotFound(message)
+            | Self::Validation(message)
+            | Self::Conflict(message)
+            | Self::TooManyRequests(message, _)
+            | Self::Authorization(message)
+            | Self::Internal(message)
+            | Self::Upstream(message)
+            | Self::NotReady(message)
+            | Self::Timeout(message)
+            | Self::PolicyDenied(message, _)
+            | Self::PayloadTooLarge(message) => message,
+        }
+    }
+
+    /// Returns the number of seconds after which the request may be retried, if any.
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::TooManyRequests(_, retry_after) => Some(*retry_after),
+            _ => None,
+        }
+    }
+
+    /// Returns the policy rule that denied the request, if any.
+    pub fn rule(&self) -> Option<PolicyRule> {
+        match self {
+            Self::PolicyDenied(_, rule) => Some(*rule),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+impl Reject for ServiceError {}
+
+impl From<CommonError> for ServiceError {
+    fn from(error: CommonError) -> Self {
+        match error {
+            CommonError::Decode(message) => Self::Decode(message),
+            CommonError::Conflict(message) => Self::Conflict(message),
+            CommonError::TooManyRequests(message, retry_after) => {
+                Self::TooManyRequests(message, retry_after)
+            }
+            CommonError::Internal(message) => Self::Internal(message),
+            CommonError::NotReady(message) => Self::NotReady(message),
+            CommonError::Timeout(message) => Self::Timeout(message),
+        }
+    }
+}
+
+/// The JSON body of an error response.
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    pub code: &'static str,
+    pub message: String,
+    /// The policy rule that denied the request, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<&'static str>,
+}
+
+/// Every code that may be returned in an error body, including those of rejections by warp.
+pub const ERROR_CODES: &[&str] = &[
+    "decode_error",
+    "unsupported_media_type",
+    "unauthorized",
+    "forbidden",
+    "key_not_found",
+    "validation_error",
+    "idempotency_conflict",
+    "too_many_requests",
+    "authorization_error",
+    "internal_error",
+    "upstream_error",
+    "not_ready",
+    "timeout",
+    "policy_denied",
+    "payload_too_large",
+    "length_required",
+    "invalid_header",
+    "method_not_allowed",
+    "not_found",
+];
+
+/// Converts a rejection into a JSON error response.
+pub async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, Infallible> {
+    let mut retry_after = None;
+    let mut rule = None;
+    let (status, code, message) = if let Some(error) = rejection.find::<ServiceError>() {
+        retry_after = error.retry_after();
+        rule = error.rule().map(|rule| rule.as_str());
+        (error.status(), error.code(), error.message().to_string())
+    } else if rejection.find::<PayloadTooLarge>().is_some() {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "payload_too_large",
+            "The request body is too large".to_string(),
+        )
+    } else if rejection.find::<LengthRequired>().is_some() {
+        (
+            StatusCode::LENGTH_REQUIRED,
+            "length_required",
+            "The request must have a content length".to_string(),
+        )
+    } else if rejection.find::<MissingHeader>().is_some()
+        || rejection.find::<InvalidHeader>().is_some()
+    {
+        (
+            StatusCode::BAD_REQUEST,
+            "invalid_header",
+            "A request header is missing or invalid".to_string(),
+        )
+    } else if rejection.find::<MethodNotAllowed>().is_some() {
+        (
+            StatusCode::METHOD_NOT_ALLOWED,
+            "method_not_allowed",
+            "The method is not allowed".to_string(),
+        )
+    } else if rejection.is_not_found() {
+        (
+            StatusCode::NOT_FOUND,
+            "not_found",
+            "The route does not exist".to_string(),
+        )
+    } else {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            "Unhandled rejection".to_string(),
+        )
+    };
+
+    // Log the error. Only the code and message are logged, never the request.
+    match status.is_server_error() {
+        true => log::error!("{} {}: {}", status.as_u16(), code, message),
+        false => log::warn!("{} {}: {}", status.as_u16(), code, message),
+    }
+
+    let body = ErrorBody {
+        code,
+        message,
+        rule,
+    };
+    let mut response = warp::reply::with_status(warp::reply::json(&body), status).into_response();
+    if let Some(retry_after) = retry_after {
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A rejection that the handler does not know.
+    #[derive(Debug)]
+    struct Unknown;
+
+    impl Reject for Unknown {}
+
+    #[tokio::test]
+    async fn test_rejections_are_json_errors() {
+        let message = || "message".to_string();
+        let errors = [
+            (
+                ServiceError::Decode(message()),
+                StatusCode::BAD_REQUEST,
+                "decode_error",
+            ),
+            (
+                ServiceError::UnsupportedMediaType(message()),
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "unsupported_media_type",
+            ),
+            (
+                ServiceError::Unauthorized(message()),
+                StatusCode::UNAUTHORIZED,
+                "unauthorized",
+            ),
+            (
+                ServiceError::Forbidden(message()),
+                StatusCode::FORBIDDEN,
+                "forbidden",
+            ),
+            (
+                ServiceError::KeyNotFound(message()),
+                StatusCode::NOT_FOUND,
+                "key_not_found",
+            ),
+            (
+                ServiceError::Validation(message()),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "validation_error",
+            ),
+            (
+                ServiceError::Conflict(message()),
+                StatusCode::CONFLICT,
+                "idempotency_conflict",
+            ),
+            (
+                ServiceError::TooManyRequests(message(), 7),
+                StatusCode::TOO_MANY_REQUESTS,
+                "too_many_requests",
+            ),
+            (
+                ServiceError::Authorization(message()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "authorization_error",
+            ),
+            (
+                ServiceError::Internal(message()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+            ),
+            (
+                ServiceError::Upstream(message()),
+                StatusCode::BAD_GATEWAY,
+                "upstream_error",
+            ),
+            (
+                ServiceError::NotReady(message()),
+                StatusCode::SERVICE_UNAVAILABLE,
+                "not_ready",
+            ),
+            (
+                ServiceError::Timeout(message()),
+                StatusCode::GATEWAY_TIMEOUT,
+                "timeout",
+            ),
+            (
+                ServiceError::PolicyDenied(message(), PolicyRule::DailyLimitInMicrocredits),
+                StatusCode::FORBIDDEN,
+                "policy_denied",
+            ),
+            (
+                ServiceError::PayloadTooLarge(message()),
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "payload_too_large",
+            ),
+        ];
+        let mut cases: Vec<_> = errors
+            .into_iter()
+            .map(|(error, status, code)| (warp::reject::custom(error), status, code, "message"))
+            .collect();
+
+        // The rejections of warp's own filters.
+        cases.extend([
+            (
+                warp::test::request()
+                    .body([0u8; 11])
+                    .filter(&warp::body::content_length_limit(10))
+                    .await
+                    .unwrap_err(),
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "payload_too_large",
+                "The request body is too large",
+            ),
+            (
+                warp::test::request()
+                    .filter(&warp::body::content_length_limit(10))
+                    .await
+                    .unwrap_err(),
+                StatusCode::LENGTH_REQUIRED,
+                "length_required",
+                "The request must have a content length",
+            ),
+            (
+                warp::test::request()
+                    .filter(&warp::header::<String>("x-missing"))
+                    .await
+                    .unwrap_err(),
+                StatusCode::BAD_REQUEST,
+                "invalid_header",
+                "A request header is missing or invalid",
+            ),
+            (
+                warp::test::request()
+                    .header("x-number", "one")
+                    .filter(&warp::header::<u64>("x-number"))
+                    .await
+                    .unwrap_err(),
+                StatusCode::BAD_REQUEST,
+                "invalid_header",
+                "A request header is missing or invalid",
+            ),
+            (
+                warp::test::request()
+                    .method("GET")
+                    .filter(&warp::post())
+                    .await
+                    .unwrap_err(),
+                StatusCode::METHOD_NOT_ALLOWED,
+                "method_not_allowed",
+                "The method is not allowed",
+            ),
+            (
+                warp::reject::not_found(),
+                StatusCode::NOT_FOUND,
+                "not_found",
+                "The route does not exist",
+            ),
+            (
+                warp::reject::custom(Unknown),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Unhandled rejection",
+            ),
+        ]);
+
+        for (rejection, status, code, message) in cases {
+            let response = handle_rejection(rejection).await.unwrap().into_response();
+            assert_eq!(response.status(), status, "{code}");
+            assert!(ERROR_CODES.contains(&code), "{code}");
+            assert_eq!(response.headers()["content-type"], "application/json");
+            // Only rate limits are retried after a delay.
+            match code {
+                "too_many_requests" => assert_eq!(response.headers()[RETRY_AFTER], "7"),
+                _ => assert!(!response.headers().contains_key(RETRY_AFTER), "{code}"),
+            }
+            let body = warp::hyper::body::to_bytes(response.into_body())
+                .await
+                .unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(body["code"], code);
+            assert_eq!(body["message"], message, "{code}");
+            // Only policy denials name their rule.
+            match code {
+                "policy_denied" => assert_eq!(body["rule"], "daily_limit_in_microcredits"),
+                _ => assert!(body.get("rule").is_none(), "{code}"),
+            }
+        }
+    }
+}
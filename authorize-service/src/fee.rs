@@ -0,0 +1,482 @@
+use super::*;
+
+use anyhow::{ensure, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use snarkvm::ledger::block::{Block, Transaction};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The maximum number of blocks that are requested from the node at once.
+pub const MAX_BLOCKS_PER_REQUEST: u32 = 50;
+/// The time after which connecting to the node fails.
+pub const NODE_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// The time after which a request to the node fails, including reading its body.
+pub const NODE_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The functions in `credits.aleo` that fees can be suggested for.
+pub const FEE_FUNCTIONS: &[&str] = CREDITS_FUNCTIONS;
+
+/// The priority fee paid by a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeSample {
+    /// The function in `credits.aleo` that the transaction executed, if any.
+    pub function: Option<String>,
+    /// The priority fee, in microcredits.
+    pub priority_fee_in_microcredits: u64,
+}
+
+/// A source of recent blocks, such as the REST API of a node.
+#[async_trait]
+pub trait BlockSource: Send + Sync {
+    /// Returns the priority fees paid by the transactions in the given number of most recent blocks.
+    async fn recent_fees(&self, blocks: u32) -> Result<Vec<FeeSample>>;
+}
+
+/// Reads recent blocks from the REST API of a node, e.g. `http://localhost:3033/testnet3`.
+pub struct NodeBlockSource {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl NodeBlockSource {
+    /// Returns a source that reads from the node API at the given URL.
+    /// Requests to the node fail after `NODE_CONNECT_TIMEOUT` and `NODE_REQUEST_TIMEOUT`.
+    pub fn new(base_url: &str) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .connect_timeout(NODE_CONNECT_TIMEOUT)
+            .timeout(NODE_REQUEST_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http,
+        })
+    }
+
+    // Returns the JSON body of a GET request to the node.
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self
+            .http
+            .get(format!("{}{path}", self.base_url))
+            .send()
+            .await?;
+        ensure!(
+            response.status().is_success(),
+            "The node returned status {} for '{path}'",
+            response.status()
+        );
+        Ok(response.json().await?)
+    }
+}
+
+#[async_trait]
+impl BlockSource for NodeBlockSource {
+    async fn recent_fees(&self, blocks: u32) -> Result<Vec<FeeSample>> {
+        let height = self.get::<u32>("/latest/height").await?;
+        // The node's block ranges exclude their end.
+        let end = height.saturating_add(1);
+        let mut start = end.saturating_sub(blocks);
+        let mut samples = Vec::new();
+        while start < end {
+            let chunk_end = end.min(start.saturating_add(MAX_BLOCKS_PER_REQUEST));
+            let chunk = self
+                .get::<Vec<Block<CurrentNetwork>>>(&format!(
+                    "/blocks?start={start}&end={chunk_end}"
+                ))
+                .await?;
+            for block in &chunk {
+                samples.extend(block_fees(block));
+            }
+            start = chunk_end;
+        }
+        Ok(samples)
+    }
+}
+
+/// Returns the priority fees paid by the transactions in a block, including rejected ones.
+pub fn block_fees<N: Network>(block: &Block<N>) -> Vec<FeeSample> {
+    block
+        .transactions()
+        .iter()
+        .filter_map(|confirmed| {
+            let transaction = confirmed.transaction();
+            let fee = transaction.fee_transition()?;
+            let priority_fee = fee.priority_amount().ok()?;
+            Some(FeeSample {
+                function: credits_function(transaction),
+                priority_fee_in_microcredits: *priority_fee,
+            })
+        })
+        .collect()
+}
+
+// Returns the function in `credits.aleo` that a transaction executed, if any.
+fn credits_function<N: Network>(transaction: &Transaction<N>) -> Option<String> {
+    transaction
+        .execution()?
+        .transitions()
+        .find_map(|transition| {
+            let function = transition.function_name().to_string();
+            (transition.program_id().to_string() == "credits.aleo"
+                && FEE_FUNCTIONS.contains(&function.as_str()))
+            .then_some(function)
+        })
+}
+
+/// The suggested priority fees, in microcredits, from the fees paid in recent blocks.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeSuggestion {
+    /// The function that the fees were paid for, or unset if they were paid for every function.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    /// The number of transactions that the suggestion is based on.
+    pub samples: usize,
+    /// The 25th percentile of the fees.
+    pub low: u64,
+    /// The median of the fees.
+    pub medium: u64,
+    /// The 90th percentile of the fees.
+    pub high: u64,
+}
+
+impl FeeSuggestion {
+    /// Returns the suggestion for the given fees. If there are no fees, every suggestion is zero.
+    pub fn from_fees(function: Option<String>, mut fees: Vec<u64>) -> Self {
+        fees.sort_unstable();
+        Self {
+            function,
+            samples: fees.len(),
+            low: percentile(&fees, 25),
+            medium: percentile(&fees, 50),
+            high: percentile(&fees, 90),
+        }
+    }
+}
+
+// Returns the nearest-rank percentile of sorted values, or zero if there are none.
+fn percentile(sorted: &[u64], percent: usize) -> u64 {
+    match sorted.len() {
+        0 => 0,
+        length => sorted[((length * percent + 99) / 100).max(1) - 1],
+    }
+}
+
+/// The query of a fee suggestion.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct FeeQuery {
+    /// The function in `credits.aleo` to suggest a fee for. If unset, the fees of every transaction are used.
+    #[serde(default)]
+    pub function: Option<String>,
+}
+
+/// Suggests priority fees from the fees paid in recent blocks.
+/// The samples are cached, so that the node is read at most once per refresh interval.
+/// While one request reads the node, the others are served the stale samples.
+pub struct FeeOracle(Option<OpenFeeOracle>);
+
+// The fee samples of the most recent blocks, which are shared by the requests that use them.
+type Samples = Arc<Vec<FeeSample>>;
+
+struct OpenFeeOracle {
+    source: Box<dyn BlockSource>,
+    blocks: u32,
+    refresh: Duration,
+    // The samples of the last successful read, and when it was made.
+    // Stale samples are used while the node is read, and if it fails.
+    cache: Mutex<Option<(Instant, Samples)>>,
+    // Held by the request that reads the node, so that the node is read once at a time.
+    reading: tokio::sync::Mutex<()>,
+}
+
+impl FeeOracle {
+    /// Returns an oracle that rejects every suggestion with `NotReady`, as no node is configured.
+    pub fn disabled() -> Self {
+        Self(None)
+    }
+
+    /// Returns an oracle that reads the given number of recent blocks from the source, at most once per refresh interval.
+    pub fn new(source: Box<dyn BlockSource>, blocks: u32, refresh: Duration) -> Self {
+        Self(Some(OpenFeeOracle {
+            source,
+            blocks,
+            refresh,
+            cache: Mutex::new(None),
+            reading: tokio::sync::Mutex::new(()),
+        }))
+    }
+
+    /// Suggests priority fees for the given function, or for every transaction if it is unset.
+    /// If no recent transaction executed the function, the fees of every transaction are used.
+    pub async fn suggest(&self, function: Option<&str>) -> Result<FeeSuggestion, ServiceError> {
+        let oracle = self.0.as_ref().ok_or_else(|| {
+            ServiceError::NotReady("The fee oracle is not configured".to_string())
+        })?;
+        if let Some(function) = function {
+            if !FEE_FUNCTIONS.contains(&function) {
+                return Err(ServiceError::Validation(format!(
+                    "'{function}' is not a function in 'credits.aleo'"
+                )));
+            }
+        }
+        let samples = oracle.samples().await?;
+        let fees_of = |function: Option<&str>| {
+            samples
+                .iter()
+                .filter(|sample| function.is_none() || sample.function.as_deref() == function)
+                .map(|sample| sample.priority_fee_in_microcredits)
+                .collect::<Vec<_>>()
+        };
+        let fees = fees_of(function);
+        Ok(match fees.is_empty() {
+            true => FeeSuggestion::from_fees(None, fees_of(None)),
+            false => FeeSuggestion::from_fees(function.map(str::to_string), fees),
+        })
+    }
+}
+
+impl OpenFeeOracle {
+    // Returns the cached samples, and whether they are fresh.
+    fn cached(&self) -> Result<Option<(bool, Samples)>, ServiceError> {
+        let cache = self
+            .cache
+            .lock()
+            .map_err(|_| ServiceError::Internal("The fee cache is poisoned".to_string()))?;
+        Ok(cache
+            .as_ref()
+            .map(|(read_at, samples)| (read_at.elapsed() < self.refresh, samples.clone())))
+    }
+
+    // Returns the cached samples, reading the node if they are stale.
+    async fn samples(&self) -> Result<Samples, ServiceError> {
+        let cached = self.cached()?;
+        if let Some((true, samples)) = cached {
+            return Ok(samples);
+        }
+        // A single request reads the node. While it does, the others use the stale samples,
+        // or wait for the read if there are none.
+        let _reading = match (self.reading.try_lock(), cached) {
+            (Ok(reading), _) => reading,
+            (Err(_), Some((_, samples))) => return Ok(samples),
+            (Err(_), None) => self.reading.lock().await,
+        };
+        // The node may have been read while this request waited.
+        let cached = self.cached()?;
+        if let Some((true, samples)) = cached {
+            return Ok(samples);
+        }
+        match self.source.recent_fees(self.blocks).await {
+            Ok(samples) => {
+                let samples = Arc::new(samples);
+                let mut cache = self
+                    .cache
+                    .lock()
+                    .map_err(|_| ServiceError::Internal("The fee cache is poisoned".to_string()))?;
+                *cache = Some((Instant::now(), samples.clone()));
+                Ok(samples)
+            }
+            Err(error) => match cached {
+                Some((_, samples)) => {
+                    log::warn!("Failed to read recent blocks, using stale fees: {error:#}");
+                    Ok(samples)
+                }
+                None => Err(ServiceError::Upstream(format!(
+                    "Failed to read recent blocks: {error}"
+                ))),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use warp::Filter;
+
+    // A block source that returns fixed samples, and counts its reads.
+    struct MockBlockSource {
+        samples: Vec<FeeSample>,
+        reads: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl BlockSource for MockBlockSource {
+        async fn recent_fees(&self, _blocks: u32) -> Result<Vec<FeeSample>> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            Ok(self.samples.clone())
+        }
+    }
+
+    // A block source whose reads after the first wait until they are released.
+    struct GatedBlockSource {
+        reads: Arc<AtomicUsize>,
+        gate: Arc<tokio::sync::Semaphore>,
+    }
+
+    #[async_trait]
+    impl BlockSource for GatedBlockSource {
+        async fn recent_fees(&self, _blocks: u32) -> Result<Vec<FeeSample>> {
+            let read = self.reads.fetch_add(1, Ordering::SeqCst) + 1;
+            if read > 1 {
+                self.gate.acquire().await?.forget();
+            }
+            Ok(vec![sample("transfer_public", read as u64)])
+        }
+    }
+
+    fn sample(function: &str, priority_fee_in_microcredits: u64) -> FeeSample {
+        FeeSample {
+            function: Some(function.to_string()),
+            priority_fee_in_microcredits,
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let suggestion = FeeSuggestion::from_fees(None, (1..=100).rev().collect());
+        assert_eq!(
+            (suggestion.low, suggestion.medium, suggestion.high),
+            (25, 50, 90)
+        );
+        let suggestion = FeeSuggestion::from_fees(None, vec![7]);
+        assert_eq!(
+            (suggestion.low, suggestion.medium, suggestion.high),
+            (7, 7, 7)
+        );
+        let suggestion = FeeSuggestion::from_fees(None, vec![]);
+        assert_eq!((suggestion.samples, suggestion.high), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_suggest() {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let mut samples = (1..=10)
+            .map(|fee| sample("transfer_public", fee * 100))
+            .collect::<Vec<_>>();
+        samples.push(sample("join", 5));
+        let oracle = FeeOracle::new(
+            Box::new(MockBlockSource {
+                samples,
+                reads: reads.clone(),
+            }),
+            10,
+            Duration::from_secs(60),
+        );
+
+        let suggestion = oracle.suggest(Some("transfer_public")).await.unwrap();
+        assert_eq!(suggestion.function.as_deref(), Some("transfer_public"));
+        assert_eq!(suggestion.samples, 10);
+        assert_eq!(
+            (suggestion.low, suggestion.medium, suggestion.high),
+            (300, 500, 900)
+        );
+
+        // Functions without recent transactions fall back to every transaction.
+        let suggestion = oracle.suggest(Some("split")).await.unwrap();
+        assert_eq!(suggestion.function, None);
+        assert_eq!(suggestion.samples, 11);
+
+        // The node is read once per refresh interval.
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+        // Unknown functions are rejected, and so is every request to a disabled oracle.
+        assert!(matches!(
+            oracle.suggest(Some("mint")).await,
+            Err(ServiceError::Validation(_))
+        ));
+        assert!(matches!(
+            FeeOracle::disabled().suggest(None).await,
+            Err(ServiceError::NotReady(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stale_fees_are_served_while_the_node_is_read() {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let gate = Arc::new(tokio::sync::Semaphore::new(0));
+        let oracle = Arc::new(FeeOracle::new(
+            Box::new(GatedBlockSource {
+                reads: reads.clone(),
+                gate: gate.clone(),
+            }),
+            10,
+            Duration::ZERO,
+        ));
+        assert_eq!(oracle.suggest(None).await.unwrap().medium, 1);
+
+        // The samples are always stale, so the next request reads the node, and waits for it.
+        let refresh = tokio::spawn({
+            let oracle = oracle.clone();
+            async move { oracle.suggest(None).await }
+        });
+        while reads.load(Ordering::SeqCst) < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // Meanwhile, other requests are served the stale samples, without reading the node again.
+        assert_eq!(oracle.suggest(None).await.unwrap().medium, 1);
+        assert_eq!(reads.load(Ordering::SeqCst), 2);
+
+        gate.add_permits(1);
+        assert_eq!(refresh.await.unwrap().unwrap().medium, 2);
+    }
+
+    #[tokio::test]
+    async fn test_node_block_source() {
+        // A mock node at height 120, which has no transactions.
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let recorded = ranges.clone();
+        let node = warp::path!("latest" / "height")
+            .map(|| warp::reply::json(&120))
+            .or(warp::path("blocks")
+                .and(warp::query::<std::collections::HashMap<String, u32>>())
+                .map(move |query: std::collections::HashMap<String, u32>| {
+                    recorded
+                        .lock()
+                        .unwrap()
+                        .push((query["start"], query["end"]));
+                    warp::reply::json(&Vec::<u8>::new())
+                }));
+        let (address, server) = warp::serve(node).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let source = NodeBlockSource::new(&format!("http://{address}")).unwrap();
+        assert!(source.recent_fees(60).await.unwrap().is_empty());
+        assert_eq!(*ranges.lock().unwrap(), vec![(61, 111), (111, 121)]);
+    }
+
+    #[tokio::test]
+    async fn test_fee_route() {
+        let oracle = FeeOracle::new(
+            Box::new(MockBlockSource {
+                samples: vec![sample("transfer_public", 10)],
+                reads: Default::default(),
+            }),
+            10,
+            Duration::from_secs(60),
+        );
+        let route = fee_route(
+            Arc::new(Authenticator::disabled()),
+            Arc::new(Limits::unlimited()),
+            Arc::new(oracle),
+        )
+        .recover(handle_rejection);
+
+        let response = warp::test::request()
+            .method("GET")
+            .path("/fee/suggest?function=transfer_public")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), 200);
+        let suggestion: FeeSuggestion = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(suggestion.medium, 10);
+
+        let response = warp::test::request()
+            .method("GET")
+            .path("/fee/suggest?function=mint")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), 422);
+    }
+}
@@ -1,10 +1,208 @@
 use super::*;
 
-use anyhow::Result;
+use anyhow::{anyhow, ensure, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use snarkvm::prelude::Environment;
-use snarkvm::prelude::Field;
+use snarkvm::prelude::{Address, Field, ViewKey};
+use std::collections::HashSet;
+use std::fmt;
+
+/// The minimum length of a seed, in bytes.
+pub const MIN_SEED_BYTES: usize = 32;
+/// The domain separator of seeded keys, so that they never collide with keys from other schemes.
+pub const SEED_DOMAIN: &[u8] = b"aleo-tools/keygen-seed/v1";
 
 pub fn private_key_from_seed<N: Network>(seed: &str) -> Result<PrivateKey<N>> {
     let seed = Field::new(<N as Environment>::Field::from_str(seed)?);
     PrivateKey::try_from(seed)
 }
+
+/// Returns `true` if the bytes are clearly not random: if they have few distinct values,
+/// repeat a shorter pattern, or step by a constant difference.
+/// Random seeds are never rejected in practice, but this cannot prove that a seed is random.
+pub fn is_low_complexity_seed(bytes: &[u8]) -> bool {
+    let distinct = bytes.iter().collect::<HashSet<_>>().len();
+    let periodic = (1..=bytes.len() / 2)
+        .any(|period| bytes.iter().skip(period).zip(bytes).all(|(a, b)| a == b));
+    let stepped = bytes
+        .windows(2)
+        .map(|pair| pair[1].wrapping_sub(pair[0]))
+        .collect::<HashSet<_>>()
+        .len()
+        <= 1;
+    distinct < MIN_SEED_BYTES / 2 || periodic || stepped
+}
+
+/// Returns the private key of a seed of at least `MIN_SEED_BYTES` random bytes, encoded as hex.
+/// The key is `SHA-256(SEED_DOMAIN || network_id || seed)`, truncated to 31 bytes,
+/// which is always less than the modulus of the field.
+pub fn private_key_from_strong_seed<N: Network>(seed: &str) -> Result<PrivateKey<N>> {
+    let bytes = hex::decode(seed).map_err(|_| anyhow!("The seed must be encoded as hex"))?;
+    ensure!(
+        bytes.len() >= MIN_SEED_BYTES,
+        "The seed must be at least {MIN_SEED_BYTES} bytes"
+    );
+    ensure!(
+        !is_low_complexity_seed(&bytes),
+        "The seed must be random bytes, not a pattern"
+    );
+    let digest = Sha256::new()
+        .chain_update(SEED_DOMAIN)
+        .chain_update(N::ID.to_le_bytes())
+        .chain_update(&bytes)
+        .finalize();
+    let mut field = [0u8; 32];
+    field[..31].copy_from_slice(&digest[..31]);
+    PrivateKey::try_from(Field::<N>::from_bytes_le(&field)?)
+}
+
+/// A request to generate an account. If the seed is unset, the account is generated from server-side entropy.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct KeygenRequest {
+    #[serde(default)]
+    pub seed: Option<String>,
+}
+
+impl fmt::Debug for KeygenRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeygenRequest")
+            .field("seed", &self.seed.as_ref().map(|_| Redacted))
+            .finish()
+    }
+}
+
+/// A private key, with its view key and address.
+/// The keys are redacted when the account is printed, and zeroed when it is dropped.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct KeygenResponse<N: Network> {
+    pub private_key: PrivateKey<N>,
+    pub view_key: ViewKey<N>,
+    pub address: Address<N>,
+}
+
+impl<N: Network> fmt::Debug for KeygenResponse<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeygenResponse")
+            .field("private_key", &Redacted)
+            .field("view_key", &Redacted)
+            .field("address", &self.address)
+            .finish()
+    }
+}
+
+impl<N: Network> Drop for KeygenResponse<N> {
+    fn drop(&mut self) {
+        zeroize_private_key(&mut self.private_key);
+        zeroize_view_key(&mut self.view_key);
+    }
+}
+
+impl<N: Network> KeygenResponse<N> {
+    /// Returns the view key and address of the private key.
+    pub fn new(private_key: PrivateKey<N>) -> Result<Self> {
+        Ok(Self {
+            private_key,
+            view_key: ViewKey::try_from(&private_key)?,
+            address: Address::try_from(&private_key)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::Arc;
+    use warp::Filter;
+
+    const SEED: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn test_strong_seed() {
+        private_key_from_strong_seed::<CurrentNetwork>(SEED).unwrap();
+        // The seed is too short, or not hex.
+        assert!(private_key_from_strong_seed::<CurrentNetwork>("01").is_err());
+        assert!(private_key_from_strong_seed::<CurrentNetwork>(&SEED[2..]).is_err());
+        assert!(private_key_from_strong_seed::<CurrentNetwork>(&"zz".repeat(32)).is_err());
+        // Decimal seeds that are long enough to be hex are patterns.
+        assert!(private_key_from_strong_seed::<CurrentNetwork>(&"1234567890".repeat(7)).is_err());
+        // The seed is a pattern.
+        assert!(private_key_from_strong_seed::<CurrentNetwork>(&"00".repeat(32)).is_err());
+        let counting = (0..32u8)
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        assert!(private_key_from_strong_seed::<CurrentNetwork>(&counting).is_err());
+        let repeated = SEED[..32].repeat(3);
+        assert!(private_key_from_strong_seed::<CurrentNetwork>(&repeated).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_post_keygen() {
+        let route = post_keygen_route(
+            Arc::new(Authenticator::disabled()),
+            Arc::new(Limits::unlimited()),
+        )
+        .recover(handle_rejection);
+
+        // Without a seed, the account is generated from server-side entropy.
+        let response = warp::test::request()
+            .method("POST")
+            .path("/keygen")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["cache-control"], "no-store");
+        let account: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        let private_key =
+            PrivateKey::<CurrentNetwork>::from_str(account["private_key"].as_str().unwrap())
+                .unwrap();
+        assert_eq!(
+            account["address"],
+            Address::try_from(&private_key).unwrap().to_string()
+        );
+        assert_eq!(
+            account["view_key"],
+            ViewKey::try_from(&private_key).unwrap().to_string()
+        );
+
+        // A strong seed always generates the same account, and a weak one is rejected.
+        let response = warp::test::request()
+            .method("POST")
+            .path("/keygen")
+            .json(&serde_json::json!({ "seed": SEED }))
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), 200);
+        let account: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(
+            account["private_key"],
+            private_key_from_strong_seed::<CurrentNetwork>(SEED)
+                .unwrap()
+                .to_string()
+        );
+        let response = warp::test::request()
+            .method("POST")
+            .path("/keygen")
+            .json(&serde_json::json!({ "seed": "1234567890".repeat(4) }))
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), 422);
+    }
+
+    #[tokio::test]
+    async fn test_get_keygen_is_deprecated() {
+        let route = keygen_route(
+            Arc::new(Authenticator::disabled()),
+            Arc::new(Limits::unlimited()),
+        );
+        let response = warp::test::request()
+            .method("GET")
+            .path("/keygen/1234")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["deprecation"], "true");
+    }
+}
@@ -0,0 +1,533 @@
+use super::*;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use snarkvm::prelude::Address;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+// The version of the keystore file.
+const KEYSTORE_VERSION: u16 = 1;
+// The plaintext that is sealed in the file, to check the password when the keystore is opened.
+const PASSWORD_CHECK: &[u8] = b"authorize-service keystore";
+// The maximum number of keys in the keystore.
+const MAX_KEYS: usize = 10_000;
+
+// The Argon2id parameters of new keystores.
+const DEFAULT_MEMORY_KIB: u32 = 19 * 1024;
+const DEFAULT_ITERATIONS: u32 = 2;
+const DEFAULT_PARALLELISM: u32 = 1;
+
+/// A request to import a private key into the keystore.
+/// The private key is redacted when the request is printed, and zeroed when it is dropped.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ImportKeyRequest<N: Network> {
+    pub private_key: PrivateKey<N>,
+}
+
+impl<N: Network> fmt::Debug for ImportKeyRequest<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImportKeyRequest")
+            .field("private_key", &Redacted)
+            .finish()
+    }
+}
+
+impl<N: Network> Drop for ImportKeyRequest<N> {
+    fn drop(&mut self) {
+        zeroize_private_key(&mut self.private_key);
+    }
+}
+
+/// A key in the keystore. The private key is never returned.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyInfo {
+    pub id: String,
+    pub address: Address<CurrentNetwork>,
+}
+
+// The parameters that derive the encryption key from the password.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct KdfParams {
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl KdfParams {
+    // Returns new parameters, with a random salt.
+    fn new() -> Self {
+        let mut salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        Self {
+            salt: hex::encode(salt),
+            memory_kib: DEFAULT_MEMORY_KIB,
+            iterations: DEFAULT_ITERATIONS,
+            parallelism: DEFAULT_PARALLELISM,
+        }
+    }
+
+    // Derives the cipher from the password, with Argon2id.
+    fn cipher(&self, password: &str) -> Result<XChaCha20Poly1305> {
+        let salt = hex::decode(&self.salt).context("The salt is not valid hex")?;
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|error| anyhow!("Invalid key derivation parameters: {error}"))?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password.as_bytes(), &salt, key.as_mut())
+            .map_err(|error| anyhow!("Failed to derive the keystore key: {error}"))?;
+        Ok(XChaCha20Poly1305::new(Key::from_slice(key.as_ref())))
+    }
+}
+
+// A value that is encrypted and authenticated with XChaCha20-Poly1305.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Sealed {
+    nonce: String,
+    ciphertext: String,
+}
+
+impl Sealed {
+    // Encrypts the plaintext, binding it to the associated data.
+    fn seal(cipher: &XChaCha20Poly1305, plaintext: &[u8], aad: &[u8]) -> Result<Self> {
+        let mut nonce = [0u8; 24];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad,
+                },
+            )
+            .map_err(|_| anyhow!("Failed to encrypt the key"))?;
+        Ok(Self {
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    // Decrypts the plaintext, which fails if the password or the associated data is wrong.
+    fn open(&self, cipher: &XChaCha20Poly1305, aad: &[u8]) -> Result<SecretBytes> {
+        let nonce = hex::decode(&self.nonce).context("The nonce is not valid hex")?;
+        ensure!(nonce.len() == 24, "The nonce must be 24 bytes");
+        let ciphertext =
+            hex::decode(&self.ciphertext).context("The ciphertext is not valid hex")?;
+        cipher
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad,
+                },
+            )
+            .map(SecretBytes::new)
+            .map_err(|_| anyhow!("Failed to decrypt the key"))
+    }
+}
+
+// A private key in the keystore file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct StoredKey {
+    id: String,
+    address: Address<CurrentNetwork>,
+    // The caller that imported the key, which is the only caller that may use it.
+    owner: Option<String>,
+    #[serde(flatten)]
+    sealed: Sealed,
+}
+
+impl StoredKey {
+    // Returns the associated data of the key, which binds its ciphertext to its ID, address and owner.
+    fn aad(id: &str, address: &Address<CurrentNetwork>, owner: Option<&str>) -> Vec<u8> {
+        format!("{id}\n{address}\n{}", owner.unwrap_or_default()).into_bytes()
+    }
+
+    fn info(&self) -> KeyInfo {
+        KeyInfo {
+            id: self.id.clone(),
+            address: self.address,
+        }
+    }
+}
+
+// The keystore file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct KeystoreFile {
+    version: u16,
+    kdf: KdfParams,
+    check: Sealed,
+    keys: Vec<StoredKey>,
+}
+
+// An open keystore.
+struct OpenKeystore {
+    path: PathBuf,
+    cipher: XChaCha20Poly1305,
+    file: Mutex<KeystoreFile>,
+}
+
+/// Holds private keys on behalf of callers, in a file that is encrypted with a password.
+/// Keys are encrypted individually, and are only decrypted to authorize a request, into locked memory that is zeroed after use.
+/// Each key belongs to the caller that imported it, and is hidden from every other caller.
+pub struct Keystore(Option<OpenKeystore>);
+
+impl Keystore {
+    /// Returns a keystore that is not configured, which rejects every operation.
+    pub fn disabled() -> Self {
+        Self(None)
+    }
+
+    /// Opens the keystore at the given path, or creates it if it does not exist.
+    /// Returns an error if the password is wrong.
+    pub fn open(path: &Path, password: &str) -> Result<Self> {
+        ensure!(!password.is_empty(), "The keystore password is empty");
+
+        let (file, cipher) = match path.exists() {
+            true => {
+                let contents = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read '{}'", path.display()))?;
+                let file: KeystoreFile = serde_json::from_str(&contents)
+                    .with_context(|| format!("Failed to parse '{}'", path.display()))?;
+                ensure!(
+                    file.version == KEYSTORE_VERSION,
+                    "Unsupported keystore version {}",
+                    file.version
+                );
+                let cipher = file.kdf.cipher(password)?;
+                file.check
+                    .open(&cipher, &[])
+                    .map_err(|_| anyhow!("The keystore password is wrong"))?;
+                (file, cipher)
+            }
+            false => {
+                let kdf = KdfParams::new();
+                let cipher = kdf.cipher(password)?;
+                let file = KeystoreFile {
+                    version: KEYSTORE_VERSION,
+                    kdf,
+                    check: Sealed::seal(&cipher, PASSWORD_CHECK, &[])?,
+                    keys: vec![],
+                };
+                write_keystore(path, &file)?;
+                (file, cipher)
+            }
+        };
+
+        Ok(Self(Some(OpenKeystore {
+            path: path.to_path_buf(),
+            cipher,
+            file: Mutex::new(file),
+        })))
+    }
+
+    /// Returns `true` if the keystore is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Imports a private key for the owner, and returns its key.
+    /// If the owner already holds the private key, its existing key is returned.
+    pub fn import(
+        &self,
+        private_key: &PrivateKey<CurrentNetwork>,
+        owner: Option<&str>,
+    ) -> Result<KeyInfo, ServiceError> {
+        let keystore = self.open_keystore()?;
+        let address = Address::try_from(private_key)
+            .map_err(|error| ServiceError::Validation(error.to_string()))?;
+        let mut file = keystore.lock()?;
+
+        if let Some(key) = file
+            .keys
+            .iter()
+            .find(|key| key.address == address && key.owner.as_deref() == owner)
+        {
+            return Ok(key.info());
+        }
+        if file.keys.len() >= MAX_KEYS {
+            return Err(ServiceError::Validation("The keystore is full".to_string()));
+        }
+
+        // Seal the private key under a new ID.
+        let mut id = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut id);
+        let id = hex::encode(id);
+        let plaintext = private_key
+            .to_bytes_le()
+            .map(SecretBytes::new)
+            .map_err(|error| ServiceError::Internal(error.to_string()))?;
+        let sealed = Sealed::seal(
+            &keystore.cipher,
+            plaintext.as_ref(),
+            &StoredKey::aad(&id, &address, owner),
+        )
+        .map_err(|error| ServiceError::Internal(error.to_string()))?;
+        let key = StoredKey {
+            id,
+            address,
+            owner: owner.map(str::to_string),
+            sealed,
+        };
+
+        // Persist the keystore, and only then keep the key.
+        let info = key.info();
+        file.keys.push(key);
+        if let Err(error) = write_keystore(&keystore.path, &file) {
+            file.keys.pop();
+            return Err(ServiceError::Internal(format!(
+                "Failed to write the keystore: {error}"
+            )));
+        }
+        Ok(info)
+    }
+
+    /// Generates a new private key for the owner, and returns its key.
+    pub fn generate(&self, owner: Option<&str>) -> Result<KeyInfo, ServiceError> {
+        let private_key = PrivateKey::new(&mut rand::thread_rng())
+            .map_err(|error| ServiceError::Internal(error.to_string()))?;
+        self.import(&private_key, owner)
+    }
+
+    /// Returns the keys of the owner.
+    pub fn list(&self, owner: Option<&str>) -> Result<Vec<KeyInfo>, ServiceError> {
+        let keystore = self.open_keystore()?;
+        let file = keystore.lock()?;
+        Ok(file
+            .keys
+            .iter()
+            .filter(|key| key.owner.as_deref() == owner)
+            .map(StoredKey::info)
+            .collect())
+    }
+
+    /// Deletes a key of the owner.
+    pub fn delete(&self, id: &str, owner: Option<&str>) -> Result<(), ServiceError> {
+        let keystore = self.open_keystore()?;
+        let mut file = keystore.lock()?;
+        let index = file
+            .keys
+            .iter()
+            .position(|key| key.id == id && key.owner.as_deref() == owner)
+            .ok_or_else(|| key_not_found(id))?;
+        let key = file.keys.remove(index);
+        if let Err(error) = write_keystore(&keystore.path, &file) {
+            file.keys.insert(index, key);
+            return Err(ServiceError::Internal(format!(
+                "Failed to write the keystore: {error}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Decrypts the private key of a key of the owner.
+    pub fn private_key(
+        &self,
+        id: &str,
+        owner: Option<&str>,
+    ) -> Result<PrivateKey<CurrentNetwork>, ServiceError> {
+        let keystore = self.open_keystore()?;
+        let file = keystore.lock()?;
+        let key = file
+            .keys
+            .iter()
+            .find(|key| key.id == id && key.owner.as_deref() == owner)
+            .ok_or_else(|| key_not_found(id))?;
+        let plaintext = key
+            .sealed
+            .open(
+                &keystore.cipher,
+                &StoredKey::aad(&key.id, &key.address, owner),
+            )
+            .map_err(|error| ServiceError::Internal(error.to_string()))?;
+        PrivateKey::from_bytes_le(plaintext.as_ref())
+            .map_err(|error| ServiceError::Internal(error.to_string()))
+    }
+
+    fn open_keystore(&self) -> Result<&OpenKeystore, ServiceError> {
+        self.0
+            .as_ref()
+            .ok_or_else(|| ServiceError::Forbidden("The keystore is not configured".to_string()))
+    }
+}
+
+impl OpenKeystore {
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, KeystoreFile>, ServiceError> {
+        self.file
+            .lock()
+            .map_err(|_| ServiceError::Internal("The keystore is poisoned".to_string()))
+    }
+}
+
+fn key_not_found(id: &str) -> ServiceError {
+    ServiceError::KeyNotFound(format!("The key '{id}' does not exist"))
+}
+
+// Writes the keystore to a temporary file, which replaces the keystore once it is complete.
+// The file is only readable by its owner.
+fn write_keystore(path: &Path, file: &KeystoreFile) -> Result<()> {
+    let contents = serde_json::to_vec_pretty(file)?;
+    let temporary = path.with_extension("tmp");
+    {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut writer = options
+            .open(&temporary)
+            .with_context(|| format!("Failed to create '{}'", temporary.display()))?;
+        std::io::Write::write_all(&mut writer, &contents)?;
+        writer.sync_all()?;
+    }
+    std::fs::rename(&temporary, path)
+        .with_context(|| format!("Failed to replace '{}'", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use warp::Filter;
+
+    const PRIVATE_KEY: &str = "APrivateKey1zkpCE9rCw9SixY82xaDrW2Hwxc2f3VjeuR2oZHR81zcuUDV";
+
+    // Returns a path for a keystore that does not exist yet.
+    fn keystore_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "authorize-service-{name}-{}.json",
+            rand::random::<u64>()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_import_and_reopen() {
+        let path = keystore_path("reopen");
+        let private_key = PrivateKey::<CurrentNetwork>::from_str(PRIVATE_KEY).unwrap();
+
+        // Import a key, and check that it is listed by address.
+        let keystore = Keystore::open(&path, "password").unwrap();
+        let info = keystore.import(&private_key, Some("alice")).unwrap();
+        assert_eq!(info.address, Address::try_from(&private_key).unwrap());
+        assert_eq!(keystore.list(Some("alice")).unwrap(), vec![info.clone()]);
+
+        // The file does not contain the private key.
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains(PRIVATE_KEY));
+
+        // The key survives a restart, and only opens with the right password.
+        assert!(Keystore::open(&path, "wrong password").is_err());
+        let keystore = Keystore::open(&path, "password").unwrap();
+        assert_eq!(
+            keystore.private_key(&info.id, Some("alice")).unwrap(),
+            private_key
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_keys_belong_to_their_owner() {
+        let path = keystore_path("owner");
+        let keystore = Keystore::open(&path, "password").unwrap();
+        let info = keystore.generate(Some("alice")).unwrap();
+
+        // Other callers can neither see, use nor delete the key.
+        assert!(keystore.list(Some("bob")).unwrap().is_empty());
+        assert!(keystore.list(None).unwrap().is_empty());
+        assert!(keystore.private_key(&info.id, Some("bob")).is_err());
+        assert!(keystore.delete(&info.id, Some("bob")).is_err());
+
+        // The owner can delete the key.
+        keystore.delete(&info.id, Some("alice")).unwrap();
+        assert!(keystore.private_key(&info.id, Some("alice")).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_keys_routes() {
+        let path = keystore_path("routes");
+        let keystore = Arc::new(Keystore::open(&path, "password").unwrap());
+        let authenticator = Arc::new(Authenticator::disabled());
+        let limits = Arc::new(Limits::unlimited());
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let workers = Arc::new(
+            WorkerPool::new(1, Duration::from_secs(10), metrics.queue_depth.clone()).unwrap(),
+        );
+        let routes = keys_routes(authenticator.clone(), limits.clone(), keystore.clone())
+            .or(authorize_routes(AuthorizeState {
+                authenticator,
+                limits,
+                metrics,
+                process: SharedProcess::default(),
+                workers,
+                keystore,
+                idempotency: Arc::new(IdempotencyStore::new(Duration::from_secs(60))),
+                audit: Arc::new(AuditLog::disabled()),
+                policies: Arc::new(PolicyEngine::disabled()),
+            }))
+            .recover(handle_rejection);
+
+        // Import a key, and list it.
+        let response = warp::test::request()
+            .method("POST")
+            .path("/keys")
+            .header("content-type", "application/json")
+            .body(serde_json::json!({ "private_key": PRIVATE_KEY }).to_string())
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), 201);
+        let key: KeyInfo = serde_json::from_slice(response.body()).unwrap();
+        let response = warp::test::request()
+            .method("GET")
+            .path("/keys")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), 200);
+        let keys: Vec<KeyInfo> = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(keys, vec![key.clone()]);
+        assert!(!String::from_utf8_lossy(response.body()).contains(PRIVATE_KEY));
+
+        // Delete the key. Requests that refer to it are then rejected.
+        let response = warp::test::request()
+            .method("DELETE")
+            .path(&format!("/keys/{}", key.id))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), 204);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/authorize")
+            .header("content-type", "application/json")
+            .body(
+                serde_json::json!({
+                    "key_id": key.id,
+                    "recipient": key.address.to_string(),
+                    "amount_in_microcredits": 100,
+                    "priority_fee_in_microcredits": 10,
+                })
+                .to_string(),
+            )
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), 404);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "key_not_found");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,9 +1,48 @@
+pub mod audit;
+pub use audit::*;
+
+pub mod auth;
+pub use auth::*;
+
 pub mod authorize;
 pub use authorize::*;
 
+pub mod config;
+pub use config::*;
+
+pub mod credits;
+pub use credits::*;
+
+pub mod error;
+pub use error::*;
+
+pub mod fee;
+pub use fee::*;
+
 pub mod keygen;
 pub use keygen::*;
 
+pub mod keystore;
+pub use keystore::*;
+
+pub mod limits;
+pub use limits::*;
+
+pub mod metrics;
+pub use metrics::*;
+
+pub mod mnemonic;
+pub use mnemonic::*;
+
+pub mod openapi;
+pub use openapi::*;
+
+pub mod policy;
+pub use policy::*;
+
+pub mod process;
+pub use process::*;
+
 pub mod request;
 pub use request::*;
 
@@ -13,7 +52,10 @@ pub use response::*;
 pub mod routes;
 pub use routes::*;
 
-use snarkvm::prelude::{FromBytes, Network, PrivateKey, Testnet3, ToBytes};
-use std::str::FromStr;
+pub mod secret;
+pub use secret::*;
 
-pub type CurrentNetwork = Testnet3;
+pub use service_common::*;
+
+use snarkvm::prelude::{FromBytes, Network, PrivateKey, ToBytes};
+use std::str::FromStr;
@@ -0,0 +1,253 @@
+use super::*;
+
+use bytes::{Buf, BufMut, BytesMut};
+use futures_util::{Stream, StreamExt};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use warp::hyper::body::Bytes;
+use warp::{Filter, Rejection};
+
+/// The maximum size of a request body, in bytes, unless `max_body_bytes` is configured.
+pub const DEFAULT_MAX_BODY_BYTES: u64 = 1024; // 1 kilobyte
+
+/// Returns the maximum size of a request body, in bytes.
+pub fn max_body_bytes(limits: &Limits) -> u64 {
+    limits.max_body_bytes().unwrap_or(DEFAULT_MAX_BODY_BYTES)
+}
+
+/// Reads the request body, up to the given number of bytes.
+/// Unlike `warp::body::content_length_limit`, requests without a content length are accepted, such as GET requests,
+/// and their bodies are limited as they are read.
+pub fn limited_body(
+    max_body_bytes: u64,
+) -> impl Filter<Extract = (Bytes,), Error = Rejection> + Clone {
+    warp::header::optional::<u64>("content-length")
+        .and(warp::body::stream())
+        .and_then(move |length, body| read_limited_body(length, body, max_body_bytes))
+}
+
+// Reads a body of at most `max_body_bytes`, rejecting it as soon as it is larger.
+async fn read_limited_body<B: Buf>(
+    length: Option<u64>,
+    body: impl Stream<Item = Result<B, warp::Error>>,
+    max_body_bytes: u64,
+) -> Result<Bytes, Rejection> {
+    let too_large = || {
+        warp::reject::custom(ServiceError::PayloadTooLarge(format!(
+            "The request body is larger than {max_body_bytes} bytes"
+        )))
+    };
+    if length.map_or(false, |length| length > max_body_bytes) {
+        return Err(too_large());
+    }
+    let mut body = Box::pin(body);
+    let mut bytes = BytesMut::new();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|error| {
+            warp::reject::custom(ServiceError::Decode(format!(
+                "Failed to read the request body: {error}"
+            )))
+        })?;
+        if (bytes.len() + chunk.remaining()) as u64 > max_body_bytes {
+            return Err(too_large());
+        }
+        bytes.put(chunk);
+    }
+    Ok(bytes.freeze())
+}
+
+/// Limits the rate and concurrency of requests to the named route.
+/// Requests are rate limited by their IP address before they are authenticated, so that credentials are not verified without limit.
+/// Once authenticated, callers with credentials are rate limited by their key ID or Aleo address, and anonymous callers by their IP address.
+/// The extracted permit must be moved into the future of the handler, so that it is held until the request completes.
+/// A permit that is only taken by the handler closure is dropped before the request is handled.
+pub fn with_limits(
+    limits: Arc<Limits>,
+    route: &'static str,
+    authenticated: impl Filter<Extract = (Caller, Bytes), Error = Rejection> + Clone + Send + Sync,
+) -> impl Filter<Extract = (Caller, Bytes, Permit), Error = Rejection> + Clone {
+    let ip_limits = limits.clone();
+    warp::addr::remote()
+        .and_then(move |remote: Option<SocketAddr>| {
+            let limits = ip_limits.clone();
+            async move {
+                match limits.check_ip_rate(remote.map(|remote| remote.ip()), Instant::now()) {
+                    Ok(()) => Ok(remote),
+                    Err(error) => Err(warp::reject::custom(ServiceError::from(error))),
+                }
+            }
+        })
+        .and(authenticated)
+        .and_then(
+            move |remote: Option<SocketAddr>, caller: Caller, body: Bytes| {
+                let limits = limits.clone();
+                async move {
+                    let client = match (caller.key_id(), remote) {
+                        (Some(key_id), _) => key_id,
+                        (None, Some(remote)) => remote.ip().to_string(),
+                        (None, None) => "unknown".to_string(),
+                    };
+                    if let Err(error) = limits.check_rate(&client, Instant::now()) {
+                        return Err(warp::reject::custom(ServiceError::from(error)));
+                    }
+                    match limits.acquire(route) {
+                        Ok(permit) => Ok((caller, body, permit)),
+                        Err(error) => Err(warp::reject::custom(ServiceError::from(error))),
+                    }
+                }
+            },
+        )
+        .untuple_one()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn test_too_many_requests_response() {
+        let limits = Arc::new(Limits::new(LimitsConfig {
+            requests_per_second: Some(0.5),
+            burst: 1,
+            ..LimitsConfig::default()
+        }));
+        let route =
+            keygen_route(Arc::new(Authenticator::disabled()), limits).recover(handle_rejection);
+
+        let response = warp::test::request()
+            .method("GET")
+            .path("/keygen/1234")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = warp::test::request()
+            .method("GET")
+            .path("/keygen/1234")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "2");
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "too_many_requests");
+    }
+
+    #[tokio::test]
+    async fn test_ip_rate_limit_comes_before_authentication() {
+        let authenticator = Authenticator::new(
+            toml::from_str(
+                r#"
+                [api_keys]
+                operator = "operator-key"
+                "#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let limits = Arc::new(Limits::new(LimitsConfig {
+            ip_requests_per_second: Some(0.5),
+            ip_burst: 1,
+            ..LimitsConfig::default()
+        }));
+        let route = keygen_route(Arc::new(authenticator), limits).recover(handle_rejection);
+        let request = |key: &str, ip: [u8; 4]| {
+            warp::test::request()
+                .method("GET")
+                .path("/keygen/1234")
+                .remote_addr(SocketAddr::from((ip, 4000)))
+                .header(API_KEY_HEADER, key)
+        };
+
+        // Once the bucket of the IP address is empty, its requests are rejected before their credentials are checked.
+        let response = request("wrong-key", [10, 0, 0, 1]).reply(&route).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = request("operator-key", [10, 0, 0, 1]).reply(&route).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "2");
+
+        // Other IP addresses have their own buckets.
+        let response = request("operator-key", [10, 0, 0, 2]).reply(&route).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_requests_hold_their_slots() {
+        let limits = Arc::new(Limits::new(LimitsConfig {
+            max_in_flight: [("authorize".to_string(), 1)].into_iter().collect(),
+            ..LimitsConfig::default()
+        }));
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let workers = Arc::new(
+            WorkerPool::new(1, Duration::from_secs(60), metrics.queue_depth.clone()).unwrap(),
+        );
+        let process = SharedProcess::default();
+        process.load().unwrap();
+        let routes = authorize_routes(AuthorizeState {
+            authenticator: Arc::new(Authenticator::disabled()),
+            limits,
+            metrics: metrics.clone(),
+            process,
+            workers: workers.clone(),
+            keystore: Arc::new(Keystore::disabled()),
+            idempotency: Arc::new(IdempotencyStore::new(Duration::from_secs(60))),
+            audit: Arc::new(AuditLog::disabled()),
+            policies: Arc::new(PolicyEngine::disabled()),
+        })
+        .recover(handle_rejection);
+        let request = || {
+            warp::test::request()
+                .method("POST")
+                .path("/authorize")
+                .header("content-type", "application/json")
+                .body(
+                    serde_json::json!({
+                        "private_key": "APrivateKey1zkpCE9rCw9SixY82xaDrW2Hwxc2f3VjeuR2oZHR81zcuUDV",
+                        "recipient": "aleo1rhgdu77hgyqd3xjj8ucu3jj9r2krwz6mnzyd80gncr5fxcwlh5rsvzp9px",
+                        "amount_in_microcredits": 100,
+                        "priority_fee_in_microcredits": 10,
+                    })
+                    .to_string(),
+                )
+        };
+
+        // Occupy the only worker, so that the first request waits in the queue with its slot.
+        let (started_sender, started) = std::sync::mpsc::channel();
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        let blocker = tokio::spawn({
+            let workers = workers.clone();
+            async move {
+                workers
+                    .run(move |_| {
+                        started_sender.send(()).unwrap();
+                        let _ = released.recv();
+                    })
+                    .await
+            }
+        });
+        started.recv().unwrap();
+        let first = tokio::spawn({
+            let routes = routes.clone();
+            let request = request();
+            async move { request.reply(&routes).await }
+        });
+        while metrics.queue_depth.get() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // The second request is limited while the first one is in flight.
+        let response = request().reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "too_many_requests");
+
+        // Dropping the first request cancels its job, which releases the slot once the worker skips it.
+        first.abort();
+        let _ = first.await;
+        release.send(()).unwrap();
+        blocker.await.unwrap().unwrap();
+    }
+}
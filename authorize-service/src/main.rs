@@ -1,26 +1,542 @@
 use authorize_service::*;
 
+use anyhow::{Context, Result};
+
+use std::future::Future;
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use structopt::StructOpt;
 use warp::Filter;
+use zeroize::Zeroizing;
+
+// The environment variable that holds the keystore password.
+const KEYSTORE_PASSWORD_VAR: &str = "AUTHORIZE_SERVICE_KEYSTORE_PASSWORD";
 
+/// The CLI flags, which override the configuration file and the environment variables.
 #[derive(StructOpt, Debug)]
 struct Opt {
-    #[structopt(short, long, default_value = "3030")]
-    port: u16,
+    /// The path to the configuration file (TOML).
+    /// Its settings are overridden by `AUTHORIZE_SERVICE_*` environment variables, and then by these flags.
+    #[structopt(long)]
+    config: Option<PathBuf>,
+    /// Prints the effective configuration, and exits.
+    #[structopt(long)]
+    print_config: bool,
+    /// The address to bind to [default: 127.0.0.1].
+    #[structopt(short, long)]
+    address: Option<IpAddr>,
+    /// The port to bind to [default: 3030].
+    #[structopt(short, long)]
+    port: Option<u16>,
+    /// The path to the PEM-encoded TLS certificate chain.
+    #[structopt(long)]
+    tls_cert: Option<PathBuf>,
+    /// The path to the PEM-encoded TLS private key.
+    #[structopt(long)]
+    tls_key: Option<PathBuf>,
+    /// The path to the PEM-encoded CA certificates that client certificates must be signed by.
+    /// If set, clients must present a valid certificate (mutual TLS).
+    #[structopt(long)]
+    tls_client_ca: Option<PathBuf>,
+    /// The path to the authentication configuration (TOML).
+    /// If unset, authentication is disabled.
+    #[structopt(long)]
+    auth_config: Option<PathBuf>,
+    /// The path to the rate and concurrency limits (TOML), which replaces the `limits` table of the configuration.
+    #[structopt(long)]
+    limits_config: Option<PathBuf>,
+    /// The path to the encrypted keystore, which is created if it does not exist.
+    /// The password is read from `--keystore-password-file`, or from `AUTHORIZE_SERVICE_KEYSTORE_PASSWORD`.
+    /// If unset, requests must include their private key.
+    #[structopt(long)]
+    keystore: Option<PathBuf>,
+    /// The path to a file that contains the keystore password.
+    #[structopt(long)]
+    keystore_password_file: Option<PathBuf>,
+    /// The path to the audit log of signed authorizations, which is created if it does not exist.
+    /// If unset, authorizations are not recorded.
+    #[structopt(long)]
+    audit_log: Option<PathBuf>,
+    /// The path to the signing policies (TOML), which are enforced before requests are signed.
+    /// If unset, every request that passes validation is signed.
+    #[structopt(long)]
+    policy_config: Option<PathBuf>,
+    /// The URL of the node API that fees are suggested from, e.g. `http://localhost:3033/testnet3`.
+    /// If unset, fees are not suggested.
+    #[structopt(long)]
+    fee_node: Option<String>,
+    /// The number of recent blocks that fees are suggested from [default: 20].
+    #[structopt(long)]
+    fee_blocks: Option<u32>,
+    /// The number of seconds for which the fees of recent blocks are cached [default: 30].
+    #[structopt(long)]
+    fee_refresh: Option<u64>,
+    /// The number of seconds for which the response of a request with an `Idempotency-Key` header is stored [default: 3600].
+    #[structopt(long)]
+    idempotency_ttl: Option<u64>,
+    /// The number of worker threads for authorizations. If zero, one per CPU [default: 0].
+    #[structopt(long)]
+    workers: Option<usize>,
+    /// The number of seconds after which an authorization times out [default: 30]. A running authorization keeps its worker until it completes.
+    #[structopt(long)]
+    job_timeout: Option<u64>,
+    /// The number of seconds for which the requests in flight may complete on shutdown, after which they are dropped [default: 30].
+    #[structopt(long)]
+    shutdown_timeout: Option<u64>,
+    /// The format of the log lines, `pretty` or `json` [default: pretty].
+    #[structopt(long)]
+    log_format: Option<LogFormat>,
+    /// The log filter, in the syntax of `RUST_LOG`. If unset, `RUST_LOG` is used.
+    #[structopt(long)]
+    log_filter: Option<String>,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+impl Opt {
+    // Loads the configuration, with the flags applied over the file and the environment.
+    fn load_config(&self) -> Result<ServiceConfig> {
+        let mut config =
+            ServiceConfig::load(self.config.as_deref(), |var| std::env::var(var).ok())?;
+        if let Some(address) = self.address {
+            config.address = address;
+        }
+        if let Some(port) = self.port {
+            config.port = port;
+        }
+        let paths = [
+            (&self.tls_cert, &mut config.tls_cert),
+            (&self.tls_key, &mut config.tls_key),
+            (&self.tls_client_ca, &mut config.tls_client_ca),
+            (&self.auth_config, &mut config.auth_config),
+            (&self.limits_config, &mut config.limits_config),
+            (&self.keystore, &mut config.keystore),
+            (
+                &self.keystore_password_file,
+                &mut config.keystore_password_file,
+            ),
+            (&self.audit_log, &mut config.audit_log),
+            (&self.policy_config, &mut config.policy_config),
+        ];
+        for (flag, setting) in paths {
+            if flag.is_some() {
+                setting.clone_from(flag);
+            }
+        }
+        if self.fee_node.is_some() {
+            config.fee_node.clone_from(&self.fee_node);
+        }
+        if let Some(fee_blocks) = self.fee_blocks {
+            config.fee_blocks = fee_blocks;
+        }
+        if let Some(fee_refresh) = self.fee_refresh {
+            config.fee_refresh = fee_refresh;
+        }
+        if let Some(idempotency_ttl) = self.idempotency_ttl {
+            config.idempotency_ttl = idempotency_ttl;
+        }
+        if let Some(workers) = self.workers {
+            config.workers = workers;
+        }
+        if let Some(job_timeout) = self.job_timeout {
+            config.job_timeout = job_timeout;
+        }
+        if let Some(shutdown_timeout) = self.shutdown_timeout {
+            config.shutdown_timeout = shutdown_timeout;
+        }
+        if let Some(log_format) = self.log_format {
+            config.log_format = log_format;
+        }
+        if self.log_filter.is_some() {
+            config.log_filter.clone_from(&self.log_filter);
+        }
+        config.resolve()
+    }
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Inspects an audit log, instead of serving.
+    Audit(AuditCommand),
+}
+
+#[derive(StructOpt, Debug)]
+enum AuditCommand {
+    /// Verifies the hash chain of an audit log, and prints its head.
+    /// Keep the head where the host of the log cannot change it, and pass it to later verifications to catch removed entries.
+    Verify {
+        /// The path to the audit log.
+        path: PathBuf,
+        /// The number of entries in a head that was kept elsewhere.
+        #[structopt(long, requires = "last-hash")]
+        entries: Option<usize>,
+        /// The last hash in a head that was kept elsewhere.
+        #[structopt(long, requires = "entries")]
+        last_hash: Option<String>,
+    },
+    /// Prints the entries of an audit log that match every given filter, one JSON object per line.
+    Search {
+        /// The path to the audit log.
+        path: PathBuf,
+        /// The caller, e.g. `hmac:wallet`.
+        #[structopt(long)]
+        caller: Option<String>,
+        /// The address of the signer.
+        #[structopt(long)]
+        signer: Option<String>,
+        /// The function in `credits.aleo`.
+        #[structopt(long)]
+        function: Option<String>,
+        /// The address of the recipient.
+        #[structopt(long)]
+        recipient: Option<String>,
+        /// The execution ID.
+        #[structopt(long)]
+        execution_id: Option<String>,
+        /// The earliest timestamp, in seconds since the Unix epoch.
+        #[structopt(long)]
+        since: Option<u64>,
+        /// The latest timestamp, in seconds since the Unix epoch.
+        #[structopt(long)]
+        until: Option<u64>,
+    },
+}
+
+// Runs an audit command.
+fn audit(command: AuditCommand) -> Result<()> {
+    match command {
+        AuditCommand::Verify {
+            path,
+            entries,
+            last_hash,
+        } => {
+            let head = entries
+                .zip(last_hash)
+                .map(|(entries, last_hash)| AuditSummary { entries, last_hash });
+            let summary = verify_audit_log(&path, head.as_ref())?;
+            println!(
+                "Verified {} entries, the last hash is {}",
+                summary.entries, summary.last_hash
+            );
+        }
+        AuditCommand::Search {
+            path,
+            caller,
+            signer,
+            function,
+            recipient,
+            execution_id,
+            since,
+            until,
+        } => {
+            let filter = AuditFilter {
+                caller,
+                signer,
+                function,
+                recipient,
+                execution_id,
+                since,
+                until,
+            };
+            for entry in search_audit_log(&path, &filter)? {
+                println!("{}", serde_json::to_string(&entry)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Reads the keystore password from the password file, or from the environment.
+// The password is zeroed once the keystore is open.
+fn keystore_password(config: &ServiceConfig) -> Result<Zeroizing<String>> {
+    match &config.keystore_password_file {
+        Some(path) => {
+            let password = Zeroizing::new(
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read '{}'", path.display()))?,
+            );
+            Ok(Zeroizing::new(
+                password.trim_end_matches(['\r', '\n']).to_string(),
+            ))
+        }
+        None => std::env::var(KEYSTORE_PASSWORD_VAR)
+            .map(Zeroizing::new)
+            .with_context(|| format!("{KEYSTORE_PASSWORD_VAR} is not set")),
+    }
+}
+
+// Initializes the logger, with the filter and format of the configuration.
+fn init_logger(config: &ServiceConfig) {
+    let mut builder = pretty_env_logger::formatted_builder();
+    if let Some(filters) = config
+        .log_filter
+        .clone()
+        .or_else(|| std::env::var("RUST_LOG").ok())
+    {
+        builder.parse_filters(&filters);
+    }
+    if config.log_format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64();
+            let line = serde_json::json!({
+                "timestamp": timestamp,
+                "level": record.level().to_string(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{line}")
+        });
+    }
+    builder.init();
 }
 
-async fn run(port: u16) {
-    pretty_env_logger::init();
+async fn run(config: ServiceConfig) {
+    init_logger(&config);
+
+    let authenticator = match &config.auth_config {
+        Some(path) => match Authenticator::load(path) {
+            Ok(authenticator) => authenticator,
+            Err(error) => {
+                log::error!("Failed to load the authentication configuration: {error:#}");
+                std::process::exit(1);
+            }
+        },
+        None => {
+            log::warn!("Authentication is disabled, anyone who can reach the service can call it");
+            Authenticator::disabled()
+        }
+    };
+    let authenticator = Arc::new(authenticator);
+
+    let limits = Arc::new(Limits::new(config.limits.clone()));
+
+    let keystore = match &config.keystore {
+        Some(path) => {
+            match keystore_password(&config).and_then(|password| Keystore::open(path, &password)) {
+                Ok(keystore) => keystore,
+                Err(error) => {
+                    log::error!("Failed to open the keystore: {error:#}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => Keystore::disabled(),
+    };
+    if keystore.is_enabled() && config.auth_config.is_none() {
+        log::warn!("The keystore is enabled without authentication, anyone who can reach the service can use its keys");
+    }
+    let keystore = Arc::new(keystore);
+
+    let audit_log = match &config.audit_log {
+        Some(path) => match AuditLog::open(path) {
+            Ok(audit_log) => audit_log,
+            Err(error) => {
+                log::error!("Failed to open the audit log: {error:#}");
+                std::process::exit(1);
+            }
+        },
+        None => AuditLog::disabled(),
+    };
+    let audit_log = Arc::new(audit_log);
+
+    let policies = match &config.policy_config {
+        Some(path) => match PolicyEngine::load(path) {
+            Ok(policies) => policies,
+            Err(error) => {
+                log::error!("Failed to load the signing policies: {error:#}");
+                std::process::exit(1);
+            }
+        },
+        None => PolicyEngine::disabled(),
+    };
+    // The daily limits count the authorizations of the last day in the audit log, so that they hold across restarts.
+    if policies.has_daily_limits() {
+        match &config.audit_log {
+            Some(path) => match policies.restore_spending(path) {
+                Ok(entries) => {
+                    log::info!("Restored {entries} authorizations against the daily limits")
+                }
+                Err(error) => {
+                    log::error!("Failed to restore the daily limits from the audit log: {error:#}");
+                    std::process::exit(1);
+                }
+            },
+            None => log::warn!(
+                "The daily limits are reset on restart, as the audit log is not configured"
+            ),
+        }
+    }
+    let policies = Arc::new(policies);
+
+    let fee_oracle = match &config.fee_node {
+        Some(url) => match NodeBlockSource::new(url) {
+            Ok(source) => FeeOracle::new(
+                Box::new(source),
+                config.fee_blocks,
+                Duration::from_secs(config.fee_refresh),
+            ),
+            Err(error) => {
+                log::error!("Failed to create the client of the fee node: {error:#}");
+                std::process::exit(1);
+            }
+        },
+        None => FeeOracle::disabled(),
+    };
+    let fee_oracle = Arc::new(fee_oracle);
+
+    let idempotency = Arc::new(IdempotencyStore::new(Duration::from_secs(
+        config.idempotency_ttl,
+    )));
+
+    let metrics = match Metrics::new() {
+        Ok(metrics) => Arc::new(metrics),
+        Err(error) => {
+            log::error!("Failed to register the metrics: {error:#}");
+            std::process::exit(1);
+        }
+    };
+
+    // Start the worker pool, which runs the authorizations off the async runtime.
+    let workers = match WorkerPool::new(
+        config.workers,
+        Duration::from_secs(config.job_timeout),
+        metrics.queue_depth.clone(),
+    ) {
+        Ok(workers) => Arc::new(workers),
+        Err(error) => {
+            log::error!("Failed to start the worker pool: {error:#}");
+            std::process::exit(1);
+        }
+    };
+    log::info!("Started {} workers", workers.threads());
+
+    // Load the process in the background. The service is ready once it is loaded.
+    let process = SharedProcess::default();
+    let loading = process.clone();
+    tokio::task::spawn_blocking(move || match loading.load() {
+        Ok(()) => log::info!("The process is loaded, the service is ready"),
+        Err(error) => {
+            log::error!("Failed to load the process: {error:#}");
+            std::process::exit(1);
+        }
+    });
 
-    let routes = keygen_route().or(authorize_route()).with(warp::trace(
-        |info| tracing::debug_span!("Debugging headers", headers = ?info.request_headers()),
-    ));
+    let routes = health_route()
+        .or(ready_route(process.clone()))
+        .or(version_route())
+        .or(openapi_route())
+        .or(challenge_route(authenticator.clone(), limits.clone()))
+        .or(metrics_route(authenticator.clone(), metrics.clone()))
+        .or(post_keygen_route(authenticator.clone(), limits.clone()))
+        .or(derive_route(authenticator.clone(), limits.clone()))
+        .or(keygen_route(authenticator.clone(), limits.clone()))
+        .or(fee_route(authenticator.clone(), limits.clone(), fee_oracle))
+        .or(keys_routes(
+            authenticator.clone(),
+            limits.clone(),
+            keystore.clone(),
+        ))
+        .or(authorize_routes(AuthorizeState {
+            authenticator,
+            limits,
+            metrics: metrics.clone(),
+            process,
+            workers,
+            keystore,
+            idempotency,
+            audit: audit_log,
+            policies,
+        }))
+        .recover(handle_rejection);
+    let routes = with_in_flight(&metrics, routes)
+        .with(with_metrics(metrics.clone()))
+        .with(warp::trace(
+            |info| tracing::debug_span!("Debugging headers", headers = ?info.request_headers()),
+        ));
 
-    warp::serve(routes).run(([127, 0, 0, 1], port)).await;
+    // On SIGTERM or Ctrl-C, stop accepting requests, and let the requests in flight complete until the deadline.
+    let shutdown = Shutdown::listen();
+    let signal = shutdown.clone().begun();
+    let deadline = Duration::from_secs(config.shutdown_timeout);
+    let address = SocketAddr::new(config.address, config.port);
+    // The server is boxed, as the servers with and without TLS have different types.
+    type BoxedServer = Pin<Box<dyn Future<Output = ()>>>;
+    let bound = match (config.tls_cert, config.tls_key) {
+        (Some(cert), Some(key)) => {
+            let server = warp::serve(routes).tls().cert_path(cert).key_path(key);
+            let server = match config.tls_client_ca {
+                Some(client_ca) => server.client_auth_required_path(client_ca),
+                None => server,
+            };
+            server
+                .try_bind_with_graceful_shutdown(address, signal)
+                .map(|(address, server)| (address, Box::pin(server) as BoxedServer))
+        }
+        _ => {
+            if !address.ip().is_loopback() {
+                log::warn!("Serving on {address} without TLS, requests are not encrypted");
+            }
+            warp::serve(routes)
+                .try_bind_with_graceful_shutdown(address, signal)
+                .map(|(address, server)| (address, Box::pin(server) as BoxedServer))
+        }
+    };
+    // Exit with an error if the address is taken, or the TLS files are invalid, instead of panicking.
+    let server = match bound {
+        Ok((address, server)) => {
+            log::info!("Listening on {address}");
+            server
+        }
+        Err(error) => {
+            log::error!("Failed to serve on {address}: {error}");
+            std::process::exit(1);
+        }
+    };
+    let dropped =
+        serve_until_drained(server, shutdown, deadline, || metrics.requests_in_flight()).await;
+    if dropped > 0 {
+        log::warn!("Shut down, dropping {dropped} requests in flight");
+    } else {
+        log::info!("Shut down, no requests were dropped");
+    }
 }
 
 #[tokio::main]
 async fn main() {
-    let opt = Opt::from_args();
-    run(opt.port).await;
+    let mut opt = Opt::from_args();
+    match opt.command.take() {
+        Some(Command::Audit(command)) => {
+            if let Err(error) = audit(command) {
+                eprintln!("{error:#}");
+                std::process::exit(1);
+            }
+        }
+        None => {
+            let config = match opt.load_config() {
+                Ok(config) => config,
+                Err(error) => {
+                    eprintln!("Invalid configuration: {error:#}");
+                    std::process::exit(1);
+                }
+            };
+            if opt.print_config {
+                match config.to_toml() {
+                    Ok(config) => print!("{config}"),
+                    Err(error) => {
+                        eprintln!("Failed to print the configuration: {error:#}");
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+            run(config).await
+        }
+    }
 }
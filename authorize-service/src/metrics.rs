@@ -0,0 +1,262 @@
+use super::*;
+
+use anyhow::Result;
+use prometheus::core::Collector;
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::convert::Infallible;
+use std::sync::Arc;
+use warp::hyper::body::Bytes;
+use warp::{Filter, Rejection, Reply};
+
+/// The metrics of the service, exposed at `/metrics` in the Prometheus text format.
+pub struct Metrics {
+    registry: Registry,
+    /// The number of requests, by route and status.
+    pub requests: IntCounterVec,
+    /// The time taken to authorize a function and its fee, by function.
+    pub authorization_duration: HistogramVec,
+    /// The number of requests in flight, by route.
+    pub in_flight: IntGaugeVec,
+    /// The number of jobs waiting for a worker.
+    pub queue_depth: IntGauge,
+}
+
+impl Metrics {
+    /// Returns a new set of metrics, registered in their own registry.
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("authorize_service".to_string()), None)?;
+
+        let requests = IntCounterVec::new(
+            Opts::new(
+                "requests_total",
+                "The number of requests, by route and status",
+            ),
+            &["route", "status"],
+        )?;
+        let authorization_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "authorization_duration_seconds",
+                "The time taken to authorize a function and its fee",
+            )
+            .buckets(exponential_buckets(0.005, 2.0, 12)?),
+            &["function"],
+        )?;
+        let in_flight = IntGaugeVec::new(
+            Opts::new("in_flight_requests", "The number of requests in flight"),
+            &["route"],
+        )?;
+        let queue_depth = IntGauge::new("queue_depth", "The number of jobs waiting for a worker")?;
+
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(authorization_duration.clone()))?;
+        registry.register(Box::new(in_flight.clone()))?;
+        registry.register(Box::new(queue_depth.clone()))?;
+
+        Ok(Self {
+            registry,
+            requests,
+            authorization_duration,
+            in_flight,
+            queue_depth,
+        })
+    }
+
+    /// Counts a completed request.
+    pub fn observe_request(&self, path: &str, status: u16) {
+        self.requests
+            .with_label_values(&[route_label(path), &status.to_string()])
+            .inc();
+    }
+
+    /// Returns the number of requests in flight, across all routes.
+    pub fn requests_in_flight(&self) -> i64 {
+        self.in_flight
+            .collect()
+            .iter()
+            .flat_map(|family| family.get_metric())
+            .map(|metric| metric.get_gauge().get_value() as i64)
+            .sum()
+    }
+
+    /// Encodes the metrics in the Prometheus text format.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+// Returns the route label of a request path.
+// Paths are mapped onto a fixed set of labels, so that clients cannot create new series,
+// and so that path parameters, such as seeds, are never recorded.
+fn route_label(path: &str) -> &'static str {
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    match segments.as_slice() {
+        ["keygen", ..] => "keygen",
+        ["keys", ..] => "keys",
+        ["auth", "challenge"] => "auth/challenge",
+        ["fee", "suggest"] => "fee/suggest",
+        ["authorize"] => "authorize",
+        ["authorize", function] => CREDITS_ROUTE_LABELS
+            .iter()
+            .find(|label| label.strip_prefix("authorize/") == Some(*function))
+            .copied()
+            .unwrap_or("other"),
+        ["metrics"] => "metrics",
+        ["health"] => "health",
+        ["ready"] => "ready",
+        ["version"] => "version",
+        ["openapi.json"] => "openapi",
+        _ => "other",
+    }
+}
+
+// The route labels of the credits functions.
+const CREDITS_ROUTE_LABELS: &[&str] = &[
+    "authorize/transfer_public",
+    "authorize/transfer_private",
+    "authorize/transfer_private_to_public",
+    "authorize/transfer_public_to_private",
+    "authorize/join",
+    "authorize/split",
+    "authorize/bond_public",
+    "authorize/unbond_public",
+    "authorize/unbond_delegator_as_validator",
+    "authorize/claim_unbond_public",
+];
+
+/// Counts the requests in flight, by route. This must wrap the recovered routes.
+pub fn with_in_flight<F, T>(
+    metrics: &Metrics,
+    routes: F,
+) -> impl Filter<Extract = (T,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (T,), Error = Infallible> + Clone + Send,
+    T: Reply,
+{
+    track_in_flight(metrics.in_flight.clone(), route_label, routes)
+}
+
+/// Counts every request by route and status. This must wrap the recovered routes.
+pub fn with_metrics(
+    metrics: Arc<Metrics>,
+) -> warp::log::Log<impl Fn(warp::log::Info<'_>) + Clone + Send> {
+    warp::log::custom(move |info| metrics.observe_request(info.path(), info.status().as_u16()))
+}
+
+// GET /metrics
+pub fn metrics_route(
+    authenticator: Arc<Authenticator>,
+    metrics: Arc<Metrics>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get()
+        .and(warp::path("metrics"))
+        .and(warp::path::end())
+        .and(with_auth(authenticator, "metrics", DEFAULT_MAX_BODY_BYTES))
+        .and_then(move |_caller: Caller, _body: Bytes| {
+            let metrics = metrics.clone();
+            async move {
+                let bytes = match metrics.encode() {
+                    Ok(bytes) => bytes,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Internal(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                let response = match warp::http::Response::builder()
+                    .header("content-type", prometheus::TEXT_FORMAT)
+                    .body(bytes)
+                {
+                    Ok(response) => response,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Internal(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                Ok(response)
+            }
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_route_label() {
+        assert_eq!(route_label("/keygen/1234"), "keygen");
+        assert_eq!(route_label("/authorize"), "authorize");
+        assert_eq!(route_label("/authorize/join"), "authorize/join");
+        assert_eq!(route_label("/fee/suggest"), "fee/suggest");
+        assert_eq!(route_label("/authorize/unknown"), "other");
+        assert_eq!(route_label("/unknown/path"), "other");
+    }
+
+    #[tokio::test]
+    async fn test_scrape_metrics() {
+        let authenticator = Arc::new(Authenticator::disabled());
+        let limits = Arc::new(Limits::unlimited());
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let workers = Arc::new(
+            WorkerPool::new(1, Duration::from_secs(10), metrics.queue_depth.clone()).unwrap(),
+        );
+        let routes = metrics_route(authenticator.clone(), metrics.clone())
+            .or(keygen_route(authenticator.clone(), limits.clone()))
+            .or(authorize_routes(AuthorizeState {
+                authenticator,
+                limits,
+                metrics: metrics.clone(),
+                process: SharedProcess::default(),
+                workers,
+                keystore: Arc::new(Keystore::disabled()),
+                idempotency: Arc::new(IdempotencyStore::new(Duration::from_secs(60))),
+                audit: Arc::new(AuditLog::disabled()),
+                policies: Arc::new(PolicyEngine::disabled()),
+            }))
+            .recover(handle_rejection);
+        let routes = with_in_flight(&metrics, routes).with(with_metrics(metrics));
+
+        // Send a valid keygen request, and a request that cannot be decoded.
+        let response = warp::test::request()
+            .method("GET")
+            .path("/keygen/1234")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), 200);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/authorize/join")
+            .header("content-type", "application/json")
+            .body("{}")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), 400);
+
+        // Scrape the metrics.
+        let response = warp::test::request()
+            .method("GET")
+            .path("/metrics")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), 200);
+        let body = String::from_utf8(response.body().to_vec()).unwrap();
+        assert!(
+            body.contains("authorize_service_requests_total{route=\"keygen\",status=\"200\"} 1")
+        );
+        assert!(body.contains(
+            "authorize_service_requests_total{route=\"authorize/join\",status=\"400\"} 1"
+        ));
+        // Every route is counted in flight, including the scrape itself.
+        assert!(body.contains("authorize_service_in_flight_requests{route=\"keygen\"} 0"));
+        assert!(body.contains("authorize_service_in_flight_requests{route=\"authorize/join\"} 0"));
+        assert!(body.contains("authorize_service_in_flight_requests{route=\"metrics\"} 1"));
+        assert!(body.contains("authorize_service_queue_depth 0"));
+    }
+}
@@ -0,0 +1,236 @@
+use super::*;
+
+use anyhow::{anyhow, ensure, Result};
+use bip39::{Language, Mnemonic};
+use hmac::{Hmac, Mac};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha512;
+use snarkvm::prelude::Field;
+use std::fmt;
+
+/// The domain separator of account derivation, so that derived keys never collide with keys from other schemes.
+pub const DERIVATION_DOMAIN: &[u8] = b"aleo-tools/account-derivation/v1";
+/// The number of words in a new mnemonic, which encodes 256 bits of entropy.
+pub const MNEMONIC_WORDS: usize = 24;
+
+/// Returns a new mnemonic phrase, from the given source of entropy.
+pub fn new_mnemonic<R: RngCore + CryptoRng>(rng: &mut R) -> Result<String> {
+    let mut entropy = [0u8; MNEMONIC_WORDS / 3 * 4];
+    rng.fill_bytes(&mut entropy);
+    mnemonic_from_entropy(&entropy)
+}
+
+/// Encodes entropy as an English mnemonic phrase.
+/// The entropy must be 16, 20, 24, 28 or 32 bytes.
+pub fn mnemonic_from_entropy(entropy: &[u8]) -> Result<String> {
+    let mnemonic = Mnemonic::from_entropy_in(Language::English, entropy)
+        .map_err(|error| anyhow!("Invalid mnemonic entropy: {error}"))?;
+    Ok(mnemonic.to_string())
+}
+
+/// Decodes an English mnemonic phrase into its entropy, checking its checksum.
+pub fn entropy_from_mnemonic(phrase: &str) -> Result<Vec<u8>> {
+    Ok(parse_mnemonic(phrase)?.to_entropy())
+}
+
+/// Returns the 64-byte master seed of a mnemonic phrase and an optional passphrase, as in BIP-39.
+pub fn master_seed_from_mnemonic(phrase: &str, passphrase: &str) -> Result<[u8; 64]> {
+    Ok(parse_mnemonic(phrase)?.to_seed_normalized(passphrase))
+}
+
+/// Derives the seed of the account at the given index from a master seed.
+/// The seed is `HMAC-SHA512(DERIVATION_DOMAIN, master_seed || network_id || index)`, truncated to 31 bytes,
+/// which is always less than the modulus of the field.
+pub fn derive_account_seed<N: Network>(master_seed: &[u8], index: u32) -> Result<Field<N>> {
+    ensure!(
+        master_seed.len() >= 16,
+        "The master seed must be at least 16 bytes"
+    );
+    let mut mac = Hmac::<Sha512>::new_from_slice(DERIVATION_DOMAIN)
+        .map_err(|_| anyhow!("Failed to initialize the derivation"))?;
+    mac.update(master_seed);
+    mac.update(&N::ID.to_le_bytes());
+    mac.update(&index.to_le_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let mut bytes = [0u8; 32];
+    bytes[..31].copy_from_slice(&digest[..31]);
+    Field::from_bytes_le(&bytes)
+}
+
+/// Derives the private key of the account at the given index from a master seed.
+pub fn derive_private_key<N: Network>(master_seed: &[u8], index: u32) -> Result<PrivateKey<N>> {
+    PrivateKey::try_from(derive_account_seed::<N>(master_seed, index)?)
+}
+
+// Parses an English mnemonic phrase, ignoring case and extra whitespace.
+fn parse_mnemonic(phrase: &str) -> Result<Mnemonic> {
+    let phrase = phrase
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    Mnemonic::parse_in_normalized(Language::English, &phrase)
+        .map_err(|error| anyhow!("Invalid mnemonic: {error}"))
+}
+
+/// A request to derive an account. If the mnemonic is unset, a new one is generated.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DeriveRequest {
+    #[serde(default)]
+    pub mnemonic: Option<String>,
+    #[serde(default)]
+    pub passphrase: String,
+    #[serde(default)]
+    pub index: u32,
+}
+
+impl fmt::Debug for DeriveRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeriveRequest")
+            .field("mnemonic", &self.mnemonic.as_ref().map(|_| Redacted))
+            .field("passphrase", &Redacted)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+/// A derived account, with the mnemonic if it was generated for the request.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct DeriveResponse<N: Network> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    pub index: u32,
+    #[serde(flatten)]
+    pub account: KeygenResponse<N>,
+}
+
+impl<N: Network> fmt::Debug for DeriveResponse<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeriveResponse")
+            .field("mnemonic", &self.mnemonic.as_ref().map(|_| Redacted))
+            .field("index", &self.index)
+            .field("account", &self.account)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use snarkvm::prelude::Address;
+    use std::sync::Arc;
+    use warp::Filter;
+
+    // The first test vector of BIP-39.
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const MASTER_SEED: &str = "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04";
+
+    #[test]
+    fn test_mnemonic_round_trip() {
+        assert_eq!(mnemonic_from_entropy(&[0u8; 16]).unwrap(), MNEMONIC);
+        assert_eq!(entropy_from_mnemonic(MNEMONIC).unwrap(), vec![0u8; 16]);
+        assert_eq!(
+            hex::encode(master_seed_from_mnemonic(MNEMONIC, "TREZOR").unwrap()),
+            MASTER_SEED
+        );
+
+        // Case and whitespace are ignored, but the checksum is not.
+        let shouting = format!("  {}  ", MNEMONIC.to_uppercase());
+        assert_eq!(entropy_from_mnemonic(&shouting).unwrap(), vec![0u8; 16]);
+        assert!(entropy_from_mnemonic(&MNEMONIC.replace("about", "abandon")).is_err());
+
+        // New mnemonics have 24 words.
+        let mnemonic = new_mnemonic(&mut rand::thread_rng()).unwrap();
+        assert_eq!(mnemonic.split(' ').count(), MNEMONIC_WORDS);
+    }
+
+    #[test]
+    fn test_derivation_vectors() {
+        let master_seed = hex::decode(MASTER_SEED).unwrap();
+
+        // These vectors pin the derivation. They must never change, or derived accounts are lost.
+        let vectors = [
+            (
+                0,
+                "46393576835031462839109873859090594430921039214766066191958380687303317710field",
+            ),
+            (
+                1,
+                "369324549793436336034049418238535087640477739660998741660905847659987592566field",
+            ),
+            (
+                u32::MAX,
+                "272277106930631404254041770910574249954834624656588163477314401078570177080field",
+            ),
+        ];
+        for (index, seed) in vectors {
+            let derived = derive_account_seed::<CurrentNetwork>(&master_seed, index).unwrap();
+            assert_eq!(derived.to_string(), seed);
+            assert_eq!(
+                derive_private_key::<CurrentNetwork>(&master_seed, index).unwrap(),
+                PrivateKey::try_from(derived).unwrap()
+            );
+        }
+
+        // Short master seeds are rejected.
+        assert!(derive_account_seed::<CurrentNetwork>(&[0u8; 8], 0).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_derive_route() {
+        let route = derive_route(
+            Arc::new(Authenticator::disabled()),
+            Arc::new(Limits::unlimited()),
+        )
+        .recover(handle_rejection);
+
+        // Deriving from a mnemonic returns the account at the index.
+        let response = warp::test::request()
+            .method("POST")
+            .path("/keygen/derive")
+            .json(&serde_json::json!({ "mnemonic": MNEMONIC, "passphrase": "TREZOR", "index": 1 }))
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        let master_seed = hex::decode(MASTER_SEED).unwrap();
+        assert_eq!(
+            body["private_key"],
+            derive_private_key::<CurrentNetwork>(&master_seed, 1)
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(body["index"], 1);
+        assert!(body.get("mnemonic").is_none());
+
+        // Without a mnemonic, a new one is generated and returned.
+        let response = warp::test::request()
+            .method("POST")
+            .path("/keygen/derive")
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        let mnemonic = body["mnemonic"].as_str().unwrap();
+        let master_seed = master_seed_from_mnemonic(mnemonic, "").unwrap();
+        assert_eq!(
+            body["address"],
+            Address::try_from(&derive_private_key::<CurrentNetwork>(&master_seed, 0).unwrap())
+                .unwrap()
+                .to_string()
+        );
+
+        // An invalid mnemonic is rejected.
+        let response = warp::test::request()
+            .method("POST")
+            .path("/keygen/derive")
+            .json(&serde_json::json!({ "mnemonic": "not a mnemonic" }))
+            .reply(&route)
+            .await;
+        assert_eq!(response.status(), 422);
+    }
+}
@@ -0,0 +1,1059 @@
+use super::*;
+
+use serde_json::{json, Map, Value};
+
+/// The version of the OpenAPI specification that the document follows.
+pub const OPENAPI_VERSION: &str = "3.0.3";
+
+/// A body that has both a JSON and a binary (little-endian) form.
+/// The document is generated from these definitions, so they must match the `FromBytes` and `ToBytes` implementations.
+pub trait ApiSchema: WireMessage {
+    /// The name of the schema, under `#/components/schemas`.
+    const SCHEMA_NAME: &'static str;
+
+    /// Returns the fields of the body, in the order of the binary form.
+    fn fields() -> Vec<ApiField>;
+}
+
+/// A field of a body, with its JSON schema and its binary encoding.
+#[derive(Clone, Debug)]
+pub struct ApiField {
+    /// The name of the field in the JSON form.
+    pub name: &'static str,
+    /// The JSON schema of the field.
+    pub schema: Value,
+    /// The encoding of the field in the binary form.
+    pub encoding: &'static str,
+    /// Whether the properties of the field are flattened into the JSON form of the body.
+    pub flatten: bool,
+}
+
+impl ApiField {
+    /// Returns a required field.
+    pub fn new(name: &'static str, schema: Value, encoding: &'static str) -> Self {
+        Self {
+            name,
+            schema,
+            encoding,
+            flatten: false,
+        }
+    }
+
+    /// Returns the signer of a request, which is flattened into the `private_key` or `key_id` field.
+    pub fn signer() -> Self {
+        Self {
+            name: "signer",
+            schema: json!({
+                "properties": {
+                    "private_key": private_key_schema(),
+                    "key_id": {
+                        "type": "string",
+                        "maxLength": MAX_KEY_ID_LENGTH,
+                        "description": "The ID of a key in the keystore.",
+                    },
+                },
+                "oneOf": [{ "required": ["private_key"] }, { "required": ["key_id"] }],
+            }),
+            encoding: "u8 variant, then the private key if it is 0, or a u16 length and the UTF-8 key ID if it is 1. In version 0, the bare private key",
+            flatten: true,
+        }
+    }
+}
+
+/// Returns the JSON schema of a body, with its binary layout in the `x-binary-layout` extension.
+/// The layout begins with the header of the message.
+pub fn api_schema<T: ApiSchema>() -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    let mut one_of = None;
+    let mut layout =
+        vec![json!({ "field": "header", "encoding": wire_header_encoding(T::WIRE_KIND) })];
+    for field in T::fields() {
+        layout.push(json!({ "field": field.name, "encoding": field.encoding }));
+        match field.flatten {
+            true => {
+                if let Some(flattened) = field.schema["properties"].as_object() {
+                    properties.extend(flattened.clone());
+                }
+                one_of = field.schema.get("oneOf").cloned();
+            }
+            false => {
+                properties.insert(field.name.to_string(), field.schema);
+                required.push(field.name);
+            }
+        }
+    }
+    let mut schema = json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "x-binary-layout": layout,
+    });
+    if let Some(one_of) = one_of {
+        schema["oneOf"] = one_of;
+    }
+    schema
+}
+
+// The schemas of the snarkVM types, in their JSON form.
+
+fn private_key_schema() -> Value {
+    json!({ "type": "string", "pattern": "^APrivateKey1", "description": "An Aleo private key." })
+}
+
+fn address_schema() -> Value {
+    json!({ "type": "string", "pattern": "^aleo1", "description": "An Aleo address." })
+}
+
+fn view_key_schema() -> Value {
+    json!({ "type": "string", "pattern": "^AViewKey1", "description": "An Aleo view key." })
+}
+
+fn record_schema() -> Value {
+    json!({ "type": "string", "description": "A plaintext credits record, as printed by snarkVM." })
+}
+
+fn microcredits_schema() -> Value {
+    json!({ "type": "integer", "format": "int64", "minimum": 0, "description": "An amount in microcredits." })
+}
+
+fn authorization_schema() -> Value {
+    json!({ "type": "object", "description": "A snarkVM authorization, as serialized by snarkVM." })
+}
+
+// The binary encodings of the snarkVM types.
+const ADDRESS_ENCODING: &str = "Address, 32 bytes";
+const RECORD_ENCODING: &str = "Record<Plaintext>, as written by snarkVM";
+const U64_ENCODING: &str = "u64, 8 bytes";
+const AUTHORIZATION_ENCODING: &str = "Authorization, as written by snarkVM";
+
+impl<N: Network> ApiSchema for AuthorizeRequest<N> {
+    const SCHEMA_NAME: &'static str = "AuthorizeRequest";
+
+    fn fields() -> Vec<ApiField> {
+        vec![
+            ApiField::signer(),
+            ApiField::new("recipient", address_schema(), ADDRESS_ENCODING),
+            ApiField::new(
+                "amount_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+            ApiField::new(
+                "priority_fee_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+        ]
+    }
+}
+
+impl<N: Network> ApiSchema for TransferPrivateRequest<N> {
+    const SCHEMA_NAME: &'static str = "TransferPrivateRequest";
+
+    fn fields() -> Vec<ApiField> {
+        vec![
+            ApiField::signer(),
+            ApiField::new("record", record_schema(), RECORD_ENCODING),
+            ApiField::new("recipient", address_schema(), ADDRESS_ENCODING),
+            ApiField::new(
+                "amount_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+            ApiField::new(
+                "priority_fee_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+        ]
+    }
+}
+
+impl<N: Network> ApiSchema for TransferPrivateToPublicRequest<N> {
+    const SCHEMA_NAME: &'static str = "TransferPrivateToPublicRequest";
+
+    fn fields() -> Vec<ApiField> {
+        vec![
+            ApiField::signer(),
+            ApiField::new("record", record_schema(), RECORD_ENCODING),
+            ApiField::new("recipient", address_schema(), ADDRESS_ENCODING),
+            ApiField::new(
+                "amount_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+            ApiField::new(
+                "priority_fee_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+        ]
+    }
+}
+
+impl<N: Network> ApiSchema for TransferPublicToPrivateRequest<N> {
+    const SCHEMA_NAME: &'static str = "TransferPublicToPrivateRequest";
+
+    fn fields() -> Vec<ApiField> {
+        vec![
+            ApiField::signer(),
+            ApiField::new("recipient", address_schema(), ADDRESS_ENCODING),
+            ApiField::new(
+                "amount_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+            ApiField::new(
+                "priority_fee_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+        ]
+    }
+}
+
+impl<N: Network> ApiSchema for JoinRequest<N> {
+    const SCHEMA_NAME: &'static str = "JoinRequest";
+
+    fn fields() -> Vec<ApiField> {
+        vec![
+            ApiField::signer(),
+            ApiField::new("first_record", record_schema(), RECORD_ENCODING),
+            ApiField::new("second_record", record_schema(), RECORD_ENCODING),
+            ApiField::new(
+                "priority_fee_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+        ]
+    }
+}
+
+impl<N: Network> ApiSchema for SplitRequest<N> {
+    const SCHEMA_NAME: &'static str = "SplitRequest";
+
+    fn fields() -> Vec<ApiField> {
+        vec![
+            ApiField::signer(),
+            ApiField::new("record", record_schema(), RECORD_ENCODING),
+            ApiField::new(
+                "amount_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+            ApiField::new(
+                "priority_fee_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+        ]
+    }
+}
+
+impl<N: Network> ApiSchema for BondPublicRequest<N> {
+    const SCHEMA_NAME: &'static str = "BondPublicRequest";
+
+    fn fields() -> Vec<ApiField> {
+        vec![
+            ApiField::signer(),
+            ApiField::new("validator", address_schema(), ADDRESS_ENCODING),
+            ApiField::new(
+                "amount_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+            ApiField::new(
+                "priority_fee_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+        ]
+    }
+}
+
+impl<N: Network> ApiSchema for UnbondPublicRequest<N> {
+    const SCHEMA_NAME: &'static str = "UnbondPublicRequest";
+
+    fn fields() -> Vec<ApiField> {
+        vec![
+            ApiField::signer(),
+            ApiField::new(
+                "amount_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+            ApiField::new(
+                "priority_fee_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+        ]
+    }
+}
+
+impl<N: Network> ApiSchema for UnbondDelegatorAsValidatorRequest<N> {
+    const SCHEMA_NAME: &'static str = "UnbondDelegatorAsValidatorRequest";
+
+    fn fields() -> Vec<ApiField> {
+        vec![
+            ApiField::signer(),
+            ApiField::new("delegator", address_schema(), ADDRESS_ENCODING),
+            ApiField::new(
+                "priority_fee_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+        ]
+    }
+}
+
+impl<N: Network> ApiSchema for ClaimUnbondPublicRequest<N> {
+    const SCHEMA_NAME: &'static str = "ClaimUnbondPublicRequest";
+
+    fn fields() -> Vec<ApiField> {
+        vec![
+            ApiField::signer(),
+            ApiField::new(
+                "priority_fee_in_microcredits",
+                microcredits_schema(),
+                U64_ENCODING,
+            ),
+        ]
+    }
+}
+
+impl<N: Network> ApiSchema for AuthorizeResponse<N> {
+    const SCHEMA_NAME: &'static str = "AuthorizeResponse";
+
+    fn fields() -> Vec<ApiField> {
+        vec![
+            ApiField::new(
+                "function_authorization",
+                authorization_schema(),
+                AUTHORIZATION_ENCODING,
+            ),
+            ApiField::new(
+                "fee_authorization",
+                authorization_schema(),
+                AUTHORIZATION_ENCODING,
+            ),
+        ]
+    }
+}
+
+/// Returns the OpenAPI document of the service.
+pub fn openapi() -> Value {
+    let mut document = OpenApi::new();
+
+    document.operation("get", "/health", None, health_operation());
+    document.operation("get", "/ready", None, ready_operation());
+    document.operation("get", "/version", None, version_operation());
+    document.operation("get", "/metrics", None, metrics_operation());
+    document.operation("get", "/openapi.json", None, openapi_operation());
+    document.operation(
+        "post",
+        "/auth/challenge",
+        None,
+        json!({
+            "summary": "Issues a challenge nonce, to be signed by an Aleo private key.",
+            "description": "Challenges are limited by the IP address of the client. The nonce is consumed by the first request that uses it, even if its signature is not valid.",
+            "responses": {
+                "200": json_content("Challenge"),
+                "429": { "$ref": "#/components/responses/Error" },
+            },
+        }),
+    );
+
+    document.operation(
+        "get",
+        "/keygen/{seed}",
+        Some("keygen"),
+        json!({
+            "summary": "Returns the private key of a seed.",
+            "deprecated": true,
+            "parameters": [{
+                "name": "seed",
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+                "description": "A field element, in decimal.",
+            }],
+            "responses": {
+                "200": {
+                    "description": "The private key, in its binary form.",
+                    "content": { BINARY_CONTENT_TYPE: { "schema": { "type": "string", "format": "binary" } } },
+                },
+            },
+        }),
+    );
+    document.operation(
+        "post",
+        "/keygen",
+        Some("keygen"),
+        json!({
+            "summary": "Generates an account, from server-side entropy or from a strong seed.",
+            "requestBody": json_request("KeygenRequest", false),
+            "responses": { "200": json_content("Account") },
+        }),
+    );
+    document.operation(
+        "post",
+        "/keygen/derive",
+        Some("keygen"),
+        json!({
+            "summary": "Derives the account at an index from a mnemonic, generating the mnemonic if it is unset.",
+            "requestBody": json_request("DeriveRequest", false),
+            "responses": { "200": json_content("DerivedAccount") },
+        }),
+    );
+
+    document.operation(
+        "post",
+        "/keys",
+        Some("keys"),
+        json!({
+            "summary": "Imports a private key into the keystore.",
+            "requestBody": json_request("ImportKeyRequest", true),
+            "responses": { "201": json_content("KeyInfo") },
+        }),
+    );
+    document.operation(
+        "post",
+        "/keys/generate",
+        Some("keys"),
+        json!({
+            "summary": "Generates a private key in the keystore.",
+            "responses": { "201": json_content("KeyInfo") },
+        }),
+    );
+    document.operation(
+        "get",
+        "/keys",
+        Some("keys"),
+        json!({
+            "summary": "Lists the keys of the caller in the keystore.",
+            "responses": {
+                "200": {
+                    "description": "The keys of the caller.",
+                    "content": { JSON_CONTENT_TYPE: { "schema": { "type": "array", "items": schema_ref("KeyInfo") } } },
+                },
+            },
+        }),
+    );
+    document.operation(
+        "delete",
+        "/keys/{id}",
+        Some("keys"),
+        json!({
+            "summary": "Deletes a key of the caller from the keystore.",
+            "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }],
+            "responses": { "204": { "description": "The key was deleted." } },
+        }),
+    );
+
+    document.operation(
+        "get",
+        "/fee/suggest",
+        Some("fee"),
+        json!({
+            "summary": "Suggests priority fees from the fees paid in recent blocks.",
+            "parameters": [{
+                "name": "function",
+                "in": "query",
+                "required": false,
+                "schema": { "type": "string", "enum": FEE_FUNCTIONS },
+                "description": "The function in 'credits.aleo' to suggest a fee for. If unset, or if no recent transaction executed it, the fees of every transaction are used.",
+            }],
+            "responses": {
+                "200": json_content("FeeSuggestion"),
+                "422": { "$ref": "#/components/responses/Error" },
+                "502": { "$ref": "#/components/responses/Error" },
+                "503": {
+                    "description": "No node is configured to suggest fees from.",
+                    "$ref": "#/components/responses/Error",
+                },
+            },
+        }),
+    );
+
+    document.authorize::<AuthorizeRequest<CurrentNetwork>>("/authorize");
+    document.credits::<AuthorizeRequest<CurrentNetwork>>();
+    document.credits::<TransferPrivateRequest<CurrentNetwork>>();
+    document.credits::<TransferPrivateToPublicRequest<CurrentNetwork>>();
+    document.credits::<TransferPublicToPrivateRequest<CurrentNetwork>>();
+    document.credits::<JoinRequest<CurrentNetwork>>();
+    document.credits::<SplitRequest<CurrentNetwork>>();
+    document.credits::<BondPublicRequest<CurrentNetwork>>();
+    document.credits::<UnbondPublicRequest<CurrentNetwork>>();
+    document.credits::<UnbondDelegatorAsValidatorRequest<CurrentNetwork>>();
+    document.credits::<ClaimUnbondPublicRequest<CurrentNetwork>>();
+    document.schema::<AuthorizeResponse<CurrentNetwork>>();
+
+    document.schemas.insert(
+        "KeygenRequest".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "seed": {
+                    "type": "string",
+                    "description": format!("At least {MIN_SEED_BYTES} random bytes, encoded as hex. Patterns, such as repeated or counting bytes, are rejected. If unset, server-side entropy is used."),
+                },
+            },
+        }),
+    );
+    document.schemas.insert(
+        "Account".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "private_key": private_key_schema(),
+                "view_key": view_key_schema(),
+                "address": address_schema(),
+            },
+            "required": ["private_key", "view_key", "address"],
+        }),
+    );
+    document.schemas.insert(
+        "DeriveRequest".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "mnemonic": { "type": "string", "description": "A BIP-39 English mnemonic. If unset, a new one is generated." },
+                "passphrase": { "type": "string", "default": "" },
+                "index": { "type": "integer", "format": "int64", "minimum": 0, "maximum": u32::MAX, "default": 0 },
+            },
+        }),
+    );
+    document.schemas.insert(
+        "DerivedAccount".to_string(),
+        json!({
+            "allOf": [
+                schema_ref("Account"),
+                {
+                    "type": "object",
+                    "properties": {
+                        "mnemonic": { "type": "string", "description": "The mnemonic, if it was generated for the request." },
+                        "index": { "type": "integer", "format": "int64" },
+                    },
+                    "required": ["index"],
+                },
+            ],
+        }),
+    );
+    document.schemas.insert(
+        "ImportKeyRequest".to_string(),
+        json!({
+            "type": "object",
+            "properties": { "private_key": private_key_schema() },
+            "required": ["private_key"],
+        }),
+    );
+    document.schemas.insert(
+        "KeyInfo".to_string(),
+        json!({
+            "type": "object",
+            "properties": { "id": { "type": "string" }, "address": address_schema() },
+            "required": ["id", "address"],
+        }),
+    );
+    document.schemas.insert(
+        "FeeSuggestion".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "function": { "type": "string", "description": "The function that the fees were paid for, if they were not paid for every function." },
+                "samples": { "type": "integer", "description": "The number of transactions that the suggestion is based on." },
+                "low": { "type": "integer", "format": "int64", "description": "The 25th percentile, in microcredits." },
+                "medium": { "type": "integer", "format": "int64", "description": "The median, in microcredits." },
+                "high": { "type": "integer", "format": "int64", "description": "The 90th percentile, in microcredits." },
+            },
+            "required": ["samples", "low", "medium", "high"],
+        }),
+    );
+    document.schemas.insert(
+        "Challenge".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "nonce": { "type": "string", "description": "The hex-encoded nonce." },
+                "expires_at": { "type": "integer", "format": "int64", "description": "The expiry, in seconds since the Unix epoch." },
+            },
+            "required": ["nonce", "expires_at"],
+        }),
+    );
+
+    document.finish(
+        "authorize-service",
+        json!({
+            "api_key": {
+                "type": "apiKey",
+                "in": "header",
+                "name": API_KEY_HEADER,
+                "description": "A static API key.",
+            },
+            "hmac": {
+                "type": "apiKey",
+                "in": "header",
+                "name": AUTH_KEY_ID_HEADER,
+                "description": format!(
+                    "The ID of an HMAC secret. The request must also carry the '{AUTH_TIMESTAMP_HEADER}' and '{AUTH_SIGNATURE_HEADER}' headers, \
+                     where the signature is the hex-encoded HMAC-SHA256 of 'METHOD\\nPATH\\nTIMESTAMP\\nHEX(SHA256(BODY))'."
+                ),
+            },
+            "aleo_signature": {
+                "type": "apiKey",
+                "in": "header",
+                "name": ALEO_ADDRESS_HEADER,
+                "description": format!(
+                    "The Aleo address of the caller. The request must also carry the '{ALEO_NONCE_HEADER}' and '{ALEO_SIGNATURE_HEADER}' headers, \
+                     where the signature is over 'METHOD\\nPATH\\nNONCE\\nHEX(SHA256(BODY))' and the nonce is from POST /auth/challenge."
+                ),
+            },
+        }),
+    )
+}
+
+// An OpenAPI document that is being built.
+struct OpenApi {
+    paths: Map<String, Value>,
+    schemas: Map<String, Value>,
+}
+
+impl OpenApi {
+    fn new() -> Self {
+        Self {
+            paths: Map::new(),
+            schemas: Map::new(),
+        }
+    }
+
+    // Adds an operation. If the route is named, the operation is authenticated and limited as that route,
+    // and may return the errors of authentication and limits.
+    fn operation(&mut self, method: &str, path: &str, route: Option<&str>, mut operation: Value) {
+        operation["responses"]["default"] = json!({ "$ref": "#/components/responses/Error" });
+        if let Some(route) = route {
+            operation["x-route"] = json!(route);
+            operation["security"] =
+                json!([{ "api_key": [] }, { "hmac": [] }, { "aleo_signature": [] }]);
+            for status in ["401", "403", "429"] {
+                operation["responses"][status] = json!({ "$ref": "#/components/responses/Error" });
+            }
+        }
+        let item = self
+            .paths
+            .entry(path.to_string())
+            .or_insert_with(|| json!({}));
+        item[method] = operation;
+    }
+
+    // Adds a schema with both a JSON and a binary form.
+    fn schema<T: ApiSchema>(&mut self) {
+        self.schemas
+            .insert(T::SCHEMA_NAME.to_string(), api_schema::<T>());
+    }
+
+    // Adds an authorize operation for a request, and its schema.
+    fn authorize<R: CreditsRequest<CurrentNetwork> + ApiSchema>(&mut self, path: &str) {
+        self.schema::<R>();
+        let mut operation = json!({
+            "summary": format!("Authorizes a call to '{}' in 'credits.aleo', and its fee.", R::FUNCTION_NAME),
+            "parameters": [idempotency_key_parameter()],
+            "requestBody": {
+                "required": true,
+                "content": encoded_content::<R>(),
+            },
+            "responses": {
+                "200": {
+                    "description": "The function and fee authorizations, in the encoding of the 'Accept' header, or else of the request.",
+                    "content": encoded_content::<AuthorizeResponse<CurrentNetwork>>(),
+                    "headers": { IDEMPOTENT_REPLAYED_HEADER: idempotent_replayed_header() },
+                },
+            },
+        });
+        for status in [
+            "400", "403", "404", "409", "415", "422", "500", "503", "504",
+        ] {
+            operation["responses"][status] = json!({ "$ref": "#/components/responses/Error" });
+        }
+        self.operation("post", path, Some("authorize"), operation);
+    }
+
+    // Adds the authorize operation of a function in `credits.aleo`.
+    fn credits<R: CreditsRequest<CurrentNetwork> + ApiSchema>(&mut self) {
+        self.authorize::<R>(&format!("/authorize/{}", R::FUNCTION_NAME));
+    }
+
+    // Returns the document, with the shared components.
+    fn finish(mut self, title: &str, security_schemes: Value) -> Value {
+        self.schemas.insert(
+            "Error".to_string(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "code": { "type": "string", "enum": ERROR_CODES },
+                    "message": { "type": "string" },
+                    "rule": {
+                        "type": "string",
+                        "enum": PolicyRule::ALL.map(|rule| rule.as_str()),
+                        "description": "The signing policy rule that denied the request, if the code is 'policy_denied'.",
+                    },
+                },
+                "required": ["code", "message"],
+            }),
+        );
+        self.schemas.insert(
+            "Version".to_string(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "version": { "type": "string" },
+                    "snarkvm_version": { "type": "string" },
+                    "network_id": { "type": "integer" },
+                },
+                "required": ["name", "version", "snarkvm_version", "network_id"],
+            }),
+        );
+        json!({
+            "openapi": OPENAPI_VERSION,
+            "info": { "title": title, "version": env!("CARGO_PKG_VERSION") },
+            "paths": self.paths,
+            "components": {
+                "schemas": self.schemas,
+                "responses": {
+                    "Error": {
+                        "description": "An error, with a stable code. Rate-limited responses carry a 'Retry-After' header.",
+                        "content": { JSON_CONTENT_TYPE: { "schema": schema_ref("Error") } },
+                    },
+                },
+                "securitySchemes": security_schemes,
+            },
+        })
+    }
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+// Returns a response with a JSON body.
+fn json_content(name: &str) -> Value {
+    json!({
+        "description": format!("A {name}."),
+        "content": { JSON_CONTENT_TYPE: { "schema": schema_ref(name) } },
+    })
+}
+
+// Returns a request with a JSON body.
+fn json_request(name: &str, required: bool) -> Value {
+    json!({
+        "required": required,
+        "content": { JSON_CONTENT_TYPE: { "schema": schema_ref(name) } },
+    })
+}
+
+// Returns the content of a body that has both a JSON and a binary form.
+fn encoded_content<T: ApiSchema>() -> Value {
+    json!({
+        JSON_CONTENT_TYPE: { "schema": schema_ref(T::SCHEMA_NAME) },
+        BINARY_CONTENT_TYPE: {
+            "schema": { "type": "string", "format": "binary" },
+            "x-binary-layout": api_schema::<T>()["x-binary-layout"],
+        },
+    })
+}
+
+fn health_operation() -> Value {
+    json!({
+        "summary": "Returns whether the service is up.",
+        "responses": { "200": { "description": "The service is up." } },
+    })
+}
+
+fn ready_operation() -> Value {
+    json!({
+        "summary": "Returns whether the service is ready to handle requests.",
+        "responses": {
+            "200": { "description": "The service is ready." },
+            "503": { "$ref": "#/components/responses/Error" },
+        },
+    })
+}
+
+fn version_operation() -> Value {
+    json!({
+        "summary": "Returns the version of the service.",
+        "responses": { "200": json_content("Version") },
+    })
+}
+
+fn metrics_operation() -> Value {
+    json!({
+        "summary": "Returns the metrics of the service, in the Prometheus text format.",
+        "responses": {
+            "200": { "description": "The metrics.", "content": { "text/plain": { "schema": { "type": "string" } } } },
+        },
+    })
+}
+
+// Returns the parameter of the idempotency key header.
+fn idempotency_key_parameter() -> Value {
+    json!({
+        "name": IDEMPOTENCY_KEY_HEADER,
+        "in": "header",
+        "required": false,
+        "description": "A key of up to 255 bytes. A retry with the same key, body and response encoding returns the stored response, and one with a different body or response encoding is a conflict.",
+        "schema": { "type": "string", "minLength": 1, "maxLength": MAX_IDEMPOTENCY_KEY_LENGTH },
+    })
+}
+
+// Returns the header that marks a stored response.
+fn idempotent_replayed_header() -> Value {
+    json!({
+        "description": "Set to 'true' if the response was stored by an earlier request with the same idempotency key.",
+        "schema": { "type": "string" },
+    })
+}
+
+fn openapi_operation() -> Value {
+    json!({
+        "summary": "Returns this document.",
+        "responses": { "200": { "description": "The OpenAPI document.", "content": { JSON_CONTENT_TYPE: {} } } },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use serde::Serialize;
+    use snarkvm::prelude::{Address, U64};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_error_codes_are_documented() {
+        let errors = [
+            ServiceError::Decode(String::new()),
+            ServiceError::UnsupportedMediaType(String::new()),
+            ServiceError::Unauthorized(String::new()),
+            ServiceError::Forbidden(String::new()),
+            ServiceError::KeyNotFound(String::new()),
+            ServiceError::Validation(String::new()),
+            ServiceError::Conflict(String::new()),
+            ServiceError::TooManyRequests(String::new(), 0),
+            ServiceError::Authorization(String::new()),
+            ServiceError::Internal(String::new()),
+            ServiceError::Upstream(String::new()),
+            ServiceError::NotReady(String::new()),
+            ServiceError::Timeout(String::new()),
+            ServiceError::PolicyDenied(String::new(), PolicyRule::AllowedFunctions),
+            ServiceError::PayloadTooLarge(String::new()),
+        ];
+        for error in errors {
+            assert!(ERROR_CODES.contains(&error.code()), "{}", error.code());
+        }
+    }
+
+    #[test]
+    fn test_document_covers_the_routes() {
+        let document = openapi();
+        assert_eq!(document["openapi"], OPENAPI_VERSION);
+        let paths = document["paths"].as_object().unwrap();
+        for path in [
+            "/keygen",
+            "/keygen/derive",
+            "/keys",
+            "/keys/{id}",
+            "/fee/suggest",
+            "/authorize",
+        ] {
+            assert!(paths.contains_key(path), "{path}");
+        }
+        for function in [
+            "transfer_public",
+            "transfer_private",
+            "transfer_private_to_public",
+            "transfer_public_to_private",
+            "join",
+            "split",
+            "bond_public",
+            "unbond_public",
+            "unbond_delegator_as_validator",
+            "claim_unbond_public",
+        ] {
+            let operation = &paths[&format!("/authorize/{function}")]["post"];
+            assert_eq!(operation["x-route"], "authorize");
+            assert!(operation["requestBody"]["content"][BINARY_CONTENT_TYPE].is_object());
+        }
+
+        // Every referenced schema is defined.
+        let schemas = document["components"]["schemas"].as_object().unwrap();
+        let text = document.to_string();
+        for reference in text.split("\"#/components/schemas/").skip(1) {
+            let name = reference.split(['"', '/']).next().unwrap();
+            assert!(schemas.contains_key(name), "{name}");
+        }
+    }
+
+    const PRIVATE_KEY: &str = "APrivateKey1zkpCE9rCw9SixY82xaDrW2Hwxc2f3VjeuR2oZHR81zcuUDV";
+    const ADDRESS: &str = "aleo1rhgdu77hgyqd3xjj8ucu3jj9r2krwz6mnzyd80gncr5fxcwlh5rsvzp9px";
+
+    // Checks the schema of a request against its real encodings: its properties against the keys of its JSON form,
+    // and its binary layout against the encodings of its fields, by name, at their offsets in its binary form.
+    fn check_schema<R: ApiSchema + Serialize>(request: &R, fields: &[(&str, Vec<u8>)]) {
+        let schema = api_schema::<R>();
+        let names = |value: &Value| {
+            value
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<BTreeSet<_>>()
+        };
+        let properties = names(&schema["properties"]);
+        let keys = names(&serde_json::to_value(request).unwrap());
+        let required = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|name| name.as_str().unwrap().to_string())
+            .collect::<BTreeSet<_>>();
+        // Every key is documented, every required property is present,
+        // and only the other form of the signer is missing.
+        assert!(keys.is_subset(&properties), "{keys:?}");
+        assert!(required.is_subset(&keys), "{required:?}");
+        assert_eq!(properties.difference(&keys).count(), 1, "{keys:?}");
+
+        let bytes = request.to_bytes_le().unwrap();
+        let layout = schema["x-binary-layout"].as_array().unwrap();
+        assert_eq!(layout[0]["field"], "header");
+        assert_eq!(layout.len() - 1, fields.len());
+        let mut offset = WIRE_HEADER_LENGTH;
+        for field in &layout[1..] {
+            let name = field["field"].as_str().unwrap();
+            let (_, encoding) = fields
+                .iter()
+                .find(|(field, _)| *field == name)
+                .unwrap_or_else(|| panic!("{name}"));
+            assert_eq!(&bytes[offset..offset + encoding.len()], encoding, "{name}");
+            offset += encoding.len();
+        }
+        assert_eq!(offset, bytes.len());
+    }
+
+    #[test]
+    fn test_schemas_match_the_encodings() {
+        let private_key = PrivateKey::<CurrentNetwork>::from_str(PRIVATE_KEY).unwrap();
+        let address = Address::<CurrentNetwork>::from_str(ADDRESS).unwrap();
+        let amount = U64::<CurrentNetwork>::new(100);
+        let priority_fee = U64::<CurrentNetwork>::new(10);
+        let signer = (
+            "signer",
+            [vec![0], private_key.to_bytes_le().unwrap()].concat(),
+        );
+        let address_field = |name| (name, address.to_bytes_le().unwrap());
+        let amount_field = ("amount_in_microcredits", amount.to_bytes_le().unwrap());
+        let priority_fee_field = (
+            "priority_fee_in_microcredits",
+            priority_fee.to_bytes_le().unwrap(),
+        );
+
+        check_schema(
+            &AuthorizeRequest {
+                signer: Signer::PrivateKey(private_key),
+                recipient: address,
+                amount_in_microcredits: amount,
+                priority_fee_in_microcredits: priority_fee,
+            },
+            &[
+                signer.clone(),
+                address_field("recipient"),
+                amount_field.clone(),
+                priority_fee_field.clone(),
+            ],
+        );
+        // A signer by key ID has a variant, a length and the key ID.
+        check_schema(
+            &AuthorizeRequest {
+                signer: Signer::KeyId("treasury".to_string()),
+                recipient: address,
+                amount_in_microcredits: amount,
+                priority_fee_in_microcredits: priority_fee,
+            },
+            &[
+                ("signer", [&[1, 8, 0][..], b"treasury"].concat()),
+                address_field("recipient"),
+                amount_field.clone(),
+                priority_fee_field.clone(),
+            ],
+        );
+        check_schema(
+            &TransferPublicToPrivateRequest {
+                signer: Signer::PrivateKey(private_key),
+                recipient: address,
+                amount_in_microcredits: amount,
+                priority_fee_in_microcredits: priority_fee,
+            },
+            &[
+                signer.clone(),
+                address_field("recipient"),
+                amount_field.clone(),
+                priority_fee_field.clone(),
+            ],
+        );
+        check_schema(
+            &BondPublicRequest {
+                signer: Signer::PrivateKey(private_key),
+                validator: address,
+                amount_in_microcredits: amount,
+                priority_fee_in_microcredits: priority_fee,
+            },
+            &[
+                signer.clone(),
+                address_field("validator"),
+                amount_field.clone(),
+                priority_fee_field.clone(),
+            ],
+        );
+        check_schema(
+            &UnbondPublicRequest {
+                signer: Signer::PrivateKey(private_key),
+                amount_in_microcredits: amount,
+                priority_fee_in_microcredits: priority_fee,
+            },
+            &[signer.clone(), amount_field, priority_fee_field.clone()],
+        );
+        check_schema(
+            &UnbondDelegatorAsValidatorRequest {
+                signer: Signer::PrivateKey(private_key),
+                delegator: address,
+                priority_fee_in_microcredits: priority_fee,
+            },
+            &[
+                signer.clone(),
+                address_field("delegator"),
+                priority_fee_field.clone(),
+            ],
+        );
+        check_schema(
+            &ClaimUnbondPublicRequest {
+                signer: Signer::PrivateKey(private_key),
+                priority_fee_in_microcredits: priority_fee,
+            },
+            &[signer, priority_fee_field],
+        );
+    }
+
+    #[tokio::test]
+    async fn test_openapi_route() {
+        let response = warp::test::request()
+            .method("GET")
+            .path("/openapi.json")
+            .reply(&openapi_route())
+            .await;
+        assert_eq!(response.status(), 200);
+        let document: Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(document, openapi());
+    }
+}
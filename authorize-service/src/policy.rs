@@ -0,0 +1,695 @@
+use super::*;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use snarkvm::prelude::Address;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The window of the daily limits, which roll over the last 24 hours.
+pub const POLICY_DAILY_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The signing policies, loaded from a TOML file.
+///
+/// ```toml
+/// [default]
+/// max_priority_fee_in_microcredits = 100000
+///
+/// [key_ids.treasury]
+/// allowed_functions = ["transfer_public", "transfer_private"]
+/// allowed_recipients = ["aleo1..."]
+/// max_amount_in_microcredits = 1000000000
+/// daily_limit_in_microcredits = 5000000000
+///
+/// [addresses.aleo1...]
+/// allowed_functions = ["join", "split"]
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyConfig {
+    /// The policy of signers that match neither `key_ids` nor `addresses`. If unset, these signers are not restricted.
+    #[serde(default)]
+    pub default: Option<SigningPolicy>,
+    /// The policies of the keystore keys, by key ID.
+    #[serde(default)]
+    pub key_ids: HashMap<String, SigningPolicy>,
+    /// The policies of the signers, by address. They also apply to keystore keys without a policy of their own.
+    #[serde(default)]
+    pub addresses: HashMap<String, SigningPolicy>,
+}
+
+/// The rules that a signer must follow. Unset rules are not enforced.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SigningPolicy {
+    /// The programs that may be called.
+    pub allowed_programs: Option<Vec<String>>,
+    /// The functions that may be called, by name.
+    pub allowed_functions: Option<Vec<String>>,
+    /// The only recipients that may be sent to.
+    pub allowed_recipients: Option<Vec<String>>,
+    /// The recipients that may never be sent to.
+    #[serde(default)]
+    pub denied_recipients: Vec<String>,
+    /// The maximum amount that a single authorization sends away from the signer, excluding its fees.
+    pub max_amount_in_microcredits: Option<u64>,
+    /// The maximum amount that the authorizations of the signer send away over the last 24 hours, including their base and priority fees.
+    pub daily_limit_in_microcredits: Option<u64>,
+    /// The maximum priority fee of a single authorization.
+    pub max_priority_fee_in_microcredits: Option<u64>,
+}
+
+impl SigningPolicy {
+    // Checks that the addresses of the policy are valid, so that a typo never lets a recipient through.
+    fn validate(&self, name: &str) -> Result<()> {
+        let recipients = self.allowed_recipients.iter().flatten();
+        for recipient in recipients.chain(&self.denied_recipients) {
+            Address::<CurrentNetwork>::from_str(recipient).with_context(|| {
+                format!("The recipient '{recipient}' of the policy '{name}' is not a valid address")
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// A rule of a signing policy, which is named in the response when it denies a request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    AllowedPrograms,
+    AllowedFunctions,
+    AllowedRecipients,
+    DeniedRecipients,
+    MaxAmountInMicrocredits,
+    DailyLimitInMicrocredits,
+    MaxPriorityFeeInMicrocredits,
+}
+
+impl PolicyRule {
+    /// Every rule.
+    pub const ALL: [Self; 7] = [
+        Self::AllowedPrograms,
+        Self::AllowedFunctions,
+        Self::AllowedRecipients,
+        Self::DeniedRecipients,
+        Self::MaxAmountInMicrocredits,
+        Self::DailyLimitInMicrocredits,
+        Self::MaxPriorityFeeInMicrocredits,
+    ];
+
+    /// Returns the name of the rule, which is also its setting in the policy.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AllowedPrograms => "allowed_programs",
+            Self::AllowedFunctions => "allowed_functions",
+            Self::AllowedRecipients => "allowed_recipients",
+            Self::DeniedRecipients => "denied_recipients",
+            Self::MaxAmountInMicrocredits => "max_amount_in_microcredits",
+            Self::DailyLimitInMicrocredits => "daily_limit_in_microcredits",
+            Self::MaxPriorityFeeInMicrocredits => "max_priority_fee_in_microcredits",
+        }
+    }
+}
+
+// An amount that a signer authorized, or is authorizing, with its fees.
+struct Spend {
+    id: u64,
+    amount: u64,
+    at: Instant,
+}
+
+// The amounts of the signers over the daily window, by signer address.
+#[derive(Default)]
+struct Spending {
+    next_id: u64,
+    spends: HashMap<String, Vec<Spend>>,
+}
+
+/// Enforces the signing policies before a request is signed.
+pub struct PolicyEngine {
+    config: Option<PolicyConfig>,
+    spending: Mutex<Spending>,
+}
+
+/// Holds the amount of a request against the daily limit of its signer while it is signed.
+/// If the reservation is dropped before it is completed, the amount is released.
+pub struct PolicyReservation {
+    engine: Arc<PolicyEngine>,
+    spend: Option<(String, u64)>,
+}
+
+impl PolicyEngine {
+    /// Returns an engine without policies, which allows every request.
+    pub fn disabled() -> Self {
+        Self {
+            config: None,
+            spending: Mutex::new(Spending::default()),
+        }
+    }
+
+    /// Returns an engine for the given policies.
+    pub fn new(config: PolicyConfig) -> Result<Self> {
+        if let Some(policy) = &config.default {
+            policy.validate("default")?;
+        }
+        for (key_id, policy) in &config.key_ids {
+            policy.validate(key_id)?;
+        }
+        for (address, policy) in &config.addresses {
+            Address::<CurrentNetwork>::from_str(address).with_context(|| {
+                format!("The policy '{address}' is not named by a valid address")
+            })?;
+            policy.validate(address)?;
+        }
+        Ok(Self {
+            config: Some(config),
+            spending: Mutex::new(Spending::default()),
+        })
+    }
+
+    /// Loads an engine from a TOML policy file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        let config = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse '{}'", path.display()))?;
+        Self::new(config)
+    }
+
+    /// Returns the policy of a signer, by the key ID that the request named, if any, and then by its address.
+    pub fn policy(&self, key_id: Option<&str>, signer: &str) -> Option<&SigningPolicy> {
+        let config = self.config.as_ref()?;
+        key_id
+            .and_then(|key_id| config.key_ids.get(key_id))
+            .or_else(|| config.addresses.get(signer))
+            .or(config.default.as_ref())
+    }
+
+    /// Returns `true` if any policy has a daily limit.
+    pub fn has_daily_limits(&self) -> bool {
+        self.config.as_ref().map_or(false, |config| {
+            config
+                .default
+                .iter()
+                .chain(config.key_ids.values())
+                .chain(config.addresses.values())
+                .any(|policy| policy.daily_limit_in_microcredits.is_some())
+        })
+    }
+
+    /// Counts the authorizations in the audit log at the given path against the daily limits,
+    /// so that the limits hold across restarts. Returns the number of authorizations in the daily window.
+    pub fn restore_spending(&self, path: &Path) -> Result<usize> {
+        let unix_now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let filter = AuditFilter {
+            since: Some(unix_now.saturating_sub(POLICY_DAILY_WINDOW.as_secs())),
+            ..Default::default()
+        };
+        let entries = search_audit_log(path, &filter)?;
+        self.restore(&entries, Instant::now(), unix_now)?;
+        Ok(entries.len())
+    }
+
+    // Counts the audited authorizations against the daily limits, as if they were made at their timestamps.
+    fn restore(&self, entries: &[AuditEntry], now: Instant, unix_now: u64) -> Result<()> {
+        let mut spending = self
+            .spending
+            .lock()
+            .map_err(|_| anyhow!("The policy engine is poisoned"))?;
+        for entry in entries {
+            let age = Duration::from_secs(unix_now.saturating_sub(entry.timestamp));
+            if age >= POLICY_DAILY_WINDOW {
+                continue;
+            }
+            // If the clock cannot go back that far, the amount counts from now, which only holds it for longer.
+            let at = now.checked_sub(age).unwrap_or(now);
+            let id = spending.next_id;
+            spending.next_id += 1;
+            spending
+                .spends
+                .entry(entry.intent.signer.clone())
+                .or_default()
+                .push(Spend {
+                    id,
+                    amount: spent_by(&entry.intent),
+                    at,
+                });
+        }
+        Ok(())
+    }
+
+    /// Checks an intent against the policy of its signer, and reserves its amount against the daily limit.
+    /// Returns `PolicyDenied`, naming the rule, if the intent breaks a rule.
+    pub fn check(
+        self: &Arc<Self>,
+        intent: &Intent,
+        key_id: Option<&str>,
+        now: Instant,
+    ) -> Result<PolicyReservation, ServiceError> {
+        let mut reservation = PolicyReservation {
+            engine: self.clone(),
+            spend: None,
+        };
+        let Some(policy) = self.policy(key_id, &intent.signer) else {
+            return Ok(reservation);
+        };
+        let deny =
+            |rule: PolicyRule, message: String| Err(ServiceError::PolicyDenied(message, rule));
+
+        if let Some(programs) = &policy.allowed_programs {
+            if !programs.contains(&intent.program) {
+                return deny(
+                    PolicyRule::AllowedPrograms,
+                    format!("The signer may not call '{}'", intent.program),
+                );
+            }
+        }
+        if let Some(functions) = &policy.allowed_functions {
+            if !functions.contains(&intent.function) {
+                return deny(
+                    PolicyRule::AllowedFunctions,
+                    format!("The signer may not call '{}'", intent.function),
+                );
+            }
+        }
+        if let Some(recipient) = &intent.recipient {
+            if policy.denied_recipients.contains(recipient) {
+                return deny(
+                    PolicyRule::DeniedRecipients,
+                    format!("The recipient '{recipient}' is denied"),
+                );
+            }
+            if let Some(recipients) = &policy.allowed_recipients {
+                if !recipients.contains(recipient) {
+                    return deny(
+                        PolicyRule::AllowedRecipients,
+                        format!("The recipient '{recipient}' is not allowed"),
+                    );
+                }
+            }
+        }
+        let amount = intent.spent_amount_in_microcredits();
+        if let Some(max_amount) = policy.max_amount_in_microcredits {
+            if amount > max_amount {
+                return deny(
+                    PolicyRule::MaxAmountInMicrocredits,
+                    format!("The amount {amount} exceeds the maximum of {max_amount} microcredits"),
+                );
+            }
+        }
+        if let Some(max_priority_fee) = policy.max_priority_fee_in_microcredits {
+            if intent.priority_fee_in_microcredits > max_priority_fee {
+                return deny(
+                    PolicyRule::MaxPriorityFeeInMicrocredits,
+                    format!(
+                        "The priority fee {} exceeds the maximum of {max_priority_fee} microcredits",
+                        intent.priority_fee_in_microcredits
+                    ),
+                );
+            }
+        }
+
+        if let Some(daily_limit) = policy.daily_limit_in_microcredits {
+            let mut spending = self
+                .spending
+                .lock()
+                .map_err(|_| ServiceError::Internal("The policy engine is poisoned".to_string()))?;
+            // Forget the amounts that have left the window, and the signers without any.
+            spending.spends.retain(|_, spends| {
+                spends.retain(|spend| now.duration_since(spend.at) < POLICY_DAILY_WINDOW);
+                !spends.is_empty()
+            });
+            let spent = spending.spends.get(&intent.signer).map_or(0, |spends| {
+                spends.iter().map(|spend| spend.amount).sum::<u64>()
+            });
+            let spend = spent_by(intent);
+            if spent.saturating_add(spend) > daily_limit {
+                return deny(
+                    PolicyRule::DailyLimitInMicrocredits,
+                    format!(
+                        "The amount {spend}, with its fees, would exceed the daily limit of {daily_limit} microcredits, of which {spent} were spent in the last 24 hours"
+                    ),
+                );
+            }
+            let id = spending.next_id;
+            spending.next_id += 1;
+            spending
+                .spends
+                .entry(intent.signer.clone())
+                .or_default()
+                .push(Spend {
+                    id,
+                    amount: spend,
+                    at: now,
+                });
+            reservation.spend = Some((intent.signer.clone(), id));
+        }
+        Ok(reservation)
+    }
+}
+
+// Returns the amount of an intent that counts against the daily limit, which includes its fees.
+fn spent_by(intent: &Intent) -> u64 {
+    intent
+        .spent_amount_in_microcredits()
+        .saturating_add(intent.base_fee_in_microcredits)
+        .saturating_add(intent.priority_fee_in_microcredits)
+}
+
+impl PolicyReservation {
+    /// Keeps the amount against the daily limit, once the request is signed.
+    pub fn complete(mut self) {
+        self.spend = None;
+    }
+}
+
+impl Drop for PolicyReservation {
+    fn drop(&mut self) {
+        if let Some((signer, id)) = self.spend.take() {
+            if let Ok(mut spending) = self.engine.spending.lock() {
+                if let Some(spends) = spending.spends.get_mut(&signer) {
+                    spends.retain(|spend| spend.id != id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use warp::Filter;
+
+    const PRIVATE_KEY: &str = "APrivateKey1zkpCE9rCw9SixY82xaDrW2Hwxc2f3VjeuR2oZHR81zcuUDV";
+    const SIGNER: &str = "aleo1rhgdu77hgyqd3xjj8ucu3jj9r2krwz6mnzyd80gncr5fxcwlh5rsvzp9px";
+    const RECIPIENT: &str = "aleo1s3ws5tra87fjycnjrwsjcrnw2qxr8jfqqdugnf0xzqqw29q9m5pqem2u4t";
+
+    // Returns an intent of the signer to transfer the amount to the recipient.
+    fn intent(recipient: &str, amount: u64, priority_fee: u64) -> Intent {
+        Intent {
+            signer: SIGNER.to_string(),
+            program: "credits.aleo".to_string(),
+            function: "transfer_public".to_string(),
+            recipient: Some(recipient.to_string()),
+            amount_in_microcredits: Some(amount),
+            base_fee_in_microcredits: 0,
+            priority_fee_in_microcredits: priority_fee,
+        }
+    }
+
+    // Returns the rule that denied the intent, if any.
+    fn denied_by(
+        engine: &Arc<PolicyEngine>,
+        intent: &Intent,
+        key_id: Option<&str>,
+    ) -> Option<PolicyRule> {
+        match engine.check(intent, key_id, Instant::now()) {
+            Ok(reservation) => {
+                reservation.complete();
+                None
+            }
+            Err(error) => error.rule(),
+        }
+    }
+
+    // Returns an engine whose default policy has the daily limit.
+    fn daily_limit_engine(daily_limit: u64) -> Arc<PolicyEngine> {
+        let config = toml::from_str(&format!(
+            "[default]\ndaily_limit_in_microcredits = {daily_limit}"
+        ))
+        .unwrap();
+        Arc::new(PolicyEngine::new(config).unwrap())
+    }
+
+    #[test]
+    fn test_rules() {
+        let config: PolicyConfig = toml::from_str(&format!(
+            r#"
+            [default]
+            allowed_functions = ["join"]
+
+            [addresses.{SIGNER}]
+            allowed_programs = ["credits.aleo"]
+            allowed_functions = ["transfer_public"]
+            denied_recipients = ["{SIGNER}"]
+            max_amount_in_microcredits = 100
+            max_priority_fee_in_microcredits = 10
+
+            [key_ids.treasury]
+            allowed_recipients = ["{SIGNER}"]
+            "#
+        ))
+        .unwrap();
+        let engine = Arc::new(PolicyEngine::new(config).unwrap());
+
+        assert_eq!(denied_by(&engine, &intent(RECIPIENT, 100, 10), None), None);
+        let denials = [
+            (intent(SIGNER, 1, 0), PolicyRule::DeniedRecipients),
+            (
+                intent(RECIPIENT, 101, 0),
+                PolicyRule::MaxAmountInMicrocredits,
+            ),
+            (
+                intent(RECIPIENT, 1, 11),
+                PolicyRule::MaxPriorityFeeInMicrocredits,
+            ),
+            (
+                Intent {
+                    function: "split".to_string(),
+                    ..intent(RECIPIENT, 1, 0)
+                },
+                PolicyRule::AllowedFunctions,
+            ),
+            (
+                Intent {
+                    program: "token.aleo".to_string(),
+                    ..intent(RECIPIENT, 1, 0)
+                },
+                PolicyRule::AllowedPrograms,
+            ),
+        ];
+        for (intent, rule) in denials {
+            assert_eq!(denied_by(&engine, &intent, None), Some(rule), "{intent:?}");
+        }
+
+        // The policy of the key ID comes before the policy of the address, which comes before the default.
+        assert_eq!(
+            denied_by(&engine, &intent(RECIPIENT, 1000, 0), Some("treasury")),
+            Some(PolicyRule::AllowedRecipients)
+        );
+        assert_eq!(
+            denied_by(&engine, &intent(SIGNER, 1000, 0), Some("treasury")),
+            None
+        );
+        // A key ID without a policy falls back to the policy of the address.
+        assert_eq!(
+            denied_by(&engine, &intent(SIGNER, 1, 0), Some("hot-wallet")),
+            Some(PolicyRule::DeniedRecipients)
+        );
+        let other = Intent {
+            signer: RECIPIENT.to_string(),
+            ..intent(SIGNER, 1, 0)
+        };
+        assert_eq!(
+            denied_by(&engine, &other, None),
+            Some(PolicyRule::AllowedFunctions)
+        );
+
+        // Without policies, every intent is allowed.
+        let engine = Arc::new(PolicyEngine::disabled());
+        assert_eq!(
+            denied_by(&engine, &intent(SIGNER, u64::MAX, u64::MAX), None),
+            None
+        );
+
+        // A policy with an invalid address is rejected, and so is a policy of an invalid address.
+        let config: PolicyConfig =
+            toml::from_str("[default]\ndenied_recipients = [\"aleo1typo\"]").unwrap();
+        assert!(PolicyEngine::new(config).is_err());
+        let config: PolicyConfig =
+            toml::from_str("[addresses.treasury]\nmax_amount_in_microcredits = 1").unwrap();
+        assert!(PolicyEngine::new(config).is_err());
+    }
+
+    #[test]
+    fn test_daily_limit() {
+        let engine = daily_limit_engine(100);
+        let start = Instant::now();
+
+        // A reservation that is dropped without completing releases its amount.
+        let reservation = engine
+            .check(&intent(RECIPIENT, 60, 0), None, start)
+            .unwrap();
+        assert_eq!(
+            denied_by(&engine, &intent(RECIPIENT, 60, 0), None),
+            Some(PolicyRule::DailyLimitInMicrocredits)
+        );
+        drop(reservation);
+        engine
+            .check(&intent(RECIPIENT, 60, 0), None, start)
+            .unwrap()
+            .complete();
+
+        // A completed amount counts against the limit until it leaves the window.
+        let later = start + Duration::from_secs(60);
+        engine
+            .check(&intent(RECIPIENT, 40, 0), None, later)
+            .unwrap()
+            .complete();
+        assert!(engine.check(&intent(RECIPIENT, 1, 0), None, later).is_err());
+        let next_day = start + POLICY_DAILY_WINDOW;
+        engine
+            .check(&intent(RECIPIENT, 60, 0), None, next_day)
+            .unwrap()
+            .complete();
+        assert!(engine
+            .check(&intent(RECIPIENT, 1, 0), None, next_day)
+            .is_err());
+
+        // Fees count against the limit.
+        let engine = daily_limit_engine(100);
+        let with_fees = Intent {
+            base_fee_in_microcredits: 20,
+            ..intent(RECIPIENT, 60, 10)
+        };
+        engine.check(&with_fees, None, start).unwrap().complete();
+        assert!(engine
+            .check(&intent(RECIPIENT, 11, 0), None, start)
+            .is_err());
+        engine
+            .check(&intent(RECIPIENT, 10, 0), None, start)
+            .unwrap()
+            .complete();
+    }
+
+    #[test]
+    fn test_split_amount_is_not_spent() {
+        let config = toml::from_str(
+            "[default]\nmax_amount_in_microcredits = 100\ndaily_limit_in_microcredits = 100",
+        )
+        .unwrap();
+        let engine = Arc::new(PolicyEngine::new(config).unwrap());
+        let split = Intent {
+            function: "split".to_string(),
+            recipient: None,
+            base_fee_in_microcredits: 20,
+            ..intent(RECIPIENT, 1000, 10)
+        };
+
+        // The amount of a split stays with the signer, so only its fees count against the limits.
+        assert_eq!(denied_by(&engine, &split, None), None);
+        assert_eq!(denied_by(&engine, &split, None), None);
+        assert_eq!(
+            denied_by(&engine, &intent(RECIPIENT, 41, 0), None),
+            Some(PolicyRule::DailyLimitInMicrocredits)
+        );
+        assert_eq!(denied_by(&engine, &intent(RECIPIENT, 40, 0), None), None);
+
+        // The same holds when the spending is restored from the audit log.
+        let entry = AuditEntry {
+            timestamp: 0,
+            caller: Caller::Anonymous.to_string(),
+            intent: split,
+            execution_id: "1field".to_string(),
+            previous_hash: GENESIS_AUDIT_HASH.to_string(),
+            hash: String::new(),
+        };
+        let engine = daily_limit_engine(100);
+        engine.restore(&[entry], Instant::now(), 0).unwrap();
+        assert_eq!(
+            denied_by(&engine, &intent(RECIPIENT, 71, 0), None),
+            Some(PolicyRule::DailyLimitInMicrocredits)
+        );
+        assert_eq!(denied_by(&engine, &intent(RECIPIENT, 70, 0), None), None);
+    }
+
+    #[tokio::test]
+    async fn test_spending_is_restored_from_the_audit_log() {
+        let path = std::env::temp_dir().join(format!(
+            "authorize-service-policy-{}.jsonl",
+            rand::random::<u64>()
+        ));
+        let log = AuditLog::open(&path).unwrap();
+        log.record(
+            &Caller::Anonymous,
+            intent(RECIPIENT, 60, 10),
+            "1field".to_string(),
+        )
+        .await
+        .unwrap();
+        drop(log);
+
+        // After a restart, the amounts in the log count against the limit.
+        let engine = daily_limit_engine(100);
+        assert!(engine.has_daily_limits());
+        assert_eq!(engine.restore_spending(&path).unwrap(), 1);
+        assert_eq!(
+            denied_by(&engine, &intent(RECIPIENT, 31, 0), None),
+            Some(PolicyRule::DailyLimitInMicrocredits)
+        );
+        assert_eq!(denied_by(&engine, &intent(RECIPIENT, 30, 0), None), None);
+
+        // Amounts older than the window are not restored.
+        let old = AuditEntry {
+            timestamp: 0,
+            caller: Caller::Anonymous.to_string(),
+            intent: intent(RECIPIENT, 100, 0),
+            execution_id: "2field".to_string(),
+            previous_hash: GENESIS_AUDIT_HASH.to_string(),
+            hash: String::new(),
+        };
+        let engine = daily_limit_engine(100);
+        engine
+            .restore(&[old], Instant::now(), POLICY_DAILY_WINDOW.as_secs())
+            .unwrap();
+        assert_eq!(denied_by(&engine, &intent(RECIPIENT, 100, 0), None), None);
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(audit_head_path(&path)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_denied_request_names_the_rule() {
+        let config: PolicyConfig =
+            toml::from_str(&format!("[default]\ndenied_recipients = [\"{RECIPIENT}\"]")).unwrap();
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let workers = Arc::new(
+            WorkerPool::new(1, Duration::from_secs(10), metrics.queue_depth.clone()).unwrap(),
+        );
+        let routes = authorize_routes(AuthorizeState {
+            authenticator: Arc::new(Authenticator::disabled()),
+            limits: Arc::new(Limits::unlimited()),
+            metrics,
+            process: SharedProcess::default(),
+            workers,
+            keystore: Arc::new(Keystore::disabled()),
+            idempotency: Arc::new(IdempotencyStore::new(Duration::from_secs(60))),
+            audit: Arc::new(AuditLog::disabled()),
+            policies: Arc::new(PolicyEngine::new(config).unwrap()),
+        })
+        .recover(handle_rejection);
+
+        // The request is denied before the process is loaded, since nothing is signed.
+        let response = warp::test::request()
+            .method("POST")
+            .path("/authorize/transfer_public")
+            .header("content-type", "application/json")
+            .body(
+                serde_json::json!({
+                    "private_key": PRIVATE_KEY,
+                    "recipient": RECIPIENT,
+                    "amount_in_microcredits": 100,
+                    "priority_fee_in_microcredits": 10,
+                })
+                .to_string(),
+            )
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), 403);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "policy_denied");
+        assert_eq!(body["rule"], "denied_recipients");
+    }
+}
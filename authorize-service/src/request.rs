@@ -1,27 +1,141 @@
 use super::*;
 
-use snarkvm::prelude::{Address, IoResult, U64};
-use std::io::{Read, Write};
+use serde::{Deserialize, Serialize};
+use snarkvm::prelude::{Address, IoResult, Plaintext, Record, U64};
+use std::fmt;
+use std::io::{Error, ErrorKind, Read, Write};
 
-#[derive(Clone, Debug)]
+/// The maximum length of a key ID, in bytes.
+pub const MAX_KEY_ID_LENGTH: usize = 64;
+
+/// The signer of a request, which is either a private key, or the ID of a key in the keystore.
+/// In JSON, the signer is given as the `private_key` or `key_id` field of the request.
+/// The private key is redacted when the signer is printed, and zeroed when it is dropped.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "", rename_all = "snake_case")]
+pub enum Signer<N: Network> {
+    PrivateKey(PrivateKey<N>),
+    KeyId(String),
+}
+
+impl<N: Network> Signer<N> {
+    /// Returns the private key, or an error if the signer is a key ID that was not resolved.
+    pub fn private_key(&self) -> anyhow::Result<&PrivateKey<N>> {
+        match self {
+            Self::PrivateKey(private_key) => Ok(private_key),
+            Self::KeyId(key_id) => anyhow::bail!("The key ID '{key_id}' was not resolved"),
+        }
+    }
+
+    /// Reads a signer in the layout of the given wire version.
+    /// In version 0, the signer is a bare private key, without the tag of its variant.
+    pub fn read_le_for_version<R: Read>(version: u16, reader: R) -> IoResult<Self> {
+        match version {
+            0 => Ok(Self::PrivateKey(PrivateKey::read_le(reader)?)),
+            _ => Self::read_le(reader),
+        }
+    }
+}
+
+impl<N: Network> fmt::Debug for Signer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PrivateKey(_) => f.debug_tuple("PrivateKey").field(&Redacted).finish(),
+            Self::KeyId(key_id) => f.debug_tuple("KeyId").field(key_id).finish(),
+        }
+    }
+}
+
+impl<N: Network> Drop for Signer<N> {
+    fn drop(&mut self) {
+        if let Self::PrivateKey(private_key) = self {
+            zeroize_private_key(private_key);
+        }
+    }
+}
+
+impl<N: Network> From<PrivateKey<N>> for Signer<N> {
+    fn from(private_key: PrivateKey<N>) -> Self {
+        Self::PrivateKey(private_key)
+    }
+}
+
+impl<N: Network> FromBytes for Signer<N> {
+    fn read_le<R: Read>(mut reader: R) -> IoResult<Self>
+    where
+        Self: Sized,
+    {
+        match u8::read_le(&mut reader)? {
+            0 => Ok(Self::PrivateKey(PrivateKey::read_le(&mut reader)?)),
+            1 => {
+                let length = u16::read_le(&mut reader)? as usize;
+                if length > MAX_KEY_ID_LENGTH {
+                    return Err(Error::new(ErrorKind::InvalidData, "The key ID is too long"));
+                }
+                let mut bytes = vec![0u8; length];
+                reader.read_exact(&mut bytes)?;
+                String::from_utf8(bytes)
+                    .map(Self::KeyId)
+                    .map_err(|_| Error::new(ErrorKind::InvalidData, "The key ID is not UTF-8"))
+            }
+            variant => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid signer variant '{variant}'"),
+            )),
+        }
+    }
+}
+
+impl<N: Network> ToBytes for Signer<N> {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()>
+    where
+        Self: Sized,
+    {
+        match self {
+            Self::PrivateKey(private_key) => {
+                0u8.write_le(&mut writer)?;
+                private_key.write_le(&mut writer)
+            }
+            Self::KeyId(key_id) => {
+                if key_id.len() > MAX_KEY_ID_LENGTH {
+                    return Err(Error::new(ErrorKind::InvalidData, "The key ID is too long"));
+                }
+                1u8.write_le(&mut writer)?;
+                (key_id.len() as u16).write_le(&mut writer)?;
+                writer.write_all(key_id.as_bytes())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct AuthorizeRequest<N: Network> {
-    pub private_key: PrivateKey<N>,
+    #[serde(flatten)]
+    pub signer: Signer<N>,
     pub recipient: Address<N>,
+    #[serde(with = "microcredits")]
     pub amount_in_microcredits: U64<N>,
+    #[serde(with = "microcredits")]
     pub priority_fee_in_microcredits: U64<N>,
 }
 
+impl<N: Network> WireMessage for AuthorizeRequest<N> {
+    const WIRE_KIND: WireKind = WireKind::AuthorizeRequest;
+}
+
 impl<N: Network> FromBytes for AuthorizeRequest<N> {
-    fn read_le<R: Read>(mut reader: R) -> IoResult<Self>
+    fn read_le<R: Read>(reader: R) -> IoResult<Self>
     where
         Self: Sized,
     {
-        let private_key = PrivateKey::read_le(&mut reader)?;
+        let (version, mut reader) = read_wire_header::<N, _>(Self::WIRE_KIND, reader)?;
+        let signer = Signer::read_le_for_version(version, &mut reader)?;
         let recipient = Address::read_le(&mut reader)?;
         let amount_in_microcredits = U64::read_le(&mut reader)?;
         let priority_fee_in_microcredits = U64::read_le(&mut reader)?;
         Ok(Self {
-            private_key,
+            signer,
             recipient,
             amount_in_microcredits,
             priority_fee_in_microcredits,
@@ -34,9 +148,603 @@ impl<N: Network> ToBytes for AuthorizeRequest<N> {
     where
         Self: Sized,
     {
-        self.private_key.write_le(&mut writer)?;
+        write_wire_header::<N, _>(Self::WIRE_KIND, &mut writer)?;
+        self.signer.write_le(&mut writer)?;
         self.recipient.write_le(&mut writer)?;
         self.amount_in_microcredits.write_le(&mut writer)?;
         self.priority_fee_in_microcredits.write_le(&mut writer)
     }
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct TransferPrivateRequest<N: Network> {
+    #[serde(flatten)]
+    pub signer: Signer<N>,
+    pub record: Record<N, Plaintext<N>>,
+    pub recipient: Address<N>,
+    #[serde(with = "microcredits")]
+    pub amount_in_microcredits: U64<N>,
+    #[serde(with = "microcredits")]
+    pub priority_fee_in_microcredits: U64<N>,
+}
+
+impl<N: Network> WireMessage for TransferPrivateRequest<N> {
+    const WIRE_KIND: WireKind = WireKind::TransferPrivateRequest;
+}
+
+impl<N: Network> FromBytes for TransferPrivateRequest<N> {
+    fn read_le<R: Read>(reader: R) -> IoResult<Self>
+    where
+        Self: Sized,
+    {
+        let (version, mut reader) = read_wire_header::<N, _>(Self::WIRE_KIND, reader)?;
+        let signer = Signer::read_le_for_version(version, &mut reader)?;
+        let record = Record::read_le(&mut reader)?;
+        let recipient = Address::read_le(&mut reader)?;
+        let amount_in_microcredits = U64::read_le(&mut reader)?;
+        let priority_fee_in_microcredits = U64::read_le(&mut reader)?;
+        Ok(Self {
+            signer,
+            record,
+            recipient,
+            amount_in_microcredits,
+            priority_fee_in_microcredits,
+        })
+    }
+}
+
+impl<N: Network> ToBytes for TransferPrivateRequest<N> {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()>
+    where
+        Self: Sized,
+    {
+        write_wire_header::<N, _>(Self::WIRE_KIND, &mut writer)?;
+        self.signer.write_le(&mut writer)?;
+        self.record.write_le(&mut writer)?;
+        self.recipient.write_le(&mut writer)?;
+        self.amount_in_microcredits.write_le(&mut writer)?;
+        self.priority_fee_in_microcredits.write_le(&mut writer)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct TransferPrivateToPublicRequest<N: Network> {
+    #[serde(flatten)]
+    pub signer: Signer<N>,
+    pub record: Record<N, Plaintext<N>>,
+    pub recipient: Address<N>,
+    #[serde(with = "microcredits")]
+    pub amount_in_microcredits: U64<N>,
+    #[serde(with = "microcredits")]
+    pub priority_fee_in_microcredits: U64<N>,
+}
+
+impl<N: Network> WireMessage for TransferPrivateToPublicRequest<N> {
+    const WIRE_KIND: WireKind = WireKind::TransferPrivateToPublicRequest;
+}
+
+impl<N: Network> FromBytes for TransferPrivateToPublicRequest<N> {
+    fn read_le<R: Read>(reader: R) -> IoResult<Self>
+    where
+        Self: Sized,
+    {
+        let (version, mut reader) = read_wire_header::<N, _>(Self::WIRE_KIND, reader)?;
+        let signer = Signer::read_le_for_version(version, &mut reader)?;
+        let record = Record::read_le(&mut reader)?;
+        let recipient = Address::read_le(&mut reader)?;
+        let amount_in_microcredits = U64::read_le(&mut reader)?;
+        let priority_fee_in_microcredits = U64::read_le(&mut reader)?;
+        Ok(Self {
+            signer,
+            record,
+            recipient,
+            amount_in_microcredits,
+            priority_fee_in_microcredits,
+        })
+    }
+}
+
+impl<N: Network> ToBytes for TransferPrivateToPublicRequest<N> {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()>
+    where
+        Self: Sized,
+    {
+        write_wire_header::<N, _>(Self::WIRE_KIND, &mut writer)?;
+        self.signer.write_le(&mut writer)?;
+        self.record.write_le(&mut writer)?;
+        self.recipient.write_le(&mut writer)?;
+        self.amount_in_microcredits.write_le(&mut writer)?;
+        self.priority_fee_in_microcredits.write_le(&mut writer)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct TransferPublicToPrivateRequest<N: Network> {
+    #[serde(flatten)]
+    pub signer: Signer<N>,
+    pub recipient: Address<N>,
+    #[serde(with = "microcredits")]
+    pub amount_in_microcredits: U64<N>,
+    #[serde(with = "microcredits")]
+    pub priority_fee_in_microcredits: U64<N>,
+}
+
+impl<N: Network> WireMessage for TransferPublicToPrivateRequest<N> {
+    const WIRE_KIND: WireKind = WireKind::TransferPublicToPrivateRequest;
+}
+
+impl<N: Network> FromBytes for TransferPublicToPrivateRequest<N> {
+    fn read_le<R: Read>(reader: R) -> IoResult<Self>
+    where
+        Self: Sized,
+    {
+        let (version, mut reader) = read_wire_header::<N, _>(Self::WIRE_KIND, reader)?;
+        let signer = Signer::read_le_for_version(version, &mut reader)?;
+        let recipient = Address::read_le(&mut reader)?;
+        let amount_in_microcredits = U64::read_le(&mut reader)?;
+        let priority_fee_in_microcredits = U64::read_le(&mut reader)?;
+        Ok(Self {
+            signer,
+            recipient,
+            amount_in_microcredits,
+            priority_fee_in_microcredits,
+        })
+    }
+}
+
+impl<N: Network> ToBytes for TransferPublicToPrivateRequest<N> {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()>
+    where
+        Self: Sized,
+    {
+        write_wire_header::<N, _>(Self::WIRE_KIND, &mut writer)?;
+        self.signer.write_le(&mut writer)?;
+        self.recipient.write_le(&mut writer)?;
+        self.amount_in_microcredits.write_le(&mut writer)?;
+        self.priority_fee_in_microcredits.write_le(&mut writer)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct JoinRequest<N: Network> {
+    #[serde(flatten)]
+    pub signer: Signer<N>,
+    pub first_record: Record<N, Plaintext<N>>,
+    pub second_record: Record<N, Plaintext<N>>,
+    #[serde(with = "microcredits")]
+    pub priority_fee_in_microcredits: U64<N>,
+}
+
+impl<N: Network> WireMessage for JoinRequest<N> {
+    const WIRE_KIND: WireKind = WireKind::JoinRequest;
+}
+
+impl<N: Network> FromBytes for JoinRequest<N> {
+    fn read_le<R: Read>(reader: R) -> IoResult<Self>
+    where
+        Self: Sized,
+    {
+        let (version, mut reader) = read_wire_header::<N, _>(Self::WIRE_KIND, reader)?;
+        let signer = Signer::read_le_for_version(version, &mut reader)?;
+        let first_record = Record::read_le(&mut reader)?;
+        let second_record = Record::read_le(&mut reader)?;
+        let priority_fee_in_microcredits = U64::read_le(&mut reader)?;
+        Ok(Self {
+            signer,
+            first_record,
+            second_record,
+            priority_fee_in_microcredits,
+        })
+    }
+}
+
+impl<N: Network> ToBytes for JoinRequest<N> {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()>
+    where
+        Self: Sized,
+    {
+        write_wire_header::<N, _>(Self::WIRE_KIND, &mut writer)?;
+        self.signer.write_le(&mut writer)?;
+        self.first_record.write_le(&mut writer)?;
+        self.second_record.write_le(&mut writer)?;
+        self.priority_fee_in_microcredits.write_le(&mut writer)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SplitRequest<N: Network> {
+    #[serde(flatten)]
+    pub signer: Signer<N>,
+    pub record: Record<N, Plaintext<N>>,
+    #[serde(with = "microcredits")]
+    pub amount_in_microcredits: U64<N>,
+    #[serde(with = "microcredits")]
+    pub priority_fee_in_microcredits: U64<N>,
+}
+
+impl<N: Network> WireMessage for SplitRequest<N> {
+    const WIRE_KIND: WireKind = WireKind::SplitRequest;
+}
+
+impl<N: Network> FromBytes for SplitRequest<N> {
+    fn read_le<R: Read>(reader: R) -> IoResult<Self>
+    where
+        Self: Sized,
+    {
+        let (version, mut reader) = read_wire_header::<N, _>(Self::WIRE_KIND, reader)?;
+        let signer = Signer::read_le_for_version(version, &mut reader)?;
+        let record = Record::read_le(&mut reader)?;
+        let amount_in_microcredits = U64::read_le(&mut reader)?;
+        let priority_fee_in_microcredits = U64::read_le(&mut reader)?;
+        Ok(Self {
+            signer,
+            record,
+            amount_in_microcredits,
+            priority_fee_in_microcredits,
+        })
+    }
+}
+
+impl<N: Network> ToBytes for SplitRequest<N> {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()>
+    where
+        Self: Sized,
+    {
+        write_wire_header::<N, _>(Self::WIRE_KIND, &mut writer)?;
+        self.signer.write_le(&mut writer)?;
+        self.record.write_le(&mut writer)?;
+        self.amount_in_microcredits.write_le(&mut writer)?;
+        self.priority_fee_in_microcredits.write_le(&mut writer)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BondPublicRequest<N: Network> {
+    #[serde(flatten)]
+    pub signer: Signer<N>,
+    pub validator: Address<N>,
+    #[serde(with = "microcredits")]
+    pub amount_in_microcredits: U64<N>,
+    #[serde(with = "microcredits")]
+    pub priority_fee_in_microcredits: U64<N>,
+}
+
+impl<N: Network> WireMessage for BondPublicRequest<N> {
+    const WIRE_KIND: WireKind = WireKind::BondPublicRequest;
+}
+
+impl<N: Network> FromBytes for BondPublicRequest<N> {
+    fn read_le<R: Read>(reader: R) -> IoResult<Self>
+    where
+        Self: Sized,
+    {
+        let (version, mut reader) = read_wire_header::<N, _>(Self::WIRE_KIND, reader)?;
+        let signer = Signer::read_le_for_version(version, &mut reader)?;
+        let validator = Address::read_le(&mut reader)?;
+        let amount_in_microcredits = U64::read_le(&mut reader)?;
+        let priority_fee_in_microcredits = U64::read_le(&mut reader)?;
+        Ok(Self {
+            signer,
+            validator,
+            amount_in_microcredits,
+            priority_fee_in_microcredits,
+        })
+    }
+}
+
+impl<N: Network> ToBytes for BondPublicRequest<N> {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()>
+    where
+        Self: Sized,
+    {
+        write_wire_header::<N, _>(Self::WIRE_KIND, &mut writer)?;
+        self.signer.write_le(&mut writer)?;
+        self.validator.write_le(&mut writer)?;
+        self.amount_in_microcredits.write_le(&mut writer)?;
+        self.priority_fee_in_microcredits.write_le(&mut writer)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct UnbondPublicRequest<N: Network> {
+    #[serde(flatten)]
+    pub signer: Signer<N>,
+    #[serde(with = "microcredits")]
+    pub amount_in_microcredits: U64<N>,
+    #[serde(with = "microcredits")]
+    pub priority_fee_in_microcredits: U64<N>,
+}
+
+impl<N: Network> WireMessage for UnbondPublicRequest<N> {
+    const WIRE_KIND: WireKind = WireKind::UnbondPublicRequest;
+}
+
+impl<N: Network> FromBytes for UnbondPublicRequest<N> {
+    fn read_le<R: Read>(reader: R) -> IoResult<Self>
+    where
+        Self: Sized,
+    {
+        let (version, mut reader) = read_wire_header::<N, _>(Self::WIRE_KIND, reader)?;
+        let signer = Signer::read_le_for_version(version, &mut reader)?;
+        let amount_in_microcredits = U64::read_le(&mut reader)?;
+        let priority_fee_in_microcredits = U64::read_le(&mut reader)?;
+        Ok(Self {
+            signer,
+            amount_in_microcredits,
+            priority_fee_in_microcredits,
+        })
+    }
+}
+
+impl<N: Network> ToBytes for UnbondPublicRequest<N> {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()>
+    where
+        Self: Sized,
+    {
+        write_wire_header::<N, _>(Self::WIRE_KIND, &mut writer)?;
+        self.signer.write_le(&mut writer)?;
+        self.amount_in_microcredits.write_le(&mut writer)?;
+        self.priority_fee_in_microcredits.write_le(&mut writer)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct UnbondDelegatorAsValidatorRequest<N: Network> {
+    #[serde(flatten)]
+    pub signer: Signer<N>,
+    pub delegator: Address<N>,
+    #[serde(with = "microcredits")]
+    pub priority_fee_in_microcredits: U64<N>,
+}
+
+impl<N: Network> WireMessage for UnbondDelegatorAsValidatorRequest<N> {
+    const WIRE_KIND: WireKind = WireKind::UnbondDelegatorAsValidatorRequest;
+}
+
+impl<N: Network> FromBytes for UnbondDelegatorAsValidatorRequest<N> {
+    fn read_le<R: Read>(reader: R) -> IoResult<Self>
+    where
+        Self: Sized,
+    {
+        let (version, mut reader) = read_wire_header::<N, _>(Self::WIRE_KIND, reader)?;
+        let signer = Signer::read_le_for_version(version, &mut reader)?;
+        let delegator = Address::read_le(&mut reader)?;
+        let priority_fee_in_microcredits = U64::read_le(&mut reader)?;
+        Ok(Self {
+            signer,
+            delegator,
+            priority_fee_in_microcredits,
+        })
+    }
+}
+
+impl<N: Network> ToBytes for UnbondDelegatorAsValidatorRequest<N> {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()>
+    where
+        Self: Sized,
+    {
+        write_wire_header::<N, _>(Self::WIRE_KIND, &mut writer)?;
+        self.signer.write_le(&mut writer)?;
+        self.delegator.write_le(&mut writer)?;
+        self.priority_fee_in_microcredits.write_le(&mut writer)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ClaimUnbondPublicRequest<N: Network> {
+    #[serde(flatten)]
+    pub signer: Signer<N>,
+    #[serde(with = "microcredits")]
+    pub priority_fee_in_microcredits: U64<N>,
+}
+
+impl<N: Network> WireMessage for ClaimUnbondPublicRequest<N> {
+    const WIRE_KIND: WireKind = WireKind::ClaimUnbondPublicRequest;
+}
+
+impl<N: Network> FromBytes for ClaimUnbondPublicRequest<N> {
+    fn read_le<R: Read>(reader: R) -> IoResult<Self>
+    where
+        Self: Sized,
+    {
+        let (version, mut reader) = read_wire_header::<N, _>(Self::WIRE_KIND, reader)?;
+        let signer = Signer::read_le_for_version(version, &mut reader)?;
+        let priority_fee_in_microcredits = U64::read_le(&mut reader)?;
+        Ok(Self {
+            signer,
+            priority_fee_in_microcredits,
+        })
+    }
+}
+
+impl<N: Network> ToBytes for ClaimUnbondPublicRequest<N> {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()>
+    where
+        Self: Sized,
+    {
+        write_wire_header::<N, _>(Self::WIRE_KIND, &mut writer)?;
+        self.signer.write_le(&mut writer)?;
+        self.priority_fee_in_microcredits.write_le(&mut writer)
+    }
+}
+
+/// Serializes amounts in microcredits as plain numbers, e.g. `100` instead of `"100u64"`.
+pub(crate) mod microcredits {
+    use super::*;
+
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<N: Network, S: Serializer>(
+        amount: &U64<N>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(**amount)
+    }
+
+    pub fn deserialize<'de, N: Network, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<U64<N>, D::Error> {
+        Ok(U64::new(u64::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::str::FromStr;
+
+    const PRIVATE_KEY: &str = "APrivateKey1zkpCE9rCw9SixY82xaDrW2Hwxc2f3VjeuR2oZHR81zcuUDV";
+    const RECIPIENT: &str = "aleo1zcsyu7wfrdp4n6gq752p3np45sat9d6zun2uhjer2h4skccsgsgq7ndrnj";
+
+    #[test]
+    fn test_signer_encodings() {
+        // In JSON, the signer is either a private key or a key ID.
+        let request: AuthorizeRequest<CurrentNetwork> = serde_json::from_value(serde_json::json!({
+            "key_id": "0123456789abcdef",
+            "recipient": RECIPIENT,
+            "amount_in_microcredits": 100,
+            "priority_fee_in_microcredits": 10,
+        }))
+        .unwrap();
+        assert!(matches!(&request.signer, Signer::KeyId(key_id) if key_id == "0123456789abcdef"));
+
+        let request: AuthorizeRequest<CurrentNetwork> = serde_json::from_value(serde_json::json!({
+            "private_key": PRIVATE_KEY,
+            "recipient": RECIPIENT,
+            "amount_in_microcredits": 100,
+            "priority_fee_in_microcredits": 10,
+        }))
+        .unwrap();
+        assert_eq!(
+            request.signer.private_key().unwrap(),
+            &PrivateKey::from_str(PRIVATE_KEY).unwrap()
+        );
+
+        // Both signers round-trip in binary.
+        for signer in [
+            Signer::<CurrentNetwork>::PrivateKey(PrivateKey::from_str(PRIVATE_KEY).unwrap()),
+            Signer::KeyId("0123456789abcdef".to_string()),
+        ] {
+            let bytes = signer.to_bytes_le().unwrap();
+            let decoded = Signer::<CurrentNetwork>::from_bytes_le(&bytes).unwrap();
+            assert_eq!(decoded.to_bytes_le().unwrap(), bytes);
+        }
+
+        // Key IDs that are too long are rejected.
+        let signer = Signer::<CurrentNetwork>::KeyId("a".repeat(MAX_KEY_ID_LENGTH + 1));
+        assert!(signer.to_bytes_le().is_err());
+    }
+
+    #[test]
+    fn test_wire_header() {
+        let request = AuthorizeRequest::<CurrentNetwork> {
+            signer: Signer::KeyId("0123456789abcdef".to_string()),
+            recipient: Address::from_str(RECIPIENT).unwrap(),
+            amount_in_microcredits: U64::new(100),
+            priority_fee_in_microcredits: U64::new(10),
+        };
+        let bytes = request.to_bytes_le().unwrap();
+        assert!(bytes.starts_with(&WIRE_MAGIC));
+        assert_eq!(wire_version(&bytes), WIRE_VERSION);
+        let decoded = AuthorizeRequest::<CurrentNetwork>::from_bytes_le(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes_le().unwrap(), bytes);
+
+        // Other kinds of messages are rejected.
+        let error = JoinRequest::<CurrentNetwork>::from_bytes_le(&bytes).unwrap_err();
+        assert!(
+            error.to_string().contains("found AuthorizeRequest"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_version_0_requests() {
+        let private_key = PrivateKey::<CurrentNetwork>::from_str(PRIVATE_KEY).unwrap();
+        let recipient = Address::<CurrentNetwork>::from_str(RECIPIENT).unwrap();
+
+        // The encoder before the header wrote the bare private key, then the other fields.
+        let mut legacy = Vec::new();
+        private_key.write_le(&mut legacy).unwrap();
+        recipient.write_le(&mut legacy).unwrap();
+        U64::<CurrentNetwork>::new(100)
+            .write_le(&mut legacy)
+            .unwrap();
+        U64::<CurrentNetwork>::new(10)
+            .write_le(&mut legacy)
+            .unwrap();
+        assert_eq!(wire_version(&legacy), 0);
+
+        let decoded = AuthorizeRequest::<CurrentNetwork>::from_bytes_le(&legacy).unwrap();
+        assert_eq!(decoded.signer.private_key().unwrap(), &private_key);
+        assert_eq!(decoded.recipient, recipient);
+        assert_eq!(decoded.amount_in_microcredits, U64::new(100));
+        assert_eq!(decoded.priority_fee_in_microcredits, U64::new(10));
+
+        // The decoded request is written in the current version, with the tag of its signer.
+        let bytes = decoded.to_bytes_le().unwrap();
+        assert_eq!(&bytes[WIRE_HEADER_LENGTH..WIRE_HEADER_LENGTH + 1], &[0]);
+        assert_eq!(&bytes[WIRE_HEADER_LENGTH + 1..], &legacy[..]);
+
+        // Requests of the kinds that were introduced with the header must have it.
+        let error = JoinRequest::<CurrentNetwork>::from_bytes_le(&legacy).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("must begin with the wire header"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_authorize_request_encodings() {
+        let request = AuthorizeRequest::<CurrentNetwork> {
+            signer: Signer::PrivateKey(PrivateKey::from_str(PRIVATE_KEY).unwrap()),
+            recipient: Address::from_str(RECIPIENT).unwrap(),
+            amount_in_microcredits: U64::new(100),
+            priority_fee_in_microcredits: U64::new(10),
+        };
+
+        // The JSON encoding uses the string forms of the key and address.
+        let json = Encoding::Json.encode(&request).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!({
+                "private_key": PRIVATE_KEY,
+                "recipient": RECIPIENT,
+                "amount_in_microcredits": 100,
+                "priority_fee_in_microcredits": 10,
+            })
+        );
+
+        // Both encodings round-trip.
+        for encoding in [Encoding::Binary, Encoding::Json] {
+            let bytes = encoding.encode(&request).unwrap();
+            let decoded = encoding
+                .decode::<AuthorizeRequest<CurrentNetwork>>(&bytes)
+                .unwrap();
+            assert_eq!(
+                decoded.signer.private_key().unwrap(),
+                request.signer.private_key().unwrap()
+            );
+            assert_eq!(decoded.recipient, request.recipient);
+            assert_eq!(
+                decoded.amount_in_microcredits,
+                request.amount_in_microcredits
+            );
+            assert_eq!(
+                decoded.priority_fee_in_microcredits,
+                request.priority_fee_in_microcredits
+            );
+        }
+    }
+}
@@ -1,29 +1,38 @@
 use super::*;
 
+use serde::{Deserialize, Serialize};
 use snarkvm::prelude::IoResult;
 use snarkvm::synthesizer::Authorization;
 use std::io::{Read, Write};
 
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct AuthorizeResponse<N: Network> {
     pub function_authorization: Authorization<N>,
     pub fee_authorization: Authorization<N>,
 }
 
+impl<N: Network> WireMessage for AuthorizeResponse<N> {
+    const WIRE_KIND: WireKind = WireKind::AuthorizeResponse;
+}
+
 impl<N: Network> ToBytes for AuthorizeResponse<N> {
     fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()>
     where
         Self: Sized,
     {
+        write_wire_header::<N, _>(Self::WIRE_KIND, &mut writer)?;
         self.function_authorization.write_le(&mut writer)?;
         self.fee_authorization.write_le(&mut writer)
     }
 }
 
 impl<N: Network> FromBytes for AuthorizeResponse<N> {
-    fn read_le<R: Read>(mut reader: R) -> IoResult<Self>
+    fn read_le<R: Read>(reader: R) -> IoResult<Self>
     where
         Self: Sized,
     {
+        let (_, mut reader) = read_wire_header::<N, _>(Self::WIRE_KIND, reader)?;
         let function_authorization = Authorization::read_le(&mut reader)?;
         let fee_authorization = Authorization::read_le(&mut reader)?;
         Ok(Self {
@@ -1,60 +1,699 @@
 use super::*;
 
-use warp::{http::Response, hyper::body::Bytes, Filter, Rejection, Reply};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use warp::http::{Response, StatusCode};
+use warp::hyper::body::{Body, Bytes};
+use warp::{Filter, Rejection, Reply};
 
-// GET /keygen
-pub fn keygen_route() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+// GET /health
+pub fn health_route() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get()
+        .and(warp::path("health"))
+        .and(warp::path::end())
+        .map(|| warp::reply::json(&serde_json::json!({ "status": "ok" })))
+}
+
+// GET /ready
+pub fn ready_route(
+    process: SharedProcess,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get()
+        .and(warp::path("ready"))
+        .and(warp::path::end())
+        .and_then(move || {
+            let process = process.clone();
+            async move {
+                match process.get() {
+                    Ok(_) => Ok(warp::reply::json(&serde_json::json!({ "status": "ready" }))),
+                    Err(error) => Err(warp::reject::custom(ServiceError::from(error))),
+                }
+            }
+        })
+}
+
+// GET /version
+pub fn version_route() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get()
+        .and(warp::path("version"))
+        .and(warp::path::end())
+        .map(|| warp::reply::json(&Version::current()))
+}
+
+// GET /openapi.json
+pub fn openapi_route() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let document = Arc::new(openapi());
+    warp::get()
+        .and(warp::path("openapi.json"))
+        .and(warp::path::end())
+        .map(move || warp::reply::json(document.as_ref()))
+}
+
+// GET /keygen/{seed}
+// Deprecated, as the seed is part of the URL, and weak seeds are accepted. Use POST /keygen instead.
+pub fn keygen_route(
+    authenticator: Arc<Authenticator>,
+    limits: Arc<Limits>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let max_body_bytes = max_body_bytes(&limits);
     warp::get()
         .and(warp::path("keygen"))
         .and(warp::path::param::<String>())
         .and(warp::path::end())
-        .and_then(|seed: String| async move {
-            let private_key = match private_key_from_seed::<CurrentNetwork>(&seed) {
+        .and(with_limits(
+            limits,
+            "keygen",
+            with_auth(authenticator, "keygen", max_body_bytes),
+        ))
+        .and_then(
+            |seed: String, _caller: Caller, _body: Bytes, permit: Permit| async move {
+                let _permit = permit;
+                log::warn!("GET /keygen is deprecated, use POST /keygen instead");
+                let private_key = match private_key_from_seed::<CurrentNetwork>(&seed) {
+                    Ok(private_key) => private_key,
+                    Err(_) => {
+                        return Err(warp::reject::custom(ServiceError::Validation(
+                            "The seed is not a valid field element".to_string(),
+                        )))
+                    }
+                };
+                let bytes = match private_key.to_bytes_le() {
+                    Ok(bytes) => SecretBytes::new(bytes),
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Internal(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                let response = match Response::builder()
+                    .header("content-type", "application/octet-stream")
+                    .header("cache-control", "no-store")
+                    .header("deprecation", "true")
+                    .header("link", "</keygen>; rel=\"successor-version\"")
+                    .body(Body::from(bytes))
+                {
+                    Ok(response) => response,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Internal(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                Ok(response)
+            },
+        )
+}
+
+// POST /keygen
+pub fn post_keygen_route(
+    authenticator: Arc<Authenticator>,
+    limits: Arc<Limits>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let max_body_bytes = max_body_bytes(&limits);
+    warp::post()
+        .and(warp::path("keygen"))
+        .and(warp::path::end())
+        .and(with_limits(
+            limits,
+            "keygen",
+            with_auth(authenticator, "keygen", max_body_bytes),
+        ))
+        .and_then(|_caller: Caller, bytes: Bytes, permit: Permit| async move {
+            let _permit = permit;
+            let request = match bytes.is_empty() {
+                true => KeygenRequest::default(),
+                false => match serde_json::from_slice::<KeygenRequest>(&bytes) {
+                    Ok(request) => request,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Decode(format!(
+                            "Failed to decode the keygen request: {error}"
+                        ))))
+                    }
+                },
+            };
+            let private_key = match request.seed {
+                Some(seed) => match private_key_from_strong_seed::<CurrentNetwork>(&seed) {
+                    Ok(private_key) => private_key,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Validation(
+                            error.to_string(),
+                        )))
+                    }
+                },
+                None => match PrivateKey::new(&mut rand::rngs::OsRng) {
+                    Ok(private_key) => private_key,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Internal(
+                            error.to_string(),
+                        )))
+                    }
+                },
+            };
+            let account = match KeygenResponse::new(private_key) {
+                Ok(account) => account,
+                Err(error) => {
+                    return Err(warp::reject::custom(ServiceError::Internal(
+                        error.to_string(),
+                    )))
+                }
+            };
+            secret_json_response(&account).map_err(warp::reject::custom)
+        })
+}
+
+// POST /keygen/derive
+pub fn derive_route(
+    authenticator: Arc<Authenticator>,
+    limits: Arc<Limits>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let max_body_bytes = max_body_bytes(&limits);
+    warp::post()
+        .and(warp::path("keygen"))
+        .and(warp::path("derive"))
+        .and(warp::path::end())
+        .and(with_limits(
+            limits,
+            "keygen",
+            with_auth(authenticator, "keygen", max_body_bytes),
+        ))
+        .and_then(|_caller: Caller, bytes: Bytes, permit: Permit| async move {
+            let _permit = permit;
+            let request = match bytes.is_empty() {
+                true => DeriveRequest::default(),
+                false => match serde_json::from_slice::<DeriveRequest>(&bytes) {
+                    Ok(request) => request,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Decode(format!(
+                            "Failed to decode the derive request: {error}"
+                        ))))
+                    }
+                },
+            };
+            // Generate a mnemonic if the request does not have one. Only a new mnemonic is returned.
+            let (phrase, generated) = match request.mnemonic {
+                Some(phrase) => (phrase, false),
+                None => match new_mnemonic(&mut rand::rngs::OsRng) {
+                    Ok(phrase) => (phrase, true),
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Internal(
+                            error.to_string(),
+                        )))
+                    }
+                },
+            };
+            let private_key = match master_seed_from_mnemonic(&phrase, &request.passphrase)
+                .and_then(|master_seed| {
+                    derive_private_key::<CurrentNetwork>(&master_seed, request.index)
+                }) {
                 Ok(private_key) => private_key,
-                Err(_) => return Err(warp::reject()),
+                Err(error) => {
+                    return Err(warp::reject::custom(ServiceError::Validation(
+                        error.to_string(),
+                    )))
+                }
             };
-            let bytes = match private_key.to_bytes_le() {
-                Ok(bytes) => bytes,
-                Err(_) => return Err(warp::reject()),
+            let account = match KeygenResponse::new(private_key) {
+                Ok(account) => account,
+                Err(error) => {
+                    return Err(warp::reject::custom(ServiceError::Internal(
+                        error.to_string(),
+                    )))
+                }
             };
-            let response = match Response::builder()
-                .header("content-type", "application/octet-stream")
-                .body(bytes)
-            {
-                Ok(response) => response,
-                Err(_) => return Err(warp::reject()),
+            let response = DeriveResponse {
+                mnemonic: generated.then_some(phrase),
+                index: request.index,
+                account,
             };
-            Ok(response)
+            secret_json_response(&response).map_err(warp::reject::custom)
+        })
+}
+
+// GET /fee/suggest?function={function}
+pub fn fee_route(
+    authenticator: Arc<Authenticator>,
+    limits: Arc<Limits>,
+    oracle: Arc<FeeOracle>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let max_body_bytes = max_body_bytes(&limits);
+    warp::get()
+        .and(warp::path("fee"))
+        .and(warp::path("suggest"))
+        .and(warp::path::end())
+        .and(warp::query::<FeeQuery>())
+        .and(with_limits(
+            limits,
+            "fee",
+            with_auth(authenticator, "fee", max_body_bytes),
+        ))
+        .and(warp::any().map(move || oracle.clone()))
+        .and_then(
+            |query: FeeQuery,
+             _caller: Caller,
+             _body: Bytes,
+             permit: Permit,
+             oracle: Arc<FeeOracle>| async move {
+                let _permit = permit;
+                match oracle.suggest(query.function.as_deref()).await {
+                    Ok(suggestion) => Ok(warp::reply::json(&suggestion)),
+                    Err(error) => Err(warp::reject::custom(error)),
+                }
+            },
+        )
+}
+
+// POST /auth/challenge
+pub fn challenge_route(
+    authenticator: Arc<Authenticator>,
+    limits: Arc<Limits>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::post()
+        .and(warp::path("auth"))
+        .and(warp::path("challenge"))
+        .and(warp::path::end())
+        .and(warp::addr::remote())
+        .and_then(move |remote: Option<SocketAddr>| {
+            let authenticator = authenticator.clone();
+            let limits = limits.clone();
+            async move {
+                // The route is unauthenticated, so challenges are limited by the IP address of the client.
+                let client = match remote {
+                    Some(remote) => remote.ip().to_string(),
+                    None => "unknown".to_string(),
+                };
+                if let Err(error) = limits.check_rate(&client, Instant::now()) {
+                    return Err(warp::reject::custom(ServiceError::from(error)));
+                }
+                match authenticator.issue_challenge(&client, unix_now()) {
+                    Ok(challenge) => Ok(warp::reply::json(&challenge)),
+                    Err(error) => Err(warp::reject::custom(error)),
+                }
+            }
         })
 }
 
+// POST /keys
+pub fn import_key_route(
+    authenticator: Arc<Authenticator>,
+    limits: Arc<Limits>,
+    keystore: Arc<Keystore>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let max_body_bytes = max_body_bytes(&limits);
+    warp::post()
+        .and(warp::path("keys"))
+        .and(warp::path::end())
+        .and(with_limits(
+            limits,
+            "keys",
+            with_auth(authenticator, "keys", max_body_bytes),
+        ))
+        .and(warp::any().map(move || keystore.clone()))
+        .and_then(
+            |caller: Caller, bytes: Bytes, permit: Permit, keystore: Arc<Keystore>| async move {
+                let _permit = permit;
+                let request =
+                    match serde_json::from_slice::<ImportKeyRequest<CurrentNetwork>>(&bytes) {
+                        Ok(request) => request,
+                        Err(error) => {
+                            return Err(warp::reject::custom(ServiceError::Decode(format!(
+                                "Failed to decode the import request: {error}"
+                            ))))
+                        }
+                    };
+                match keystore.import(&request.private_key, caller.key_id().as_deref()) {
+                    Ok(key) => Ok(warp::reply::with_status(
+                        warp::reply::json(&key),
+                        StatusCode::CREATED,
+                    )),
+                    Err(error) => Err(warp::reject::custom(error)),
+                }
+            },
+        )
+}
+
+// POST /keys/generate
+pub fn generate_key_route(
+    authenticator: Arc<Authenticator>,
+    limits: Arc<Limits>,
+    keystore: Arc<Keystore>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let max_body_bytes = max_body_bytes(&limits);
+    warp::post()
+        .and(warp::path("keys"))
+        .and(warp::path("generate"))
+        .and(warp::path::end())
+        .and(with_limits(
+            limits,
+            "keys",
+            with_auth(authenticator, "keys", max_body_bytes),
+        ))
+        .and(warp::any().map(move || keystore.clone()))
+        .and_then(
+            |caller: Caller, _body: Bytes, permit: Permit, keystore: Arc<Keystore>| async move {
+                let _permit = permit;
+                match keystore.generate(caller.key_id().as_deref()) {
+                    Ok(key) => Ok(warp::reply::with_status(
+                        warp::reply::json(&key),
+                        StatusCode::CREATED,
+                    )),
+                    Err(error) => Err(warp::reject::custom(error)),
+                }
+            },
+        )
+}
+
+// GET /keys
+pub fn list_keys_route(
+    authenticator: Arc<Authenticator>,
+    limits: Arc<Limits>,
+    keystore: Arc<Keystore>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let max_body_bytes = max_body_bytes(&limits);
+    warp::get()
+        .and(warp::path("keys"))
+        .and(warp::path::end())
+        .and(with_limits(
+            limits,
+            "keys",
+            with_auth(authenticator, "keys", max_body_bytes),
+        ))
+        .and(warp::any().map(move || keystore.clone()))
+        .and_then(
+            |caller: Caller, _body: Bytes, permit: Permit, keystore: Arc<Keystore>| async move {
+                let _permit = permit;
+                match keystore.list(caller.key_id().as_deref()) {
+                    Ok(keys) => Ok(warp::reply::json(&keys)),
+                    Err(error) => Err(warp::reject::custom(error)),
+                }
+            },
+        )
+}
+
+// DELETE /keys/{id}
+pub fn delete_key_route(
+    authenticator: Arc<Authenticator>,
+    limits: Arc<Limits>,
+    keystore: Arc<Keystore>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let max_body_bytes = max_body_bytes(&limits);
+    warp::delete()
+        .and(warp::path("keys"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(with_limits(limits, "keys", with_auth(authenticator, "keys", max_body_bytes)))
+        .and(warp::any().map(move || keystore.clone()))
+        .and_then(
+            |id: String,
+             caller: Caller,
+             _body: Bytes,
+             permit: Permit,
+             keystore: Arc<Keystore>| async move {
+                let _permit = permit;
+                match keystore.delete(&id, caller.key_id().as_deref()) {
+                    Ok(()) => Ok(StatusCode::NO_CONTENT),
+                    Err(error) => Err(warp::reject::custom(error)),
+                }
+            },
+        )
+}
+
+// POST /keys, POST /keys/generate, GET /keys and DELETE /keys/{id}
+pub fn keys_routes(
+    authenticator: Arc<Authenticator>,
+    limits: Arc<Limits>,
+    keystore: Arc<Keystore>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    import_key_route(authenticator.clone(), limits.clone(), keystore.clone())
+        .or(generate_key_route(
+            authenticator.clone(),
+            limits.clone(),
+            keystore.clone(),
+        ))
+        .or(list_keys_route(
+            authenticator.clone(),
+            limits.clone(),
+            keystore.clone(),
+        ))
+        .or(delete_key_route(authenticator, limits, keystore))
+}
+
+/// The state that is shared by the authorize routes.
+#[derive(Clone)]
+pub struct AuthorizeState {
+    pub authenticator: Arc<Authenticator>,
+    pub limits: Arc<Limits>,
+    pub metrics: Arc<Metrics>,
+    pub process: SharedProcess,
+    pub workers: Arc<WorkerPool>,
+    pub keystore: Arc<Keystore>,
+    pub idempotency: Arc<IdempotencyStore>,
+    pub audit: Arc<AuditLog>,
+    pub policies: Arc<PolicyEngine>,
+}
+
 // POST /authorize
-pub fn authorize_route() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+pub fn authorize_route(
+    state: AuthorizeState,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::post()
         .and(warp::path("authorize"))
         .and(warp::path::end())
-        .and(warp::body::content_length_limit(1024)) // 1 kilobyte
-        .and(warp::body::bytes())
-        .and_then(|bytes: Bytes| async move {
-            let request = match AuthorizeRequest::from_bytes_le(&bytes) {
-                Ok(request) => request,
-                Err(_) => return Err(warp::reject()),
-            };
-            let authorization = match authorize_transfer_public::<CurrentNetwork>(request) {
-                Ok(authorization) => authorization,
-                Err(_) => return Err(warp::reject()),
-            };
-            let bytes = match authorization.to_bytes_le() {
-                Ok(bytes) => bytes,
-                Err(_) => return Err(warp::reject()),
-            };
-            let response = match Response::builder()
-                .header("content-type", "application/octet-stream")
-                .body(bytes)
-            {
-                Ok(response) => response,
-                Err(_) => return Err(warp::reject()),
-            };
-            Ok(response)
-        })
+        .and(authorize_pipeline::<AuthorizeRequest<CurrentNetwork>>(
+            state,
+        ))
+}
+
+// POST /authorize/{function}
+pub fn credits_route<R: CreditsRequest<CurrentNetwork>>(
+    state: AuthorizeState,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::post()
+        .and(warp::path("authorize"))
+        .and(warp::path(R::FUNCTION_NAME))
+        .and(warp::path::end())
+        .and(authorize_pipeline::<R>(state))
+}
+
+// POST /authorize and POST /authorize/{function} for every function in `credits.aleo`.
+pub fn authorize_routes(
+    state: AuthorizeState,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    authorize_route(state.clone())
+        .or(credits_route::<AuthorizeRequest<CurrentNetwork>>(
+            state.clone(),
+        ))
+        .or(credits_route::<TransferPrivateRequest<CurrentNetwork>>(
+            state.clone(),
+        ))
+        .or(credits_route::<
+            TransferPrivateToPublicRequest<CurrentNetwork>,
+        >(state.clone()))
+        .or(credits_route::<
+            TransferPublicToPrivateRequest<CurrentNetwork>,
+        >(state.clone()))
+        .or(credits_route::<JoinRequest<CurrentNetwork>>(state.clone()))
+        .or(credits_route::<SplitRequest<CurrentNetwork>>(state.clone()))
+        .or(credits_route::<BondPublicRequest<CurrentNetwork>>(
+            state.clone(),
+        ))
+        .or(credits_route::<UnbondPublicRequest<CurrentNetwork>>(
+            state.clone(),
+        ))
+        .or(credits_route::<
+            UnbondDelegatorAsValidatorRequest<CurrentNetwork>,
+        >(state.clone()))
+        .or(credits_route::<ClaimUnbondPublicRequest<CurrentNetwork>>(
+            state,
+        ))
+}
+
+// Decodes a request, resolves its key ID, validates it, checks its signing policy, authorizes it on the worker pool, and encodes the response.
+// This pipeline is shared by all of the authorize routes, which are limited as the "authorize" route.
+// Each route is authenticated by the policy of its function, or else by that of "authorize".
+fn authorize_pipeline<R: CreditsRequest<CurrentNetwork>>(
+    state: AuthorizeState,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let max_body_bytes = max_body_bytes(&state.limits);
+    warp::header::optional::<String>("content-type")
+        .and(warp::header::optional::<String>("accept"))
+        .and(warp::header::optional::<String>(IDEMPOTENCY_KEY_HEADER))
+        .and(with_limits(
+            state.limits.clone(),
+            "authorize",
+            with_auth(
+                state.authenticator.clone(),
+                R::FUNCTION_NAME,
+                max_body_bytes,
+            ),
+        ))
+        .and(warp::any().map(move || state.clone()))
+        .and_then(
+            |content_type: Option<String>,
+             accept: Option<String>,
+             idempotency_key: Option<String>,
+             caller: Caller,
+             bytes: Bytes,
+             permit: Permit,
+             state: AuthorizeState| async move {
+                let AuthorizeState {
+                    metrics,
+                    process,
+                    workers,
+                    keystore,
+                    idempotency,
+                    audit,
+                    policies,
+                    ..
+                } = state;
+                let encoding = match Encoding::from_content_type(content_type.as_deref()) {
+                    Ok(encoding) => encoding,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::UnsupportedMediaType(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                // Responses are encoded as the request is, unless the `Accept` header asks otherwise.
+                let response_encoding = Encoding::from_accept(accept.as_deref(), encoding);
+                // A retry under an idempotency key returns the stored response, without authorizing again.
+                let guard = match idempotency_key {
+                    Some(key) => {
+                        let fingerprint = IdempotencyStore::fingerprint(
+                            R::FUNCTION_NAME,
+                            response_encoding,
+                            &bytes,
+                        );
+                        match idempotency.begin(
+                            &caller.to_string(),
+                            &key,
+                            fingerprint,
+                            Instant::now(),
+                        ) {
+                            Ok(Idempotency::New(guard)) => Some(guard),
+                            Ok(Idempotency::Replay(response)) => {
+                                return replay_response(response).map_err(|error| {
+                                    warp::reject::custom(ServiceError::from(error))
+                                })
+                            }
+                            Err(error) => {
+                                return Err(warp::reject::custom(ServiceError::from(error)))
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                // Responses to binary requests are written in the wire version of the request.
+                let version = match encoding {
+                    Encoding::Binary => wire_version(&bytes),
+                    Encoding::Json => WIRE_VERSION,
+                };
+                let mut request = match encoding.decode::<R>(&bytes) {
+                    Ok(request) => request,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Decode(format!(
+                            "Failed to decode the {} request: {error}",
+                            R::FUNCTION_NAME
+                        ))))
+                    }
+                };
+                let key_id = match request.signer() {
+                    Signer::KeyId(key_id) => Some(key_id.clone()),
+                    Signer::PrivateKey(_) => None,
+                };
+                if let Some(key_id) = &key_id {
+                    let private_key = match keystore.private_key(key_id, caller.key_id().as_deref())
+                    {
+                        Ok(private_key) => private_key,
+                        Err(error) => return Err(warp::reject::custom(error)),
+                    };
+                    *request.signer_mut() = Signer::PrivateKey(private_key);
+                }
+                if let Err(error) = request.validate() {
+                    return Err(warp::reject::custom(ServiceError::Validation(
+                        error.to_string(),
+                    )));
+                }
+                let intent = match Intent::of(&request) {
+                    Ok(intent) => intent,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Validation(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                // Enforce the signing policy of the signer before anything is signed.
+                let reservation = match policies.check(&intent, key_id.as_deref(), Instant::now()) {
+                    Ok(reservation) => reservation,
+                    Err(error) => return Err(warp::reject::custom(error)),
+                };
+                let process = match process.get() {
+                    Ok(process) => process,
+                    Err(error) => return Err(warp::reject::custom(ServiceError::from(error))),
+                };
+                log::debug!("Authorizing {} for {caller}", R::FUNCTION_NAME);
+                let histogram = metrics
+                    .authorization_duration
+                    .with_label_values(&[R::FUNCTION_NAME]);
+                // An authorization cannot be interrupted, so its job holds the slot until it stops,
+                // even after the request times out.
+                let job_permit = permit.clone();
+                let authorization = workers
+                    .run(move |_| -> anyhow::Result<_> {
+                        let _permit = job_permit;
+                        let _timer = histogram.start_timer();
+                        let authorization = request.authorize::<CurrentAleo>(&process)?;
+                        let execution_id =
+                            authorization.function_authorization.to_execution_id()?;
+                        Ok((authorization, execution_id))
+                    })
+                    .await;
+                let authorization = match authorization {
+                    Ok(Ok((authorization, execution_id))) => {
+                        // Record what was signed, before the authorization leaves the service.
+                        if let Err(error) = audit
+                            .record(&caller, intent, execution_id.to_string())
+                            .await
+                        {
+                            return Err(warp::reject::custom(error));
+                        }
+                        reservation.complete();
+                        authorization
+                    }
+                    Ok(Err(error)) => {
+                        return Err(warp::reject::custom(ServiceError::Authorization(format!(
+                            "Failed to authorize {}: {error}",
+                            R::FUNCTION_NAME
+                        ))))
+                    }
+                    Err(error) => return Err(warp::reject::custom(ServiceError::from(error))),
+                };
+                let bytes = match response_encoding.encode_for_version(&authorization, version) {
+                    Ok(bytes) => bytes,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Internal(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                if let Some(guard) = guard {
+                    guard.complete(StoredResponse {
+                        content_type: response_encoding.content_type(),
+                        body: bytes.clone(),
+                    });
+                }
+                let response = match Response::builder()
+                    .header("content-type", response_encoding.content_type())
+                    .body(bytes)
+                {
+                    Ok(response) => response,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Internal(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                Ok(response)
+            },
+        )
 }
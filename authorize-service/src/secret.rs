@@ -0,0 +1,214 @@
+use super::*;
+
+use anyhow::Result;
+use serde::Serialize;
+use snarkvm::prelude::ViewKey;
+use std::fmt;
+use std::ptr::NonNull;
+use warp::http::Response;
+use warp::hyper::body::{Body, Bytes};
+use zeroize::Zeroize;
+
+/// Prints in place of a secret, so that secrets are never written to the logs.
+pub struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// Zeroes a private key in place.
+/// This clears only the given copy. Private keys are `Copy`, so every move of a key, and every copy that snarkVM and serde make while
+/// parsing, signing or encoding it, leaves bytes behind on the stack or the heap that are not cleared. Holders call this when they are
+/// dropped to shorten the life of their own copy, not to guarantee that no copy of the key remains in memory.
+pub fn zeroize_private_key<N: Network>(private_key: &mut PrivateKey<N>) {
+    // SAFETY: A private key is a seed and two scalars, which are arrays of limbs without pointers or `Drop` impls.
+    // All zeroes is a valid value of each, and the key is not read again by its holder.
+    unsafe { zeroize::zeroize_flat_type(private_key as *mut PrivateKey<N>) }
+}
+
+/// Zeroes a view key in place. Like `zeroize_private_key`, this clears only the given copy.
+pub fn zeroize_view_key<N: Network>(view_key: &mut ViewKey<N>) {
+    // SAFETY: A view key is a scalar, which is an array of limbs without pointers or `Drop` impls.
+    unsafe { zeroize::zeroize_flat_type(view_key as *mut ViewKey<N>) }
+}
+
+/// A buffer of secret bytes, such as an encoded private key.
+/// The bytes live on pages of their own, between guard pages, which are locked in memory where the platform allows it so that they are
+/// not swapped to disk. The pages are zeroed and unlocked when the buffer is dropped, which leaves the locks of other secrets in place.
+pub struct SecretBytes {
+    buffer: Buffer,
+    locked: bool,
+}
+
+enum Buffer {
+    // An allocation of `memsec`, which is freed with `memsec::free`.
+    Pages(NonNull<[u8]>),
+    // A plain allocation, used if the pages cannot be allocated.
+    Heap(Vec<u8>),
+}
+
+// SAFETY: The buffer owns its allocation, and only hands out shared references to it.
+unsafe impl Send for SecretBytes {}
+
+impl SecretBytes {
+    /// Copies the bytes onto pages of their own, and zeroes the given bytes.
+    pub fn new(mut bytes: Vec<u8>) -> Self {
+        let mut secret = Self::zeroed(bytes.len());
+        secret.as_mut().copy_from_slice(&bytes);
+        bytes.zeroize();
+        secret
+    }
+
+    /// Serializes the value as JSON.
+    /// The bytes are counted first, so that the JSON is written once, straight onto its own pages.
+    pub fn json<T: Serialize>(value: &T) -> Result<Self> {
+        let mut length = ByteCounter(0);
+        serde_json::to_writer(&mut length, value)?;
+        let mut secret = Self::zeroed(length.0);
+        serde_json::to_writer(secret.as_mut(), value)?;
+        Ok(secret)
+    }
+
+    /// Returns `true` if the buffer is locked in memory.
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    // Allocates a buffer of the given length, and locks it where the platform allows it.
+    // Locking fails if it would exceed `RLIMIT_MEMLOCK`, in which case the buffer is only zeroed on drop.
+    fn zeroed(length: usize) -> Self {
+        // SAFETY: The allocation is owned by the buffer, and freed only when the buffer is dropped.
+        match unsafe { memsec::malloc_sized(length) } {
+            Some(mut pages) => {
+                // SAFETY: The allocation is live, and is not aliased until the buffer is returned.
+                let bytes = unsafe { pages.as_mut() };
+                bytes.fill(0);
+                // `malloc_sized` does not report whether its pages were locked. Locking them again is harmless, since the pages hold
+                // nothing else, and reports whether they are locked.
+                // SAFETY: The range is a live allocation, and locking it does not change its contents.
+                let locked = unsafe { memsec::mlock(bytes.as_mut_ptr(), bytes.len()) };
+                Self {
+                    buffer: Buffer::Pages(pages),
+                    locked,
+                }
+            }
+            None => Self {
+                buffer: Buffer::Heap(vec![0; length]),
+                locked: false,
+            },
+        }
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        match &self.buffer {
+            // SAFETY: The allocation is live for as long as the buffer.
+            Buffer::Pages(pages) => unsafe { pages.as_ref() },
+            Buffer::Heap(bytes) => bytes,
+        }
+    }
+}
+
+impl AsMut<[u8]> for SecretBytes {
+    fn as_mut(&mut self) -> &mut [u8] {
+        match &mut self.buffer {
+            // SAFETY: The allocation is live for as long as the buffer, and is borrowed mutably through it.
+            Buffer::Pages(pages) => unsafe { pages.as_mut() },
+            Buffer::Heap(bytes) => bytes,
+        }
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SecretBytes").field(&Redacted).finish()
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        match &mut self.buffer {
+            // `free` zeroes the pages, and unlocks only them.
+            // SAFETY: The allocation came from `memsec::malloc_sized`, and is not used again.
+            Buffer::Pages(pages) => unsafe { memsec::free(*pages) },
+            Buffer::Heap(bytes) => bytes.zeroize(),
+        }
+    }
+}
+
+/// A response body of secret bytes, which are zeroed once the response is sent.
+impl From<SecretBytes> for Body {
+    fn from(bytes: SecretBytes) -> Self {
+        Body::from(Bytes::from_owner(bytes))
+    }
+}
+
+/// Returns a JSON response that holds secrets. Its body is zeroed once it is sent, and it is never cached.
+pub fn secret_json_response<T: Serialize>(value: &T) -> Result<Response<Body>, ServiceError> {
+    let body =
+        SecretBytes::json(value).map_err(|error| ServiceError::Internal(error.to_string()))?;
+    Response::builder()
+        .header("content-type", JSON_CONTENT_TYPE)
+        .header("cache-control", "no-store")
+        .body(Body::from(body))
+        .map_err(|error| ServiceError::Internal(error.to_string()))
+}
+
+// Counts the bytes that are written to it.
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0 += bytes.len();
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use snarkvm::prelude::Field;
+    use std::str::FromStr;
+
+    const PRIVATE_KEY: &str = "APrivateKey1zkpCE9rCw9SixY82xaDrW2Hwxc2f3VjeuR2oZHR81zcuUDV";
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        let private_key = PrivateKey::<CurrentNetwork>::from_str(PRIVATE_KEY).unwrap();
+        let account = KeygenResponse::new(private_key).unwrap();
+        let signer = Signer::PrivateKey(private_key);
+        for printed in [
+            format!("{account:?}"),
+            format!("{signer:?}"),
+            format!("{:?}", SecretBytes::json(&account).unwrap()),
+        ] {
+            assert!(!printed.contains(PRIVATE_KEY), "{printed}");
+            assert!(!printed.contains("AViewKey1"), "{printed}");
+            assert!(printed.contains("<redacted>"), "{printed}");
+        }
+        // The address is not a secret.
+        assert!(format!("{account:?}").contains(&account.address.to_string()));
+    }
+
+    #[test]
+    fn test_secret_bytes() {
+        let private_key = PrivateKey::<CurrentNetwork>::from_str(PRIVATE_KEY).unwrap();
+        let account = KeygenResponse::new(private_key).unwrap();
+        let bytes = SecretBytes::json(&account).unwrap();
+        assert_eq!(bytes.as_ref(), serde_json::to_vec(&account).unwrap());
+        assert_eq!(SecretBytes::new(vec![1, 2, 3]).as_ref(), [1, 2, 3]);
+        assert!(SecretBytes::new(Vec::new()).as_ref().is_empty());
+
+        let mut private_key = private_key;
+        zeroize_private_key(&mut private_key);
+        assert_eq!(private_key.seed(), Field::from_u8(0));
+    }
+}
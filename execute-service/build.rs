@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+
+// Exposes the resolved snarkVM version as `SNARKVM_VERSION`, for the `/version` route.
+fn main() {
+    let version = match lockfile() {
+        Some(lockfile) => {
+            println!("cargo:rerun-if-changed={}", lockfile.display());
+            std::fs::read_to_string(&lockfile)
+                .ok()
+                .and_then(|contents| snarkvm_version(&contents))
+        }
+        None => None,
+    };
+    // A build without the version still works, but `/version` cannot name it, so the build says so.
+    let version = version.unwrap_or_else(|| {
+        println!("cargo:warning=The snarkvm version was not found in Cargo.lock, so /version reports it as 'unknown'");
+        "unknown".to_string()
+    });
+    println!("cargo:rustc-env=SNARKVM_VERSION={version}");
+}
+
+// Returns the path of the lockfile of the build, which is in the package or the workspace above it.
+fn lockfile() -> Option<PathBuf> {
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").ok()?);
+    manifest_dir
+        .ancestors()
+        .map(|directory| directory.join("Cargo.lock"))
+        .find(|lockfile| lockfile.exists())
+}
+
+// Returns the version of the `snarkvm` package in a lockfile.
+fn snarkvm_version(lockfile: &str) -> Option<String> {
+    lockfile.split("[[package]]").find_map(|package| {
+        let mut lines = package.lines().map(str::trim);
+        lines.find(|line| *line == "name = \"snarkvm\"")?;
+        lines
+            .find_map(|line| line.strip_prefix("version = \""))
+            .map(|version| version.trim_end_matches('"').to_string())
+    })
+}
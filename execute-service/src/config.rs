@@ -0,0 +1,304 @@
+use super::*;
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The prefix of the environment variables that override the configuration file, e.g. `EXECUTE_SERVICE_PORT`.
+pub const CONFIG_ENV_PREFIX: &str = "EXECUTE_SERVICE_";
+
+// The settings that may be overridden by environment variables.
+const ENV_KEYS: &[&str] = &[
+    "address",
+    "port",
+    "tls_cert",
+    "tls_key",
+    "tls_client_ca",
+    "limits_config",
+    "idempotency_ttl",
+    "workers",
+    "job_timeout",
+    "shutdown_timeout",
+    "network_id",
+    "log_format",
+    "log_filter",
+];
+
+/// The format of the log lines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Colored lines, for terminals.
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log collectors.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "pretty" => Ok(Self::Pretty),
+            "json" => Ok(Self::Json),
+            _ => bail!("Unknown log format '{format}', expected 'pretty' or 'json'"),
+        }
+    }
+}
+
+/// The configuration of the service.
+/// It is layered: the defaults, then a TOML file, then `EXECUTE_SERVICE_*` environment variables, then CLI flags.
+///
+/// ```toml
+/// address = "0.0.0.0"
+/// port = 3031
+/// workers = 4
+/// log_format = "json"
+///
+/// [limits]
+/// requests_per_second = 5.0
+/// max_body_bytes = 32768
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServiceConfig {
+    /// The address to bind to.
+    pub address: IpAddr,
+    /// The port to bind to.
+    pub port: u16,
+    /// The path to the PEM-encoded TLS certificate chain.
+    pub tls_cert: Option<PathBuf>,
+    /// The path to the PEM-encoded TLS private key.
+    pub tls_key: Option<PathBuf>,
+    /// The path to the PEM-encoded CA certificates that client certificates must be signed by.
+    pub tls_client_ca: Option<PathBuf>,
+    /// The path to a file of rate and concurrency limits (TOML), which replaces the `limits` table.
+    pub limits_config: Option<PathBuf>,
+    /// The number of seconds for which the response of a request with an `Idempotency-Key` header is stored.
+    pub idempotency_ttl: u64,
+    /// The number of worker threads for executions. If zero, one per CPU.
+    pub workers: usize,
+    /// The number of seconds after which an execution times out.
+    /// A running proof cannot be interrupted, so the execution keeps its worker and its slot until the proof completes.
+    pub job_timeout: u64,
+    /// The number of seconds for which the requests in flight may complete on shutdown, after which they are dropped.
+    pub shutdown_timeout: u64,
+    /// The ID of the network, which must be the network that the service is built for.
+    pub network_id: u16,
+    /// The format of the log lines.
+    pub log_format: LogFormat,
+    /// The log filter, in the syntax of `RUST_LOG`. If unset, `RUST_LOG` is used.
+    pub log_filter: Option<String>,
+    /// The rate, concurrency and body size limits.
+    pub limits: LimitsConfig,
+}
+
+impl Default for ServiceConfig {
+    fn default() -> Self {
+        Self {
+            address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 3031,
+            tls_cert: None,
+            tls_key: None,
+            tls_client_ca: None,
+            limits_config: None,
+            idempotency_ttl: 3600,
+            workers: 0,
+            job_timeout: 300,
+            shutdown_timeout: 300,
+            network_id: CurrentNetwork::ID,
+            log_format: LogFormat::Pretty,
+            log_filter: None,
+            limits: LimitsConfig::default(),
+        }
+    }
+}
+
+impl ServiceConfig {
+    /// Loads the configuration from the defaults, then the TOML file if any, then the environment variables.
+    /// The CLI flags must be applied afterwards, and then the configuration resolved.
+    pub fn load(path: Option<&Path>, env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let mut table = match path {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read '{}'", path.display()))?;
+                toml::from_str(&contents)
+                    .with_context(|| format!("Failed to parse '{}'", path.display()))?
+            }
+            None => toml::Table::new(),
+        };
+        for key in ENV_KEYS {
+            let var = format!("{CONFIG_ENV_PREFIX}{}", key.to_uppercase());
+            if let Some(value) = env(&var) {
+                table.insert(key.to_string(), env_value(value));
+            }
+        }
+        toml::Value::Table(table)
+            .try_into()
+            .context("The configuration is not valid")
+    }
+
+    /// Loads the limits file, if any, into the `limits` table, and validates the configuration.
+    pub fn resolve(mut self) -> Result<Self> {
+        if let Some(path) = &self.limits_config {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read '{}'", path.display()))?;
+            self.limits = toml::from_str(&contents)
+                .with_context(|| format!("Failed to parse '{}'", path.display()))?;
+        }
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Checks that the settings are consistent.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.tls_cert.is_some() == self.tls_key.is_some(),
+            "'tls_cert' and 'tls_key' must be set together"
+        );
+        ensure!(
+            self.tls_client_ca.is_none() || self.tls_cert.is_some(),
+            "'tls_client_ca' requires 'tls_cert'"
+        );
+        ensure!(
+            self.idempotency_ttl > 0,
+            "'idempotency_ttl' must be positive"
+        );
+        ensure!(self.job_timeout > 0, "'job_timeout' must be positive");
+        ensure!(
+            self.network_id == CurrentNetwork::ID,
+            "'network_id' is {}, but the service is built for network {}",
+            self.network_id,
+            CurrentNetwork::ID
+        );
+        ensure!(
+            self.limits.max_body_bytes != Some(0),
+            "'limits.max_body_bytes' must be positive"
+        );
+        if let Some(rate) = self.limits.requests_per_second {
+            ensure!(
+                rate.is_finite() && rate > 0.0,
+                "'limits.requests_per_second' must be positive"
+            );
+        }
+        if let Some(rate) = self.limits.ip_requests_per_second {
+            ensure!(
+                rate.is_finite() && rate > 0.0,
+                "'limits.ip_requests_per_second' must be positive"
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the configuration as TOML.
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+}
+
+// Parses the value of an environment variable as a TOML value, or else as a string.
+fn env_value(value: String) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or(toml::Value::String(value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_layers() {
+        let path = std::env::temp_dir().join(format!(
+            "execute-service-config-{}.toml",
+            rand::random::<u64>()
+        ));
+        std::fs::write(
+            &path,
+            "port = 8080\nworkers = 4\nlog_format = \"json\"\n\n[limits]\nmax_body_bytes = 2048\n",
+        )
+        .unwrap();
+        let env = HashMap::from([
+            ("EXECUTE_SERVICE_PORT", "9090"),
+            ("EXECUTE_SERVICE_ADDRESS", "0.0.0.0"),
+        ]);
+        let config = ServiceConfig::load(Some(&path), |var| {
+            env.get(var).map(|value| value.to_string())
+        })
+        .unwrap()
+        .resolve()
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // The environment overrides the file, which overrides the defaults.
+        assert_eq!(config.port, 9090);
+        assert_eq!(config.address.to_string(), "0.0.0.0");
+        assert_eq!(config.workers, 4);
+        assert_eq!(config.log_format, LogFormat::Json);
+        assert_eq!(config.limits.max_body_bytes, Some(2048));
+        assert_eq!(config.job_timeout, 300);
+
+        // The printed configuration loads back to the same configuration.
+        let printed = config.to_toml().unwrap();
+        assert_eq!(toml::from_str::<ServiceConfig>(&printed).unwrap(), config);
+    }
+
+    #[test]
+    fn test_validation() {
+        // Settings of the wrong type are rejected, as are inconsistent settings.
+        assert!(ServiceConfig::load(None, |var| {
+            (var == "EXECUTE_SERVICE_PORT").then(|| "not a port".to_string())
+        })
+        .is_err());
+
+        let invalid = [
+            ServiceConfig {
+                tls_cert: Some("cert.pem".into()),
+                ..Default::default()
+            },
+            ServiceConfig {
+                tls_client_ca: Some("ca.pem".into()),
+                ..Default::default()
+            },
+            ServiceConfig {
+                job_timeout: 0,
+                ..Default::default()
+            },
+            ServiceConfig {
+                network_id: CurrentNetwork::ID + 1,
+                ..Default::default()
+            },
+        ];
+        for config in invalid {
+            assert!(config.validate().is_err(), "{config:?}");
+        }
+        ServiceConfig::default().validate().unwrap();
+    }
+
+    #[test]
+    fn test_env_keys_cover_the_settings() {
+        let config = ServiceConfig {
+            tls_cert: Some(PathBuf::new()),
+            tls_key: Some(PathBuf::new()),
+            tls_client_ca: Some(PathBuf::new()),
+            limits_config: Some(PathBuf::new()),
+            log_filter: Some(String::new()),
+            ..Default::default()
+        };
+        let table = toml::Table::try_from(config).unwrap();
+        let mut keys = table
+            .keys()
+            .filter(|key| *key != "limits")
+            .collect::<Vec<_>>();
+        keys.sort();
+        let mut env_keys = ENV_KEYS.to_vec();
+        env_keys.sort();
+        assert_eq!(keys, env_keys);
+    }
+}
@@ -0,0 +1,195 @@
+use super::*;
+
+use serde::Serialize;
+use std::convert::Infallible;
+use std::fmt;
+use warp::http::header::{HeaderValue, RETRY_AFTER};
+use warp::http::StatusCode;
+use warp::reject::{
+    InvalidHeader, LengthRequired, MethodNotAllowed, MissingHeader, PayloadTooLarge, Reject,
+};
+use warp::{Rejection, Reply};
+
+/// An error that is returned to the client as a JSON body.
+/// The messages must never contain secrets, such as the authorizations.
+#[derive(Debug)]
+pub enum ServiceError {
+    /// The request body could not be decoded.
+    Decode(String),
+    /// The content type of the request body is not supported.
+    UnsupportedMediaType(String),
+    /// The idempotency key was used with a different request, or its request is still in progress.
+    Conflict(String),
+    /// The caller exceeded a rate or concurrency limit, and may retry after the given number of seconds.
+    TooManyRequests(String, u64),
+    /// The transaction could not be constructed.
+    Execution(String),
+    /// The response could not be constructed.
+    Internal(String),
+    /// The service is not ready to handle the request yet.
+    NotReady(String),
+    /// The work did not complete in time, and was cancelled.
+    Timeout(String),
+}
+
+impl ServiceError {
+    /// Returns the HTTP status code of the error.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::Decode(_) => StatusCode::BAD_REQUEST,
+            Self::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::TooManyRequests(..) => StatusCode::TOO_MANY_REQUESTS,
+            Self::Execution(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotReady(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+
+    /// Returns the stable error code of the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Decode(_) => "decode_error",
+            Self::UnsupportedMediaType(_) => "unsupported_media_type",
+            Self::Conflict(_) => "idempotency_conflict",
+            Self::TooManyRequests(..) => "too_many_requests",
+            Self::Execution(_) => "execution_error",
+            Self::Internal(_) => "internal_error",
+            Self::NotReady(_) => "not_ready",
+            Self::Timeout(_) => "timeout",
+        }
+    }
+
+    /// Returns the message of the error.
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Decode(message)
+            | Self::UnsupportedMediaType(message)
+            | Self::Conflict(message)
+            | Self::TooManyRequests(message, _)
+            | Self::Execution(message)
+            | Self::Internal(message)
+            | Self::NotReady(message)
+            | Self::Timeout(message) => message,
+        }
+    }
+
+    /// Returns the number of seconds after which the request may be retried, if any.
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::TooManyRequests(_, retry_after) => Some(*retry_after),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+impl Reject for ServiceError {}
+
+impl From<CommonError> for ServiceError {
+    fn from(error: CommonError) -> Self {
+        match error {
+            CommonError::Decode(message) => Self::Decode(message),
+            CommonError::Conflict(message) => Self::Conflict(message),
+            CommonError::TooManyRequests(message, retry_after) => {
+                Self::TooManyRequests(message, retry_after)
+            }
+            CommonError::Internal(message) => Self::Internal(message),
+            CommonError::NotReady(message) => Self::NotReady(message),
+            CommonError::Timeout(message) => Self::Timeout(message),
+        }
+    }
+}
+
+/// The JSON body of an error response.
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// Every code that may be returned in an error body, including those of rejections by warp.
+pub const ERROR_CODES: &[&str] = &[
+    "decode_error",
+    "unsupported_media_type",
+    "idempotency_conflict",
+    "too_many_requests",
+    "execution_error",
+    "internal_error",
+    "not_ready",
+    "timeout",
+    "payload_too_large",
+    "length_required",
+    "invalid_header",
+    "method_not_allowed",
+    "not_found",
+];
+
+/// Converts a rejection into a JSON error response.
+pub async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, Infallible> {
+    let mut retry_after = None;
+    let (status, code, message) = if let Some(error) = rejection.find::<ServiceError>() {
+        retry_after = error.retry_after();
+        (error.status(), error.code(), error.message().to_string())
+    } else if rejection.find::<PayloadTooLarge>().is_some() {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "payload_too_large",
+            "The request body is too large".to_string(),
+        )
+    } else if rejection.find::<LengthRequired>().is_some() {
+        (
+            StatusCode::LENGTH_REQUIRED,
+            "length_required",
+            "The request must have a content length".to_string(),
+        )
+    } else if rejection.find::<MissingHeader>().is_some()
+        || rejection.find::<InvalidHeader>().is_some()
+    {
+        (
+            StatusCode::BAD_REQUEST,
+            "invalid_header",
+            "A request header is missing or invalid".to_string(),
+        )
+    } else if rejection.find::<MethodNotAllowed>().is_some() {
+        (
+            StatusCode::METHOD_NOT_ALLOWED,
+            "method_not_allowed",
+            "The method is not allowed".to_string(),
+        )
+    } else if rejection.is_not_found() {
+        (
+            StatusCode::NOT_FOUND,
+            "not_found",
+            "The route does not exist".to_string(),
+        )
+    } else {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            "Unhandled rejection".to_string(),
+        )
+    };
+
+    // Log the error. Only the code and message are logged, never the request.
+    match status.is_server_error() {
+        true => log::error!("{} {}: {}", status.as_u16(), code, message),
+        false => log::warn!("{} {}: {}", status.as_u16(), code, message),
+    }
+
+    let mut response =
+        warp::reply::with_status(warp::reply::json(&ErrorBody { code, message }), status)
+            .into_response();
+    if let Some(retry_after) = retry_after {
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+    }
+    Ok(response)
+}
@@ -1,6 +1,24 @@
+pub mod config;
+pub use config::*;
+
+pub mod error;
+pub use error::*;
+
 mod execute;
 use execute::*;
 
+pub mod limits;
+pub use limits::*;
+
+pub mod metrics;
+pub use metrics::*;
+
+pub mod openapi;
+pub use openapi::*;
+
+pub mod process;
+pub use process::*;
+
 pub mod query;
 pub use query::*;
 
@@ -10,13 +28,9 @@ pub use request::*;
 pub mod routes;
 pub use routes::*;
 
-use snarkvm::circuit::AleoV0;
+pub use service_common::*;
+
 use snarkvm::ledger::block::Transaction;
-use snarkvm::prelude::{
-    Authorization, FromBytes, Locator, Network, Process, StatePath, Testnet3, ToBytes,
-};
+use snarkvm::prelude::{Authorization, FromBytes, Locator, Network, Process, StatePath, ToBytes};
 
 use anyhow::{anyhow, Result};
-
-pub type CurrentNetwork = Testnet3;
-pub type CurrentAleo = AleoV0;
@@ -0,0 +1,153 @@
+use super::*;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use warp::{Filter, Rejection};
+
+/// The maximum size of a request body, in bytes, unless `max_body_bytes` is configured.
+pub const DEFAULT_MAX_BODY_BYTES: u64 = 32 * 1024; // 32 kilobytes TODO (@d0cd): Check
+
+/// Returns the maximum size of a request body, in bytes.
+pub fn max_body_bytes(limits: &Limits) -> u64 {
+    limits.max_body_bytes().unwrap_or(DEFAULT_MAX_BODY_BYTES)
+}
+
+/// Limits the rate and concurrency of requests to the named route.
+/// Clients are rate limited by their IP address, under both `requests_per_second` and `ip_requests_per_second`.
+/// The extracted permit must be moved into the future of the handler, so that it is held until the request completes.
+/// A permit that is only taken by the handler closure is dropped before the request is handled.
+pub fn with_limits(
+    limits: Arc<Limits>,
+    route: &'static str,
+) -> impl Filter<Extract = (Permit,), Error = Rejection> + Clone {
+    warp::addr::remote().and_then(move |remote: Option<SocketAddr>| {
+        let limits = limits.clone();
+        async move {
+            if let Err(error) =
+                limits.check_ip_rate(remote.map(|remote| remote.ip()), Instant::now())
+            {
+                return Err(warp::reject::custom(ServiceError::from(error)));
+            }
+            let client = match remote {
+                Some(remote) => remote.ip().to_string(),
+                None => "unknown".to_string(),
+            };
+            if let Err(error) = limits.check_rate(&client, Instant::now()) {
+                return Err(warp::reject::custom(ServiceError::from(error)));
+            }
+            match limits.acquire(route) {
+                Ok(permit) => Ok(permit),
+                Err(error) => Err(warp::reject::custom(ServiceError::from(error))),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use snarkvm::prelude::{Address, PrivateKey, Value};
+    use std::str::FromStr;
+    use std::time::Duration;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn test_in_flight_requests_hold_their_slots() {
+        let limits = Arc::new(Limits::new(LimitsConfig {
+            max_in_flight: [("execute".to_string(), 1)].into_iter().collect(),
+            max_body_bytes: Some(1 << 20),
+            ..LimitsConfig::default()
+        }));
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let workers = Arc::new(
+            WorkerPool::new(1, Duration::from_secs(60), metrics.queue_depth.clone()).unwrap(),
+        );
+        let process = SharedProcess::default();
+        process.load().unwrap();
+
+        // Authorize a transfer, which is never executed, as its job is cancelled while it is queued.
+        let body = {
+            let process = process.get().unwrap();
+            let rng = &mut rand::thread_rng();
+            let private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+            let inputs = [
+                Value::<CurrentNetwork>::from_str(
+                    &Address::try_from(&private_key).unwrap().to_string(),
+                )
+                .unwrap(),
+                Value::from_str("1u64").unwrap(),
+            ];
+            let mut authorize = || {
+                process
+                    .authorize::<CurrentAleo, _>(
+                        &private_key,
+                        "credits.aleo",
+                        "transfer_public",
+                        inputs.clone().into_iter(),
+                        rng,
+                    )
+                    .unwrap()
+            };
+            let request = ExecuteRequest {
+                function_authorization: authorize(),
+                fee_authorization: authorize(),
+                state_root: None,
+                state_path: None,
+            };
+            serde_json::to_vec(&request).unwrap()
+        };
+        let routes = execute_route(
+            limits,
+            metrics.clone(),
+            process,
+            workers.clone(),
+            Arc::new(IdempotencyStore::new(Duration::from_secs(60))),
+        )
+        .recover(handle_rejection);
+        let request = || {
+            warp::test::request()
+                .method("POST")
+                .path("/execute")
+                .header("content-type", "application/json")
+                .body(body.clone())
+        };
+
+        // Occupy the only worker, so that the first request waits in the queue with its slot.
+        let (started_sender, started) = std::sync::mpsc::channel();
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        let blocker = tokio::spawn({
+            let workers = workers.clone();
+            async move {
+                workers
+                    .run(move |_| {
+                        started_sender.send(()).unwrap();
+                        let _ = released.recv();
+                    })
+                    .await
+            }
+        });
+        started.recv().unwrap();
+        let first = tokio::spawn({
+            let routes = routes.clone();
+            let request = request();
+            async move { request.reply(&routes).await }
+        });
+        while metrics.queue_depth.get() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // The second request is limited while the first one is in flight.
+        let response = request().reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "too_many_requests");
+
+        // Dropping the first request cancels its job, which releases the slot once the worker skips it.
+        first.abort();
+        let _ = first.await;
+        release.send(()).unwrap();
+        blocker.await.unwrap().unwrap();
+    }
+}
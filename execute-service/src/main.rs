@@ -1,26 +1,270 @@
 use execute_service::*;
 
+use anyhow::Result;
+
+use std::future::Future;
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use structopt::StructOpt;
 use warp::Filter;
 
+/// The CLI flags, which override the configuration file and the environment variables.
 #[derive(StructOpt, Debug)]
 struct Opt {
-    #[structopt(short, long, default_value = "3031")]
-    port: u16,
+    /// The path to the configuration file (TOML).
+    /// Its settings are overridden by `EXECUTE_SERVICE_*` environment variables, and then by these flags.
+    #[structopt(long)]
+    config: Option<PathBuf>,
+    /// Prints the effective configuration, and exits.
+    #[structopt(long)]
+    print_config: bool,
+    /// The address to bind to [default: 127.0.0.1].
+    #[structopt(short, long)]
+    address: Option<IpAddr>,
+    /// The port to bind to [default: 3031].
+    #[structopt(short, long)]
+    port: Option<u16>,
+    /// The path to the PEM-encoded TLS certificate chain.
+    #[structopt(long)]
+    tls_cert: Option<PathBuf>,
+    /// The path to the PEM-encoded TLS private key.
+    #[structopt(long)]
+    tls_key: Option<PathBuf>,
+    /// The path to the PEM-encoded CA certificates that client certificates must be signed by.
+    /// If set, clients must present a valid certificate (mutual TLS).
+    #[structopt(long)]
+    tls_client_ca: Option<PathBuf>,
+    /// The path to the rate and concurrency limits (TOML), which replaces the `limits` table of the configuration.
+    #[structopt(long)]
+    limits_config: Option<PathBuf>,
+    /// The number of worker threads for executions. If zero, one per CPU [default: 0].
+    #[structopt(long)]
+    workers: Option<usize>,
+    /// The number of seconds after which an execution times out [default: 300]. A running proof keeps its worker until it completes.
+    #[structopt(long)]
+    job_timeout: Option<u64>,
+    /// The number of seconds for which the requests in flight may complete on shutdown, after which they are dropped [default: 300].
+    #[structopt(long)]
+    shutdown_timeout: Option<u64>,
+    /// The number of seconds for which the response of a request with an `Idempotency-Key` header is stored [default: 3600].
+    #[structopt(long)]
+    idempotency_ttl: Option<u64>,
+    /// The format of the log lines, `pretty` or `json` [default: pretty].
+    #[structopt(long)]
+    log_format: Option<LogFormat>,
+    /// The log filter, in the syntax of `RUST_LOG`. If unset, `RUST_LOG` is used.
+    #[structopt(long)]
+    log_filter: Option<String>,
+}
+
+impl Opt {
+    // Loads the configuration, with the flags applied over the file and the environment.
+    fn load_config(&self) -> Result<ServiceConfig> {
+        let mut config =
+            ServiceConfig::load(self.config.as_deref(), |var| std::env::var(var).ok())?;
+        if let Some(address) = self.address {
+            config.address = address;
+        }
+        if let Some(port) = self.port {
+            config.port = port;
+        }
+        let paths = [
+            (&self.tls_cert, &mut config.tls_cert),
+            (&self.tls_key, &mut config.tls_key),
+            (&self.tls_client_ca, &mut config.tls_client_ca),
+            (&self.limits_config, &mut config.limits_config),
+        ];
+        for (flag, setting) in paths {
+            if flag.is_some() {
+                setting.clone_from(flag);
+            }
+        }
+        if let Some(workers) = self.workers {
+            config.workers = workers;
+        }
+        if let Some(job_timeout) = self.job_timeout {
+            config.job_timeout = job_timeout;
+        }
+        if let Some(shutdown_timeout) = self.shutdown_timeout {
+            config.shutdown_timeout = shutdown_timeout;
+        }
+        if let Some(idempotency_ttl) = self.idempotency_ttl {
+            config.idempotency_ttl = idempotency_ttl;
+        }
+        if let Some(log_format) = self.log_format {
+            config.log_format = log_format;
+        }
+        if self.log_filter.is_some() {
+            config.log_filter.clone_from(&self.log_filter);
+        }
+        config.resolve()
+    }
+}
+
+// Initializes the logger, with the filter and format of the configuration.
+fn init_logger(config: &ServiceConfig) {
+    let mut builder = pretty_env_logger::formatted_builder();
+    if let Some(filters) = config
+        .log_filter
+        .clone()
+        .or_else(|| std::env::var("RUST_LOG").ok())
+    {
+        builder.parse_filters(&filters);
+    }
+    if config.log_format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64();
+            let line = serde_json::json!({
+                "timestamp": timestamp,
+                "level": record.level().to_string(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{line}")
+        });
+    }
+    builder.init();
 }
 
-async fn run(port: u16) {
-    pretty_env_logger::init();
+async fn run(config: ServiceConfig) {
+    init_logger(&config);
+
+    let limits = Arc::new(Limits::new(config.limits.clone()));
+
+    let metrics = match Metrics::new() {
+        Ok(metrics) => Arc::new(metrics),
+        Err(error) => {
+            log::error!("Failed to register the metrics: {error:#}");
+            std::process::exit(1);
+        }
+    };
+
+    // Start the worker pool, which runs the executions off the async runtime.
+    let workers = match WorkerPool::new(
+        config.workers,
+        Duration::from_secs(config.job_timeout),
+        metrics.queue_depth.clone(),
+    ) {
+        Ok(workers) => Arc::new(workers),
+        Err(error) => {
+            log::error!("Failed to start the worker pool: {error:#}");
+            std::process::exit(1);
+        }
+    };
+    log::info!("Started {} workers", workers.threads());
+
+    // Load the process and warm the proving keys in the background.
+    // The service is ready once they are loaded.
+    let process = SharedProcess::default();
+    let loading = process.clone();
+    // The inclusion proving key is otherwise loaded on the first proof.
+    let warm = || {
+        let _ = CurrentNetwork::inclusion_proving_key();
+    };
+    tokio::task::spawn_blocking(move || match loading.load_with(warm) {
+        Ok(()) => log::info!("The process is loaded, the service is ready"),
+        Err(error) => {
+            log::error!("Failed to load the process: {error:#}");
+            std::process::exit(1);
+        }
+    });
+
+    let idempotency = Arc::new(IdempotencyStore::new(Duration::from_secs(
+        config.idempotency_ttl,
+    )));
 
-    let routes = execute_route().with(warp::trace(
-        |info| tracing::debug_span!("Debugging headers", headers = ?info.request_headers()),
-    ));
+    let routes = health_route()
+        .or(ready_route(process.clone()))
+        .or(version_route())
+        .or(openapi_route())
+        .or(metrics_route(metrics.clone()))
+        .or(execute_route(
+            limits,
+            metrics.clone(),
+            process,
+            workers,
+            idempotency,
+        ))
+        .recover(handle_rejection);
+    let routes = with_in_flight(&metrics, routes)
+        .with(with_metrics(metrics.clone()))
+        .with(warp::trace(
+            |info| tracing::debug_span!("Debugging headers", headers = ?info.request_headers()),
+        ));
 
-    warp::serve(routes).run(([127, 0, 0, 1], port)).await;
+    // On SIGTERM or Ctrl-C, stop accepting requests, and let the requests in flight complete until the deadline.
+    let shutdown = Shutdown::listen();
+    let signal = shutdown.clone().begun();
+    let deadline = Duration::from_secs(config.shutdown_timeout);
+    let address = SocketAddr::new(config.address, config.port);
+    // The server is boxed, as the servers with and without TLS have different types.
+    type BoxedServer = Pin<Box<dyn Future<Output = ()>>>;
+    let bound = match (config.tls_cert, config.tls_key) {
+        (Some(cert), Some(key)) => {
+            let server = warp::serve(routes).tls().cert_path(cert).key_path(key);
+            let server = match config.tls_client_ca {
+                Some(client_ca) => server.client_auth_required_path(client_ca),
+                None => server,
+            };
+            server
+                .try_bind_with_graceful_shutdown(address, signal)
+                .map(|(address, server)| (address, Box::pin(server) as BoxedServer))
+        }
+        _ => {
+            if !address.ip().is_loopback() {
+                log::warn!("Serving on {address} without TLS, requests are not encrypted");
+            }
+            warp::serve(routes)
+                .try_bind_with_graceful_shutdown(address, signal)
+                .map(|(address, server)| (address, Box::pin(server) as BoxedServer))
+        }
+    };
+    // Exit with an error if the address is taken, or the TLS files are invalid, instead of panicking.
+    let server = match bound {
+        Ok((address, server)) => {
+            log::info!("Listening on {address}");
+            server
+        }
+        Err(error) => {
+            log::error!("Failed to serve on {address}: {error}");
+            std::process::exit(1);
+        }
+    };
+    let dropped =
+        serve_until_drained(server, shutdown, deadline, || metrics.requests_in_flight()).await;
+    if dropped > 0 {
+        log::warn!("Shut down, dropping {dropped} requests in flight");
+    } else {
+        log::info!("Shut down, no requests were dropped");
+    }
 }
 
 #[tokio::main]
 async fn main() {
     let opt = Opt::from_args();
-    run(opt.port).await;
+    let config = match opt.load_config() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("Invalid configuration: {error:#}");
+            std::process::exit(1);
+        }
+    };
+    if opt.print_config {
+        match config.to_toml() {
+            Ok(config) => print!("{config}"),
+            Err(error) => {
+                eprintln!("Failed to print the configuration: {error:#}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    run(config).await
 }
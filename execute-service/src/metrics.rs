@@ -0,0 +1,217 @@
+use super::*;
+
+use anyhow::Result;
+use prometheus::core::Collector;
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::convert::Infallible;
+use std::sync::Arc;
+use warp::{Filter, Rejection, Reply};
+
+/// The metrics of the service, exposed at `/metrics` in the Prometheus text format.
+pub struct Metrics {
+    registry: Registry,
+    /// The number of requests, by route and status.
+    pub requests: IntCounterVec,
+    /// The time taken by `Process::execute`, by transition ("function" or "fee").
+    pub execute_duration: HistogramVec,
+    /// The time taken to prove an execution, by transition ("function" or "fee").
+    pub proof_duration: HistogramVec,
+    /// The number of requests in flight, by route.
+    pub in_flight: IntGaugeVec,
+    /// The number of jobs waiting for a worker.
+    pub queue_depth: IntGauge,
+}
+
+impl Metrics {
+    /// Returns a new set of metrics, registered in their own registry.
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("execute_service".to_string()), None)?;
+
+        let requests = IntCounterVec::new(
+            Opts::new(
+                "requests_total",
+                "The number of requests, by route and status",
+            ),
+            &["route", "status"],
+        )?;
+        let execute_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "execute_duration_seconds",
+                "The time taken by `Process::execute`",
+            )
+            .buckets(exponential_buckets(0.01, 2.0, 12)?),
+            &["transition"],
+        )?;
+        let proof_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "proof_duration_seconds",
+                "The time taken to prove an execution",
+            )
+            .buckets(exponential_buckets(0.1, 2.0, 12)?),
+            &["transition"],
+        )?;
+        let in_flight = IntGaugeVec::new(
+            Opts::new("in_flight_requests", "The number of requests in flight"),
+            &["route"],
+        )?;
+        let queue_depth = IntGauge::new("queue_depth", "The number of jobs waiting for a worker")?;
+
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(execute_duration.clone()))?;
+        registry.register(Box::new(proof_duration.clone()))?;
+        registry.register(Box::new(in_flight.clone()))?;
+        registry.register(Box::new(queue_depth.clone()))?;
+
+        Ok(Self {
+            registry,
+            requests,
+            execute_duration,
+            proof_duration,
+            in_flight,
+            queue_depth,
+        })
+    }
+
+    /// Counts a completed request.
+    pub fn observe_request(&self, path: &str, status: u16) {
+        self.requests
+            .with_label_values(&[route_label(path), &status.to_string()])
+            .inc();
+    }
+
+    /// Returns the number of requests in flight, across all routes.
+    pub fn requests_in_flight(&self) -> i64 {
+        self.in_flight
+            .collect()
+            .iter()
+            .flat_map(|family| family.get_metric())
+            .map(|metric| metric.get_gauge().get_value() as i64)
+            .sum()
+    }
+
+    /// Encodes the metrics in the Prometheus text format.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+// Returns the route label of a request path.
+// Paths are mapped onto a fixed set of labels, so that clients cannot create new series.
+fn route_label(path: &str) -> &'static str {
+    match path.trim_matches('/') {
+        "execute" => "execute",
+        "metrics" => "metrics",
+        "health" => "health",
+        "ready" => "ready",
+        "version" => "version",
+        "openapi.json" => "openapi",
+        _ => "other",
+    }
+}
+
+/// Counts the requests in flight, by route. This must wrap the recovered routes.
+pub fn with_in_flight<F, T>(
+    metrics: &Metrics,
+    routes: F,
+) -> impl Filter<Extract = (T,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (T,), Error = Infallible> + Clone + Send,
+    T: Reply,
+{
+    track_in_flight(metrics.in_flight.clone(), route_label, routes)
+}
+
+/// Counts every request by route and status. This must wrap the recovered routes.
+pub fn with_metrics(
+    metrics: Arc<Metrics>,
+) -> warp::log::Log<impl Fn(warp::log::Info<'_>) + Clone + Send> {
+    warp::log::custom(move |info| metrics.observe_request(info.path(), info.status().as_u16()))
+}
+
+// GET /metrics
+pub fn metrics_route(
+    metrics: Arc<Metrics>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get()
+        .and(warp::path("metrics"))
+        .and(warp::path::end())
+        .and_then(move || {
+            let metrics = metrics.clone();
+            async move {
+                let bytes = match metrics.encode() {
+                    Ok(bytes) => bytes,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Internal(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                let response = match warp::http::Response::builder()
+                    .header("content-type", prometheus::TEXT_FORMAT)
+                    .body(bytes)
+                {
+                    Ok(response) => response,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Internal(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                Ok(response)
+            }
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_scrape_metrics() {
+        let limits = Arc::new(Limits::unlimited());
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let workers = Arc::new(
+            WorkerPool::new(1, Duration::from_secs(10), metrics.queue_depth.clone()).unwrap(),
+        );
+        let routes = metrics_route(metrics.clone())
+            .or(execute_route(
+                limits,
+                metrics.clone(),
+                SharedProcess::default(),
+                workers,
+                Arc::new(IdempotencyStore::new(Duration::from_secs(60))),
+            ))
+            .recover(handle_rejection);
+        let routes = with_in_flight(&metrics, routes).with(with_metrics(metrics));
+
+        // Send a request that cannot be decoded.
+        let response = warp::test::request()
+            .method("POST")
+            .path("/execute")
+            .body(vec![0u8; 16])
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), 400);
+
+        // Scrape the metrics.
+        let response = warp::test::request()
+            .method("GET")
+            .path("/metrics")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), 200);
+        let body = String::from_utf8(response.body().to_vec()).unwrap();
+        assert!(body.contains("execute_service_requests_total{route=\"execute\",status=\"400\"} 1"));
+        // Every route is counted in flight, including the scrape itself.
+        assert!(body.contains("execute_service_in_flight_requests{route=\"execute\"} 0"));
+        assert!(body.contains("execute_service_in_flight_requests{route=\"metrics\"} 1"));
+        assert!(body.contains("execute_service_queue_depth 0"));
+    }
+}
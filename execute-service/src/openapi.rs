@@ -0,0 +1,296 @@
+use super::*;
+
+use serde_json::{json, Map, Value};
+
+/// The version of the OpenAPI specification that the document follows.
+pub const OPENAPI_VERSION: &str = "3.0.3";
+
+/// A body that has both a JSON and a binary (little-endian) form.
+/// The document is generated from these definitions, so they must match the `FromBytes` and `ToBytes` implementations.
+pub trait ApiSchema: WireMessage {
+    /// The name of the schema, under `#/components/schemas`.
+    const SCHEMA_NAME: &'static str;
+
+    /// Returns the fields of the body, in the order of the binary form.
+    fn fields() -> Vec<ApiField>;
+}
+
+/// A field of a body, with its JSON schema and its binary encoding.
+#[derive(Clone, Debug)]
+pub struct ApiField {
+    /// The name of the field in the JSON form.
+    pub name: &'static str,
+    /// The JSON schema of the field.
+    pub schema: Value,
+    /// The encoding of the field in the binary form.
+    pub encoding: &'static str,
+    /// Whether the field must be set.
+    pub required: bool,
+}
+
+impl ApiField {
+    /// Returns a required field.
+    pub fn new(name: &'static str, schema: Value, encoding: &'static str) -> Self {
+        Self {
+            name,
+            schema,
+            encoding,
+            required: true,
+        }
+    }
+
+    /// Returns an optional field, which is `null` in JSON if it is unset.
+    pub fn optional(name: &'static str, mut schema: Value, encoding: &'static str) -> Self {
+        schema["nullable"] = json!(true);
+        Self {
+            name,
+            schema,
+            encoding,
+            required: false,
+        }
+    }
+}
+
+/// Returns the JSON schema of a body, with its binary layout in the `x-binary-layout` extension.
+/// The layout begins with the header of the message.
+pub fn api_schema<T: ApiSchema>() -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    let mut layout =
+        vec![json!({ "field": "header", "encoding": wire_header_encoding(T::WIRE_KIND) })];
+    for field in T::fields() {
+        layout.push(json!({ "field": field.name, "encoding": field.encoding }));
+        if field.required {
+            required.push(field.name);
+        }
+        properties.insert(field.name.to_string(), field.schema);
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "x-binary-layout": layout,
+    })
+}
+
+impl<N: Network> ApiSchema for ExecuteRequest<N> {
+    const SCHEMA_NAME: &'static str = "ExecuteRequest";
+
+    fn fields() -> Vec<ApiField> {
+        let authorization = json!({ "type": "object", "description": "A snarkVM authorization, as serialized by snarkVM." });
+        vec![
+            ApiField::new(
+                "function_authorization",
+                authorization.clone(),
+                "Authorization, as written by snarkVM",
+            ),
+            ApiField::new(
+                "fee_authorization",
+                authorization,
+                "Authorization, as written by snarkVM",
+            ),
+            ApiField::optional(
+                "state_root",
+                json!({ "type": "string", "pattern": "^sr1", "description": "The state root to execute against." }),
+                "u8 flag, then the state root (32 bytes) if it is 1",
+            ),
+            ApiField::optional(
+                "state_path",
+                json!({ "type": "string", "description": "The state path of the fee record, as printed by snarkVM." }),
+                "u8 flag, then the state path, as written by snarkVM, if it is 1",
+            ),
+        ]
+    }
+}
+
+/// Returns the OpenAPI document of the service.
+pub fn openapi() -> Value {
+    let mut schemas = Map::new();
+    schemas.insert(
+        <ExecuteRequest<CurrentNetwork> as ApiSchema>::SCHEMA_NAME.to_string(),
+        api_schema::<ExecuteRequest<CurrentNetwork>>(),
+    );
+    schemas.insert(
+        "Transaction".to_string(),
+        json!({
+            "type": "object",
+            "description": "A snarkVM transaction, as serialized by snarkVM.",
+            "x-binary-layout": [{ "field": "transaction", "encoding": "Transaction, as written by snarkVM" }],
+        }),
+    );
+    schemas.insert(
+        "Error".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "code": { "type": "string", "enum": ERROR_CODES },
+                "message": { "type": "string" },
+            },
+            "required": ["code", "message"],
+        }),
+    );
+    schemas.insert(
+        "Version".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "version": { "type": "string" },
+                "snarkvm_version": { "type": "string" },
+                "network_id": { "type": "integer" },
+            },
+            "required": ["name", "version", "snarkvm_version", "network_id"],
+        }),
+    );
+
+    let error = json!({ "$ref": "#/components/responses/Error" });
+    let execute = json!({
+        "summary": "Executes a function authorization and its fee authorization into a transaction.",
+        "x-route": "execute",
+        "parameters": [{
+            "name": IDEMPOTENCY_KEY_HEADER,
+            "in": "header",
+            "required": false,
+            "description": "A key of up to 255 bytes. A retry with the same key, body and response encoding returns the stored response, and one with a different body or response encoding is a conflict.",
+            "schema": { "type": "string", "minLength": 1, "maxLength": MAX_IDEMPOTENCY_KEY_LENGTH },
+        }],
+        "requestBody": {
+            "required": true,
+            "content": encoded_content(&schemas, "ExecuteRequest"),
+        },
+        "responses": {
+            "200": {
+                "description": "The transaction, in the encoding of the 'Accept' header, or else of the request.",
+                "content": encoded_content(&schemas, "Transaction"),
+                "headers": {
+                    IDEMPOTENT_REPLAYED_HEADER: {
+                        "description": "Set to 'true' if the response was stored by an earlier request with the same idempotency key.",
+                        "schema": { "type": "string" },
+                    },
+                },
+            },
+            "400": error,
+            "409": error,
+            "415": error,
+            "429": error,
+            "500": error,
+            "503": error,
+            "504": error,
+            "default": error,
+        },
+    });
+
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": { "title": "execute-service", "version": env!("CARGO_PKG_VERSION") },
+        "paths": {
+            "/health": {
+                "get": {
+                    "summary": "Returns whether the service is up.",
+                    "responses": { "200": { "description": "The service is up." }, "default": error },
+                },
+            },
+            "/ready": {
+                "get": {
+                    "summary": "Returns whether the service is ready to handle requests.",
+                    "responses": { "200": { "description": "The service is ready." }, "503": error, "default": error },
+                },
+            },
+            "/version": {
+                "get": {
+                    "summary": "Returns the version of the service.",
+                    "responses": {
+                        "200": {
+                            "description": "The version.",
+                            "content": { JSON_CONTENT_TYPE: { "schema": { "$ref": "#/components/schemas/Version" } } },
+                        },
+                        "default": error,
+                    },
+                },
+            },
+            "/metrics": {
+                "get": {
+                    "summary": "Returns the metrics of the service, in the Prometheus text format.",
+                    "responses": {
+                        "200": { "description": "The metrics.", "content": { "text/plain": { "schema": { "type": "string" } } } },
+                        "default": error,
+                    },
+                },
+            },
+            "/openapi.json": {
+                "get": {
+                    "summary": "Returns this document.",
+                    "responses": {
+                        "200": { "description": "The OpenAPI document.", "content": { JSON_CONTENT_TYPE: {} } },
+                        "default": error,
+                    },
+                },
+            },
+            "/execute": { "post": execute },
+        },
+        "components": {
+            "schemas": schemas,
+            "responses": {
+                "Error": {
+                    "description": "An error, with a stable code. Rate-limited responses carry a 'Retry-After' header.",
+                    "content": { JSON_CONTENT_TYPE: { "schema": { "$ref": "#/components/schemas/Error" } } },
+                },
+            },
+        },
+    })
+}
+
+// Returns the content of a body that has both a JSON and a binary form.
+fn encoded_content(schemas: &Map<String, Value>, name: &str) -> Value {
+    json!({
+        JSON_CONTENT_TYPE: { "schema": { "$ref": format!("#/components/schemas/{name}") } },
+        BINARY_CONTENT_TYPE: {
+            "schema": { "type": "string", "format": "binary" },
+            "x-binary-layout": schemas[name]["x-binary-layout"],
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_document() {
+        let document = openapi();
+        assert_eq!(document["openapi"], OPENAPI_VERSION);
+        let execute = &document["paths"]["/execute"]["post"];
+        let layout = execute["requestBody"]["content"][BINARY_CONTENT_TYPE]["x-binary-layout"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| field["field"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            layout,
+            [
+                "header",
+                "function_authorization",
+                "fee_authorization",
+                "state_root",
+                "state_path"
+            ]
+        );
+        let schema = &document["components"]["schemas"]["ExecuteRequest"];
+        assert_eq!(schema["required"].as_array().unwrap().len(), 2);
+        assert_eq!(schema["properties"]["state_root"]["nullable"], true);
+
+        for error in [
+            ServiceError::Decode(String::new()),
+            ServiceError::UnsupportedMediaType(String::new()),
+            ServiceError::Conflict(String::new()),
+            ServiceError::TooManyRequests(String::new(), 0),
+            ServiceError::Execution(String::new()),
+            ServiceError::Internal(String::new()),
+            ServiceError::NotReady(String::new()),
+            ServiceError::Timeout(String::new()),
+        ] {
+            assert!(ERROR_CODES.contains(&error.code()), "{}", error.code());
+        }
+    }
+}
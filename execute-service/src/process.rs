@@ -0,0 +1,61 @@
+use super::*;
+
+use serde::Serialize;
+
+/// The version of the service.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Version {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub snarkvm_version: &'static str,
+    pub network_id: u16,
+}
+
+impl Version {
+    /// Returns the version of this build.
+    pub fn current() -> Self {
+        Self {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            snarkvm_version: env!("SNARKVM_VERSION"),
+            network_id: CurrentNetwork::ID,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use warp::Filter;
+
+    #[tokio::test]
+    async fn test_health_ready_version() {
+        let process = SharedProcess::default();
+        let routes = health_route()
+            .or(ready_route(process.clone()))
+            .or(version_route())
+            .recover(handle_rejection);
+
+        // The service is live, but not ready until the process is loaded.
+        let response = warp::test::request().path("/health").reply(&routes).await;
+        assert_eq!(response.status(), 200);
+        let response = warp::test::request().path("/ready").reply(&routes).await;
+        assert_eq!(response.status(), 503);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "not_ready");
+
+        process.load().unwrap();
+        let response = warp::test::request().path("/ready").reply(&routes).await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["status"], "ready");
+
+        // The version names the crate, the network and the resolved snarkVM version.
+        let response = warp::test::request().path("/version").reply(&routes).await;
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["name"], "execute-service");
+        assert_eq!(body["network_id"], CurrentNetwork::ID);
+        assert_ne!(body["snarkvm_version"], "unknown");
+    }
+}
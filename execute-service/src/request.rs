@@ -1,9 +1,11 @@
 use super::*;
 
+use serde::{Deserialize, Serialize};
 use snarkvm::prelude::{error, IoResult};
 use std::io::{Read, Write};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ExecuteRequest<N: Network> {
     pub function_authorization: Authorization<N>,
     pub fee_authorization: Authorization<N>,
@@ -11,11 +13,17 @@ pub struct ExecuteRequest<N: Network> {
     pub state_path: Option<StatePath<N>>,
 }
 
+impl<N: Network> WireMessage for ExecuteRequest<N> {
+    const WIRE_KIND: WireKind = WireKind::ExecuteRequest;
+}
+
 impl<N: Network> FromBytes for ExecuteRequest<N> {
-    fn read_le<R: Read>(mut reader: R) -> IoResult<Self>
+    fn read_le<R: Read>(reader: R) -> IoResult<Self>
     where
         Self: Sized,
     {
+        // The layout of the fields is the same in every version.
+        let (_, mut reader) = read_wire_header::<N, _>(Self::WIRE_KIND, reader)?;
         let function_authorization = Authorization::read_le(&mut reader)?;
         let fee_authorization = Authorization::read_le(&mut reader)?;
         let state_root = match u8::read_le(&mut reader)? {
@@ -42,6 +50,7 @@ impl<N: Network> ToBytes for ExecuteRequest<N> {
     where
         Self: Sized,
     {
+        write_wire_header::<N, _>(Self::WIRE_KIND, &mut writer)?;
         self.function_authorization.write_le(&mut writer)?;
         self.fee_authorization.write_le(&mut writer)?;
         match &self.state_root {
@@ -1,34 +1,173 @@
 use super::*;
 
+use std::sync::Arc;
+use std::time::Instant;
 use warp::{http::Response, hyper::body::Bytes, Filter, Rejection, Reply};
 
+// GET /health
+pub fn health_route() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get()
+        .and(warp::path("health"))
+        .and(warp::path::end())
+        .map(|| warp::reply::json(&serde_json::json!({ "status": "ok" })))
+}
+
+// GET /ready
+pub fn ready_route(
+    process: SharedProcess,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get()
+        .and(warp::path("ready"))
+        .and(warp::path::end())
+        .and_then(move || {
+            let process = process.clone();
+            async move {
+                match process.get() {
+                    Ok(_) => Ok(warp::reply::json(&serde_json::json!({ "status": "ready" }))),
+                    Err(error) => Err(warp::reject::custom(ServiceError::from(error))),
+                }
+            }
+        })
+}
+
+// GET /version
+pub fn version_route() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get()
+        .and(warp::path("version"))
+        .and(warp::path::end())
+        .map(|| warp::reply::json(&Version::current()))
+}
+
+// GET /openapi.json
+pub fn openapi_route() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let document = Arc::new(openapi());
+    warp::get()
+        .and(warp::path("openapi.json"))
+        .and(warp::path::end())
+        .map(move || warp::reply::json(document.as_ref()))
+}
+
 // POST /execute
-pub fn execute_route() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+pub fn execute_route(
+    limits: Arc<Limits>,
+    metrics: Arc<Metrics>,
+    process: SharedProcess,
+    workers: Arc<WorkerPool>,
+    idempotency: Arc<IdempotencyStore>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let max_body_bytes = max_body_bytes(&limits);
     warp::post()
         .and(warp::path("execute"))
         .and(warp::path::end())
-        .and(warp::body::content_length_limit(32 * 1024)) // 32 kilobytes TODO (@d0cd): Check
+        .and(with_limits(limits, "execute"))
+        .and(warp::body::content_length_limit(max_body_bytes))
+        .and(warp::header::optional::<String>("content-type"))
+        .and(warp::header::optional::<String>("accept"))
+        .and(warp::header::optional::<String>(IDEMPOTENCY_KEY_HEADER))
         .and(warp::body::bytes())
-        .and_then(|bytes: Bytes| async move {
-            let request = match ExecuteRequest::from_bytes_le(&bytes) {
-                Ok(request) => request,
-                Err(_) => return Err(warp::reject()),
-            };
-            let execution = match execute(request) {
-                Ok(execution) => execution,
-                Err(_) => return Err(warp::reject()),
-            };
-            let bytes = match execution.to_bytes_le() {
-                Ok(bytes) => bytes,
-                Err(_) => return Err(warp::reject()),
-            };
-            let response = match Response::builder()
-                .header("content-type", "application/octet-stream")
-                .body(bytes)
-            {
-                Ok(response) => response,
-                Err(_) => return Err(warp::reject()),
-            };
-            Ok(response)
-        })
+        .and(warp::any().map(move || metrics.clone()))
+        .and(warp::any().map(move || process.clone()))
+        .and(warp::any().map(move || workers.clone()))
+        .and(warp::any().map(move || idempotency.clone()))
+        .and_then(
+            |permit: Permit,
+             content_type: Option<String>,
+             accept: Option<String>,
+             idempotency_key: Option<String>,
+             bytes: Bytes,
+             metrics: Arc<Metrics>,
+             process: SharedProcess,
+             workers: Arc<WorkerPool>,
+             idempotency: Arc<IdempotencyStore>| async move {
+                let encoding = match Encoding::from_content_type(content_type.as_deref()) {
+                    Ok(encoding) => encoding,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::UnsupportedMediaType(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                // Responses are encoded as the request is, unless the `Accept` header asks otherwise.
+                let response_encoding = Encoding::from_accept(accept.as_deref(), encoding);
+                // A retry under an idempotency key returns the stored response, without executing again.
+                let guard = match idempotency_key {
+                    Some(key) => {
+                        let fingerprint =
+                            IdempotencyStore::fingerprint("execute", response_encoding, &bytes);
+                        // The service has no callers, so every key is scoped to the empty caller.
+                        match idempotency.begin("", &key, fingerprint, Instant::now()) {
+                            Ok(Idempotency::New(guard)) => Some(guard),
+                            Ok(Idempotency::Replay(response)) => {
+                                return replay_response(response).map_err(|error| {
+                                    warp::reject::custom(ServiceError::from(error))
+                                })
+                            }
+                            Err(error) => {
+                                return Err(warp::reject::custom(ServiceError::from(error)))
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                let request = match encoding.decode::<ExecuteRequest<CurrentNetwork>>(&bytes) {
+                    Ok(request) => request,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Decode(format!(
+                            "Failed to decode the execute request: {error}"
+                        ))))
+                    }
+                };
+                let process = match process.get() {
+                    Ok(process) => process,
+                    Err(error) => return Err(warp::reject::custom(ServiceError::from(error))),
+                };
+                // A proof cannot be interrupted, so the job holds the slot until it stops,
+                // even after the request times out.
+                let execution = {
+                    let metrics = metrics.clone();
+                    let job_permit = permit.clone();
+                    workers
+                        .run(move |cancellation| {
+                            let _permit = job_permit;
+                            execute(&process, request, &metrics, cancellation)
+                        })
+                        .await
+                };
+                let execution = match execution {
+                    Ok(Ok(execution)) => execution,
+                    Ok(Err(error)) => {
+                        return Err(warp::reject::custom(ServiceError::Execution(format!(
+                            "Failed to execute the authorizations: {error}"
+                        ))))
+                    }
+                    Err(error) => return Err(warp::reject::custom(ServiceError::from(error))),
+                };
+                let bytes = match response_encoding.encode(&execution) {
+                    Ok(bytes) => bytes,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Internal(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                if let Some(guard) = guard {
+                    guard.complete(StoredResponse {
+                        content_type: response_encoding.content_type(),
+                        body: bytes.clone(),
+                    });
+                }
+                let response = match Response::builder()
+                    .header("content-type", response_encoding.content_type())
+                    .body(bytes)
+                {
+                    Ok(response) => response,
+                    Err(error) => {
+                        return Err(warp::reject::custom(ServiceError::Internal(
+                            error.to_string(),
+                        )))
+                    }
+                };
+                Ok(response)
+            },
+        )
 }
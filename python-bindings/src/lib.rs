@@ -0,0 +1,245 @@
+use authorize_service::{AuthorizeRequest, AuthorizeResponse, Signer};
+use execute_service::ExecuteRequest;
+
+use snarkvm::prelude::{
+    Address, Authorization, FromBytes, Network, PrivateKey, StatePath, Testnet3, ToBytes, ViewKey,
+    U64,
+};
+
+use core::str::FromStr;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+type CurrentNetwork = Testnet3;
+
+/// Converts any displayable error into a Python `ValueError`.
+fn to_py_err(error: impl std::fmt::Display) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+/// Returns a new, randomly sampled private key.
+#[pyfunction]
+fn new_private_key() -> PyResult<String> {
+    let private_key =
+        PrivateKey::<CurrentNetwork>::new(&mut rand::thread_rng()).map_err(to_py_err)?;
+    Ok(private_key.to_string())
+}
+
+/// Derives a private key from a seed, given as a field element (e.g. "1234field" or "1234").
+#[pyfunction]
+fn private_key_from_seed(seed: &str) -> PyResult<String> {
+    let private_key =
+        authorize_service::private_key_from_seed::<CurrentNetwork>(seed).map_err(to_py_err)?;
+    Ok(private_key.to_string())
+}
+
+/// Derives the view key of a private key.
+#[pyfunction]
+fn view_key_from_private_key(private_key: &str) -> PyResult<String> {
+    let private_key = PrivateKey::<CurrentNetwork>::from_str(private_key).map_err(to_py_err)?;
+    let view_key = ViewKey::try_from(&private_key).map_err(to_py_err)?;
+    Ok(view_key.to_string())
+}
+
+/// Derives the address of a private key.
+#[pyfunction]
+fn address_from_private_key(private_key: &str) -> PyResult<String> {
+    let private_key = PrivateKey::<CurrentNetwork>::from_str(private_key).map_err(to_py_err)?;
+    let address = Address::try_from(&private_key).map_err(to_py_err)?;
+    Ok(address.to_string())
+}
+
+/// Authorizes a public transfer, returning the bytes of an `AuthorizeResponse`.
+#[pyfunction]
+fn authorize_transfer_public(
+    py: Python,
+    private_key: &str,
+    recipient: &str,
+    amount_in_microcredits: u64,
+    priority_fee_in_microcredits: u64,
+) -> PyResult<PyObject> {
+    // Release the GIL while the authorizations are computed.
+    let (function_authorization, fee_authorization) = py
+        .allow_threads(|| {
+            authorize_credits::authorize_transfer_public::<CurrentNetwork>(
+                private_key,
+                recipient,
+                amount_in_microcredits,
+                priority_fee_in_microcredits,
+                &mut rand::thread_rng(),
+            )
+        })
+        .map_err(to_py_err)?;
+    authorize_response_to_bytes(py, function_authorization, fee_authorization)
+}
+
+/// Authorizes a private to public transfer, returning the bytes of an `AuthorizeResponse`.
+#[pyfunction]
+fn authorize_transfer_private_to_public(
+    py: Python,
+    private_key: &str,
+    record_microcredits: u64,
+    record_nonce: &str,
+    recipient: &str,
+    amount_in_microcredits: u64,
+    priority_fee_in_microcredits: u64,
+) -> PyResult<PyObject> {
+    // Release the GIL while the authorizations are computed.
+    let (function_authorization, fee_authorization) = py
+        .allow_threads(|| {
+            authorize_credits::authorize_transfer_private_to_public::<CurrentNetwork>(
+                private_key,
+                record_microcredits,
+                record_nonce,
+                recipient,
+                amount_in_microcredits,
+                priority_fee_in_microcredits,
+                &mut rand::thread_rng(),
+            )
+        })
+        .map_err(to_py_err)?;
+    authorize_response_to_bytes(py, function_authorization, fee_authorization)
+}
+
+/// Encodes an `AuthorizeRequest` into its little-endian byte representation.
+#[pyfunction]
+fn encode_authorize_request(
+    py: Python,
+    private_key: &str,
+    recipient: &str,
+    amount_in_microcredits: u64,
+    priority_fee_in_microcredits: u64,
+) -> PyResult<PyObject> {
+    let request = AuthorizeRequest::<CurrentNetwork> {
+        signer: Signer::PrivateKey(PrivateKey::from_str(private_key).map_err(to_py_err)?),
+        recipient: Address::from_str(recipient).map_err(to_py_err)?,
+        amount_in_microcredits: U64::new(amount_in_microcredits),
+        priority_fee_in_microcredits: U64::new(priority_fee_in_microcredits),
+    };
+    let bytes = request.to_bytes_le().map_err(to_py_err)?;
+    Ok(PyBytes::new(py, &bytes).into())
+}
+
+/// Decodes the bytes of an `AuthorizeRequest` into a dictionary.
+#[pyfunction]
+fn decode_authorize_request(py: Python, bytes: &[u8]) -> PyResult<PyObject> {
+    let request = AuthorizeRequest::<CurrentNetwork>::from_bytes_le(bytes).map_err(to_py_err)?;
+    let dict = PyDict::new(py);
+    match &request.signer {
+        Signer::PrivateKey(private_key) => dict.set_item("private_key", private_key.to_string())?,
+        Signer::KeyId(key_id) => dict.set_item("key_id", key_id)?,
+    }
+    dict.set_item("recipient", request.recipient.to_string())?;
+    dict.set_item("amount_in_microcredits", *request.amount_in_microcredits)?;
+    dict.set_item(
+        "priority_fee_in_microcredits",
+        *request.priority_fee_in_microcredits,
+    )?;
+    Ok(dict.into())
+}
+
+/// Encodes a function and fee authorization, given as JSON, into the bytes of an `AuthorizeResponse`.
+#[pyfunction]
+fn encode_authorize_response(
+    py: Python,
+    function_authorization: &str,
+    fee_authorization: &str,
+) -> PyResult<PyObject> {
+    authorize_response_to_bytes(
+        py,
+        Authorization::from_str(function_authorization).map_err(to_py_err)?,
+        Authorization::from_str(fee_authorization).map_err(to_py_err)?,
+    )
+}
+
+/// Decodes the bytes of an `AuthorizeResponse` into a `(function_authorization, fee_authorization)` tuple of JSON strings.
+#[pyfunction]
+fn decode_authorize_response(bytes: &[u8]) -> PyResult<(String, String)> {
+    let response = AuthorizeResponse::<CurrentNetwork>::from_bytes_le(bytes).map_err(to_py_err)?;
+    Ok((
+        response.function_authorization.to_string(),
+        response.fee_authorization.to_string(),
+    ))
+}
+
+/// Encodes an `ExecuteRequest` into its little-endian byte representation.
+/// The authorizations are given as JSON, and the state root and state path as strings.
+#[pyfunction]
+#[pyo3(signature = (function_authorization, fee_authorization, state_root=None, state_path=None))]
+fn encode_execute_request(
+    py: Python,
+    function_authorization: &str,
+    fee_authorization: &str,
+    state_root: Option<&str>,
+    state_path: Option<&str>,
+) -> PyResult<PyObject> {
+    let request = ExecuteRequest::<CurrentNetwork> {
+        function_authorization: Authorization::from_str(function_authorization)
+            .map_err(to_py_err)?,
+        fee_authorization: Authorization::from_str(fee_authorization).map_err(to_py_err)?,
+        state_root: state_root
+            .map(<CurrentNetwork as Network>::StateRoot::from_str)
+            .transpose()
+            .map_err(to_py_err)?,
+        state_path: state_path
+            .map(StatePath::from_str)
+            .transpose()
+            .map_err(to_py_err)?,
+    };
+    let bytes = request.to_bytes_le().map_err(to_py_err)?;
+    Ok(PyBytes::new(py, &bytes).into())
+}
+
+/// Decodes the bytes of an `ExecuteRequest` into a dictionary.
+#[pyfunction]
+fn decode_execute_request(py: Python, bytes: &[u8]) -> PyResult<PyObject> {
+    let request = ExecuteRequest::<CurrentNetwork>::from_bytes_le(bytes).map_err(to_py_err)?;
+    let dict = PyDict::new(py);
+    dict.set_item(
+        "function_authorization",
+        request.function_authorization.to_string(),
+    )?;
+    dict.set_item("fee_authorization", request.fee_authorization.to_string())?;
+    dict.set_item(
+        "state_root",
+        request.state_root.map(|state_root| state_root.to_string()),
+    )?;
+    dict.set_item(
+        "state_path",
+        request.state_path.map(|state_path| state_path.to_string()),
+    )?;
+    Ok(dict.into())
+}
+
+// Encodes a pair of authorizations as the bytes of an `AuthorizeResponse`.
+fn authorize_response_to_bytes(
+    py: Python,
+    function_authorization: Authorization<CurrentNetwork>,
+    fee_authorization: Authorization<CurrentNetwork>,
+) -> PyResult<PyObject> {
+    let response = AuthorizeResponse {
+        function_authorization,
+        fee_authorization,
+    };
+    let bytes = response.to_bytes_le().map_err(to_py_err)?;
+    Ok(PyBytes::new(py, &bytes).into())
+}
+
+/// Python bindings for the Aleo authorization tools.
+#[pymodule]
+fn aleo_tools(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(new_private_key, m)?)?;
+    m.add_function(wrap_pyfunction!(private_key_from_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(view_key_from_private_key, m)?)?;
+    m.add_function(wrap_pyfunction!(address_from_private_key, m)?)?;
+    m.add_function(wrap_pyfunction!(authorize_transfer_public, m)?)?;
+    m.add_function(wrap_pyfunction!(authorize_transfer_private_to_public, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_authorize_request, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_authorize_request, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_authorize_response, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_authorize_response, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_execute_request, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_execute_request, m)?)?;
+    Ok(())
+}
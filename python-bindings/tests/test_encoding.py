@@ -0,0 +1,59 @@
+import json
+
+import pytest
+
+import aleo_tools
+
+PRIVATE_KEY = "APrivateKey1zkpCE9rCw9SixY82xaDrW2Hwxc2f3VjeuR2oZHR81zcuUDV"
+RECIPIENT = "aleo1zcsyu7wfrdp4n6gq752p3np45sat9d6zun2uhjer2h4skccsgsgq7ndrnj"
+
+
+def test_authorize_request_round_trip():
+    encoded = aleo_tools.encode_authorize_request(PRIVATE_KEY, RECIPIENT, 100, 10)
+    assert isinstance(encoded, bytes)
+    decoded = aleo_tools.decode_authorize_request(encoded)
+    assert decoded == {
+        "private_key": PRIVATE_KEY,
+        "recipient": RECIPIENT,
+        "amount_in_microcredits": 100,
+        "priority_fee_in_microcredits": 10,
+    }
+
+
+def test_decode_truncated_authorize_request():
+    encoded = aleo_tools.encode_authorize_request(PRIVATE_KEY, RECIPIENT, 100, 10)
+    with pytest.raises(ValueError):
+        aleo_tools.decode_authorize_request(encoded[:-1])
+
+
+def test_authorize_transfer_public():
+    encoded = aleo_tools.authorize_transfer_public(PRIVATE_KEY, RECIPIENT, 100, 10)
+    function_authorization, fee_authorization = aleo_tools.decode_authorize_response(encoded)
+
+    # The authorizations are returned as JSON.
+    function_authorization_json = json.loads(function_authorization)
+    fee_authorization_json = json.loads(fee_authorization)
+    assert "credits.aleo" in function_authorization
+    assert "transfer_public" in function_authorization
+    assert "fee_public" in fee_authorization
+    assert function_authorization_json and fee_authorization_json
+
+    # Re-encoding the authorizations produces the same bytes.
+    assert aleo_tools.encode_authorize_response(function_authorization, fee_authorization) == encoded
+
+
+def test_execute_request_round_trip():
+    encoded = aleo_tools.authorize_transfer_public(PRIVATE_KEY, RECIPIENT, 100, 10)
+    function_authorization, fee_authorization = aleo_tools.decode_authorize_response(encoded)
+
+    request = aleo_tools.encode_execute_request(function_authorization, fee_authorization)
+    decoded = aleo_tools.decode_execute_request(request)
+    assert decoded["function_authorization"] == function_authorization
+    assert decoded["fee_authorization"] == fee_authorization
+    assert decoded["state_root"] is None
+    assert decoded["state_path"] is None
+
+
+def test_decode_invalid_execute_request():
+    with pytest.raises(ValueError):
+        aleo_tools.decode_execute_request(b"\x00\x01\x02")
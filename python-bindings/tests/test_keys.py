@@ -0,0 +1,37 @@
+import pytest
+
+import aleo_tools
+
+PRIVATE_KEY = "APrivateKey1zkpCE9rCw9SixY82xaDrW2Hwxc2f3VjeuR2oZHR81zcuUDV"
+
+
+def test_new_private_key():
+    private_key = aleo_tools.new_private_key()
+    assert private_key.startswith("APrivateKey1")
+    assert private_key != aleo_tools.new_private_key()
+
+
+def test_private_key_from_seed_is_deterministic():
+    first = aleo_tools.private_key_from_seed("94030298402398402")
+    second = aleo_tools.private_key_from_seed("94030298402398402")
+    assert first == second
+    assert first.startswith("APrivateKey1")
+    assert first != aleo_tools.private_key_from_seed("94030298402398403")
+
+
+def test_private_key_from_invalid_seed():
+    with pytest.raises(ValueError):
+        aleo_tools.private_key_from_seed("not a field")
+
+
+def test_view_key_and_address():
+    view_key = aleo_tools.view_key_from_private_key(PRIVATE_KEY)
+    address = aleo_tools.address_from_private_key(PRIVATE_KEY)
+    assert view_key.startswith("AViewKey1")
+    assert address.startswith("aleo1")
+    assert address == aleo_tools.address_from_private_key(PRIVATE_KEY)
+
+
+def test_invalid_private_key():
+    with pytest.raises(ValueError):
+        aleo_tools.address_from_private_key("APrivateKey1invalid")
@@ -0,0 +1,220 @@
+use super::*;
+
+use authorize_service::{
+    hmac_signature, ApiSchema, AuthorizeResponse, CreditsRequest, CurrentNetwork, DeriveRequest,
+    DeriveResponse, FeeSuggestion, ImportKeyRequest, KeyInfo, KeygenRequest, KeygenResponse,
+    API_KEY_HEADER, AUTH_KEY_ID_HEADER, AUTH_SIGNATURE_HEADER, AUTH_TIMESTAMP_HEADER,
+    BINARY_CONTENT_TYPE, JSON_CONTENT_TYPE,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use snarkvm::prelude::{FromBytes, PrivateKey, ToBytes};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The credentials that requests to the authorize service are sent with.
+#[derive(Clone, Default)]
+pub enum Credentials {
+    /// The requests are not authenticated.
+    #[default]
+    None,
+    /// A static API key, sent in the `X-Api-Key` header.
+    ApiKey(String),
+    /// An HMAC secret and its key ID. Every request is signed with the secret.
+    Hmac { key_id: String, secret: Vec<u8> },
+}
+
+/// A typed client of the authorize service.
+/// The routes and bodies are those of the service's OpenAPI document.
+#[derive(Clone)]
+pub struct AuthorizeClient {
+    base_url: String,
+    http: Client,
+    credentials: Credentials,
+}
+
+impl AuthorizeClient {
+    /// Returns a client of the service at the given base URL, e.g. `http://localhost:8080`.
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http: Client::new(),
+            credentials: Credentials::None,
+        }
+    }
+
+    /// Returns the client, sending every request with the given credentials.
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Generates an account from a seed of at least 32 random bytes, encoded as hex,
+    /// or from the service's entropy if the seed is unset.
+    pub async fn keygen(&self, seed: Option<&str>) -> Result<KeygenResponse<CurrentNetwork>> {
+        let request = KeygenRequest {
+            seed: seed.map(str::to_string),
+        };
+        self.json(Method::POST, "/keygen", Some(&request)).await
+    }
+
+    /// Derives the account at an index from a mnemonic, or from a new mnemonic if it is unset.
+    pub async fn derive(&self, request: &DeriveRequest) -> Result<DeriveResponse<CurrentNetwork>> {
+        self.json(Method::POST, "/keygen/derive", Some(request))
+            .await
+    }
+
+    /// Imports a private key into the keystore.
+    pub async fn import_key(&self, private_key: PrivateKey<CurrentNetwork>) -> Result<KeyInfo> {
+        let request = ImportKeyRequest { private_key };
+        self.json(Method::POST, "/keys", Some(&request)).await
+    }
+
+    /// Generates a private key in the keystore.
+    pub async fn generate_key(&self) -> Result<KeyInfo> {
+        self.json(Method::POST, "/keys/generate", None::<&()>).await
+    }
+
+    /// Lists the keys of the caller in the keystore.
+    pub async fn list_keys(&self) -> Result<Vec<KeyInfo>> {
+        self.json(Method::GET, "/keys", None::<&()>).await
+    }
+
+    /// Deletes a key of the caller from the keystore.
+    pub async fn delete_key(&self, id: &str) -> Result<()> {
+        self.send(Method::DELETE, &format!("/keys/{id}"), None, Vec::new())
+            .await?;
+        Ok(())
+    }
+
+    /// Suggests priority fees for a function in `credits.aleo`, from the fees paid in recent blocks.
+    pub async fn suggest_fee(&self, function: Option<&str>) -> Result<FeeSuggestion> {
+        let path = match function {
+            Some(function) => format!("/fee/suggest?function={function}"),
+            None => "/fee/suggest".to_string(),
+        };
+        self.json(Method::GET, &path, None::<&()>).await
+    }
+
+    /// Authorizes a call to a function in `credits.aleo`, and its fee.
+    /// The request and response are sent in their binary form.
+    pub async fn authorize<R: CreditsRequest<CurrentNetwork> + ApiSchema>(
+        &self,
+        request: &R,
+    ) -> Result<AuthorizeResponse<CurrentNetwork>> {
+        let path = format!("/authorize/{}", R::FUNCTION_NAME);
+        let bytes = self
+            .send(
+                Method::POST,
+                &path,
+                Some(BINARY_CONTENT_TYPE),
+                request.to_bytes_le()?,
+            )
+            .await?;
+        AuthorizeResponse::from_bytes_le(&bytes)
+    }
+
+    /// Returns the OpenAPI document of the service.
+    pub async fn openapi(&self) -> Result<serde_json::Value> {
+        self.json(Method::GET, "/openapi.json", None::<&()>).await
+    }
+
+    // Sends a request with an optional JSON body, and decodes the JSON response.
+    async fn json<T: Serialize, U: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&T>,
+    ) -> Result<U> {
+        let (content_type, body) = match body {
+            Some(body) => (Some(JSON_CONTENT_TYPE), serde_json::to_vec(body)?),
+            None => (None, Vec::new()),
+        };
+        let bytes = self.send(method, path, content_type, body).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    // Sends a request with the credentials of the client, and returns the body of the response.
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        content_type: Option<&str>,
+        body: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let mut request = self
+            .http
+            .request(method.clone(), format!("{}{path}", self.base_url));
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+        match &self.credentials {
+            Credentials::None => {}
+            Credentials::ApiKey(api_key) => request = request.header(API_KEY_HEADER, api_key),
+            Credentials::Hmac { key_id, secret } => {
+                // The service signs the path without its query.
+                let path = path.split('?').next().unwrap_or_default();
+                let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+                let signature = hmac_signature(secret, &method, path, timestamp, &body);
+                request = request
+                    .header(AUTH_KEY_ID_HEADER, key_id)
+                    .header(AUTH_TIMESTAMP_HEADER, timestamp.to_string())
+                    .header(AUTH_SIGNATURE_HEADER, signature);
+            }
+        }
+        response_body(request.body(body).send().await?).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use authorize_service::{
+        derive_route, handle_rejection, post_keygen_route, AuthConfig, Authenticator, Limits,
+    };
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use warp::Filter;
+
+    const SECRET: &[u8] = b"a secret that is long enough";
+
+    // Serves the keygen routes, authenticated with an HMAC secret, and returns their base URL.
+    fn serve() -> String {
+        let config = AuthConfig {
+            hmac_secrets: HashMap::from([("wallet".to_string(), hex::encode(SECRET))]),
+            ..Default::default()
+        };
+        let authenticator = Arc::new(Authenticator::new(config).unwrap());
+        let limits = Arc::new(Limits::unlimited());
+        let routes = post_keygen_route(authenticator.clone(), limits.clone())
+            .or(derive_route(authenticator, limits))
+            .recover(handle_rejection);
+        let (address, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        format!("http://{address}")
+    }
+
+    #[tokio::test]
+    async fn test_signed_requests() {
+        let base_url = serve();
+
+        // Unsigned requests are rejected with the service's error code.
+        let error = AuthorizeClient::new(&base_url)
+            .keygen(None)
+            .await
+            .unwrap_err()
+            .downcast::<ApiError>()
+            .unwrap();
+        assert_eq!(error.status, 401);
+        assert_eq!(error.code, "unauthorized");
+
+        // Signed requests are accepted, and decoded into the service's types.
+        let client = AuthorizeClient::new(&base_url).with_credentials(Credentials::Hmac {
+            key_id: "wallet".to_string(),
+            secret: SECRET.to_vec(),
+        });
+        client.keygen(None).await.unwrap();
+        let derived = client.derive(&DeriveRequest::default()).await.unwrap();
+        assert!(derived.mnemonic.is_some());
+        assert_eq!(derived.index, 0);
+    }
+}
@@ -0,0 +1,44 @@
+use super::*;
+
+use serde::Deserialize;
+use std::fmt;
+
+/// An error response from a service, with its stable code.
+/// Callers may downcast the errors of the clients to this type, to handle specific codes.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct ApiError {
+    /// The HTTP status code.
+    #[serde(skip)]
+    pub status: u16,
+    /// The stable error code, as listed in the OpenAPI document.
+    pub code: String,
+    /// The message of the error.
+    pub message: String,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {}", self.status, self.code, self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+// Returns the body of a successful response, or the error of a failed one.
+pub(crate) async fn response_body(response: reqwest::Response) -> Result<Vec<u8>> {
+    let status = response.status();
+    let bytes = response.bytes().await?;
+    match status.is_success() {
+        true => Ok(bytes.to_vec()),
+        false => {
+            let mut error =
+                serde_json::from_slice::<ApiError>(&bytes).unwrap_or_else(|_| ApiError {
+                    status: 0,
+                    code: "unknown".to_string(),
+                    message: String::from_utf8_lossy(&bytes).into_owned(),
+                });
+            error.status = status.as_u16();
+            Err(error.into())
+        }
+    }
+}
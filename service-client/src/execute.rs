@@ -0,0 +1,49 @@
+use super::*;
+
+use execute_service::{CurrentNetwork, ExecuteRequest, BINARY_CONTENT_TYPE};
+use snarkvm::ledger::block::Transaction;
+use snarkvm::prelude::{FromBytes, ToBytes};
+
+/// A typed client of the execute service.
+/// The routes and bodies are those of the service's OpenAPI document.
+#[derive(Clone)]
+pub struct ExecuteClient {
+    base_url: String,
+    http: Client,
+}
+
+impl ExecuteClient {
+    /// Returns a client of the service at the given base URL, e.g. `http://localhost:8081`.
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http: Client::new(),
+        }
+    }
+
+    /// Executes the authorizations into a transaction.
+    /// The request and response are sent in their binary form.
+    pub async fn execute(
+        &self,
+        request: &ExecuteRequest<CurrentNetwork>,
+    ) -> Result<Transaction<CurrentNetwork>> {
+        let response = self
+            .http
+            .request(Method::POST, format!("{}/execute", self.base_url))
+            .header("content-type", BINARY_CONTENT_TYPE)
+            .body(request.to_bytes_le()?)
+            .send()
+            .await?;
+        Transaction::from_bytes_le(&response_body(response).await?)
+    }
+
+    /// Returns the OpenAPI document of the service.
+    pub async fn openapi(&self) -> Result<serde_json::Value> {
+        let response = self
+            .http
+            .get(format!("{}/openapi.json", self.base_url))
+            .send()
+            .await?;
+        Ok(serde_json::from_slice(&response_body(response).await?)?)
+    }
+}
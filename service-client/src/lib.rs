@@ -0,0 +1,11 @@
+pub mod authorize;
+pub use authorize::*;
+
+pub mod error;
+pub use error::*;
+
+pub mod execute;
+pub use execute::*;
+
+use anyhow::Result;
+use reqwest::{Client, Method};
@@ -0,0 +1,119 @@
+use super::*;
+
+use anyhow::{bail, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The content type of binary (little-endian) bodies.
+pub const BINARY_CONTENT_TYPE: &str = "application/octet-stream";
+/// The content type of JSON bodies.
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// The encoding of a request or response body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Binary,
+    Json,
+}
+
+impl Encoding {
+    /// Returns the encoding of a request body, given its `Content-Type` header.
+    /// Bodies without a content type are decoded as binary.
+    pub fn from_content_type(content_type: Option<&str>) -> Result<Self> {
+        match content_type.map(media_type).as_deref() {
+            None | Some(BINARY_CONTENT_TYPE) => Ok(Self::Binary),
+            Some(JSON_CONTENT_TYPE) => Ok(Self::Json),
+            Some(media_type) => bail!("Unsupported content type '{media_type}'"),
+        }
+    }
+
+    /// Returns the encoding of a response body, given the `Accept` header of the request.
+    /// If the header does not name a supported encoding, `default` is used.
+    pub fn from_accept(accept: Option<&str>, default: Self) -> Self {
+        accept
+            .into_iter()
+            .flat_map(|accept| accept.split(','))
+            .find_map(|value| match media_type(value).as_str() {
+                BINARY_CONTENT_TYPE => Some(Self::Binary),
+                JSON_CONTENT_TYPE => Some(Self::Json),
+                _ => None,
+            })
+            .unwrap_or(default)
+    }
+
+    /// Returns the content type of the encoding.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Binary => BINARY_CONTENT_TYPE,
+            Self::Json => JSON_CONTENT_TYPE,
+        }
+    }
+
+    /// Decodes a value from the given bytes.
+    pub fn decode<T: FromBytes + DeserializeOwned>(&self, bytes: &[u8]) -> Result<T> {
+        match self {
+            Self::Binary => T::from_bytes_le(bytes),
+            Self::Json => Ok(serde_json::from_slice(bytes)?),
+        }
+    }
+
+    /// Encodes a value into bytes.
+    pub fn encode<T: ToBytes + Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Self::Binary => value.to_bytes_le(),
+            Self::Json => Ok(serde_json::to_vec(value)?),
+        }
+    }
+
+    /// Encodes a message for a client that sent a message of the given wire version.
+    /// Binary messages for clients of version 0 are written without the header.
+    pub fn encode_for_version<T: WireMessage + ToBytes + Serialize>(
+        &self,
+        message: &T,
+        version: u16,
+    ) -> Result<Vec<u8>> {
+        match self {
+            Self::Binary => to_bytes_for_version(message, version),
+            Self::Json => self.encode(message),
+        }
+    }
+}
+
+// Returns the lowercase media type of a header value, without parameters such as `charset` or `q`.
+fn media_type(value: &str) -> String {
+    value
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encoding_from_headers() {
+        // Request bodies without a content type are binary.
+        assert_eq!(Encoding::from_content_type(None).unwrap(), Encoding::Binary);
+        assert_eq!(
+            Encoding::from_content_type(Some("application/json; charset=utf-8")).unwrap(),
+            Encoding::Json
+        );
+        assert!(Encoding::from_content_type(Some("text/plain")).is_err());
+
+        // Responses use the first supported type in `Accept`, or the request encoding.
+        assert_eq!(
+            Encoding::from_accept(Some("text/html, application/json;q=0.9"), Encoding::Binary),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::from_accept(Some("*/*"), Encoding::Json),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::from_accept(None, Encoding::Binary),
+            Encoding::Binary
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+
+/// An error of the code that is shared by the services.
+/// Each service converts it into its own error, which is returned to the client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommonError {
+    /// A part of the request, such as a header, could not be decoded.
+    Decode(String),
+    /// The idempotency key was used with a different request, or its request is still in progress.
+    Conflict(String),
+    /// The caller exceeded a rate or concurrency limit, and may retry after the given number of seconds.
+    TooManyRequests(String, u64),
+    /// The service failed in a way that is not the fault of the request.
+    Internal(String),
+    /// The service is not ready to handle the request yet.
+    NotReady(String),
+    /// The work did not complete in time, and was cancelled.
+    Timeout(String),
+}
+
+impl fmt::Display for CommonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(message)
+            | Self::Conflict(message)
+            | Self::TooManyRequests(message, _)
+            | Self::Internal(message)
+            | Self::NotReady(message)
+            | Self::Timeout(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for CommonError {}
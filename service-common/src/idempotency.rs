@@ -0,0 +1,245 @@
+use super::*;
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The header that carries the idempotency key of a request.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// The header that is set on responses that were stored by an earlier request with the same key.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+/// The maximum length of an idempotency key, in bytes.
+pub const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+// The maximum number of keys that are stored at once.
+const MAX_IDEMPOTENCY_KEYS: usize = 10_000;
+
+/// A response that was stored under an idempotency key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+// A request under an idempotency key, which is either in flight or completed.
+struct IdempotencyEntry {
+    fingerprint: [u8; 32],
+    response: Option<StoredResponse>,
+    expires_at: Instant,
+}
+
+/// Stores the responses of completed requests under their idempotency keys, for a configurable time.
+/// Keys are scoped to the caller, so that callers can never see each other's responses.
+/// Services without callers scope every key to the empty caller.
+pub struct IdempotencyStore {
+    ttl: Duration,
+    entries: Mutex<HashMap<(String, String), IdempotencyEntry>>,
+}
+
+/// The outcome of beginning a request under an idempotency key.
+pub enum Idempotency {
+    /// The key is new. The request must be handled, and its response stored with the guard.
+    New(IdempotencyGuard),
+    /// A request with the same key and body completed, and this is its response.
+    Replay(StoredResponse),
+}
+
+/// Holds an idempotency key while its request is in flight.
+/// If the guard is dropped before a response is stored, the key is released, so that the request can be retried.
+pub struct IdempotencyGuard {
+    store: Arc<IdempotencyStore>,
+    key: (String, String),
+    completed: bool,
+}
+
+impl IdempotencyStore {
+    /// Returns a store that keeps responses for the given time.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the fingerprint of a request to the named route, which must match when the key is reused.
+    /// The fingerprint covers the encoding of the response, as the stored response is replayed as it was encoded.
+    pub fn fingerprint(route: &str, encoding: Encoding, body: &[u8]) -> [u8; 32] {
+        Sha256::new()
+            .chain_update(route.as_bytes())
+            .chain_update(b"\n")
+            .chain_update(encoding.content_type().as_bytes())
+            .chain_update(b"\n")
+            .chain_update(body)
+            .finalize()
+            .into()
+    }
+
+    /// Begins a request of the caller under an idempotency key.
+    /// Returns `Conflict` if the key was used with a different fingerprint, or if a request with the key is in flight.
+    pub fn begin(
+        self: &Arc<Self>,
+        caller: &str,
+        key: &str,
+        fingerprint: [u8; 32],
+        now: Instant,
+    ) -> Result<Idempotency, CommonError> {
+        if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+            return Err(CommonError::Decode(format!(
+                "The idempotency key must be between 1 and {MAX_IDEMPOTENCY_KEY_LENGTH} bytes"
+            )));
+        }
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| CommonError::Internal("The idempotency store is poisoned".to_string()))?;
+
+        // Evict the expired keys. Keys of requests in flight are held until their guards are dropped.
+        if entries.len() >= MAX_IDEMPOTENCY_KEYS {
+            entries.retain(|_, entry| entry.response.is_none() || entry.expires_at > now);
+            if entries.len() >= MAX_IDEMPOTENCY_KEYS {
+                return Err(CommonError::TooManyRequests(
+                    "Too many idempotency keys are stored".to_string(),
+                    1,
+                ));
+            }
+        }
+
+        let key = (caller.to_string(), key.to_string());
+        let live = |entry: &&IdempotencyEntry| entry.response.is_none() || entry.expires_at > now;
+        if let Some(entry) = entries.get(&key).filter(live) {
+            if entry.fingerprint != fingerprint {
+                return Err(CommonError::Conflict(
+                    "The idempotency key was used with a different request".to_string(),
+                ));
+            }
+            return match &entry.response {
+                Some(response) => Ok(Idempotency::Replay(response.clone())),
+                None => Err(CommonError::Conflict(
+                    "A request with the idempotency key is in progress".to_string(),
+                )),
+            };
+        }
+        // The key is held until the request completes, and then for the configured time.
+        entries.insert(
+            key.clone(),
+            IdempotencyEntry {
+                fingerprint,
+                response: None,
+                expires_at: now,
+            },
+        );
+        Ok(Idempotency::New(IdempotencyGuard {
+            store: self.clone(),
+            key,
+            completed: false,
+        }))
+    }
+}
+
+impl IdempotencyGuard {
+    /// Stores the response of the request, to be returned to its retries.
+    pub fn complete(mut self, response: StoredResponse) {
+        if let Ok(mut entries) = self.store.entries.lock() {
+            if let Some(entry) = entries.get_mut(&self.key) {
+                entry.response = Some(response);
+                entry.expires_at = Instant::now() + self.store.ttl;
+            }
+        }
+        self.completed = true;
+    }
+}
+
+impl Drop for IdempotencyGuard {
+    fn drop(&mut self) {
+        if !self.completed {
+            if let Ok(mut entries) = self.store.entries.lock() {
+                entries.remove(&self.key);
+            }
+        }
+    }
+}
+
+/// Returns a stored response, marked as replayed.
+pub fn replay_response(
+    response: StoredResponse,
+) -> Result<warp::http::Response<Vec<u8>>, CommonError> {
+    warp::http::Response::builder()
+        .header("content-type", response.content_type)
+        .header(IDEMPOTENT_REPLAYED_HEADER, "true")
+        .body(response.body)
+        .map_err(|error| CommonError::Internal(error.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn response(body: &[u8]) -> StoredResponse {
+        StoredResponse {
+            content_type: JSON_CONTENT_TYPE,
+            body: body.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_idempotency_keys() {
+        let store = Arc::new(IdempotencyStore::new(Duration::from_secs(60)));
+        let now = Instant::now();
+        let first = IdempotencyStore::fingerprint("transfer_public", Encoding::Json, b"first");
+        let second = IdempotencyStore::fingerprint("transfer_public", Encoding::Json, b"second");
+        let binary = IdempotencyStore::fingerprint("transfer_public", Encoding::Binary, b"first");
+
+        // While the request is in flight, retries conflict.
+        let guard = match store.begin("alice", "key", first, now).unwrap() {
+            Idempotency::New(guard) => guard,
+            Idempotency::Replay(_) => panic!("The key is new"),
+        };
+        assert!(matches!(
+            store.begin("alice", "key", first, now),
+            Err(CommonError::Conflict(_))
+        ));
+
+        // Once it completes, retries with the same body and encoding get its response, and others conflict.
+        guard.complete(response(b"authorization"));
+        match store.begin("alice", "key", first, now).unwrap() {
+            Idempotency::Replay(stored) => assert_eq!(stored, response(b"authorization")),
+            Idempotency::New(_) => panic!("The key was used"),
+        }
+        assert!(matches!(
+            store.begin("alice", "key", second, now),
+            Err(CommonError::Conflict(_))
+        ));
+        // The stored response is not replayed in another encoding.
+        assert!(matches!(
+            store.begin("alice", "key", binary, now),
+            Err(CommonError::Conflict(_))
+        ));
+
+        // Keys are scoped to the caller, and expire.
+        assert!(matches!(
+            store.begin("bob", "key", second, now),
+            Ok(Idempotency::New(_))
+        ));
+        assert!(matches!(
+            store.begin("alice", "key", second, now + Duration::from_secs(61)),
+            Ok(Idempotency::New(_))
+        ));
+    }
+
+    #[test]
+    fn test_failed_requests_release_their_keys() {
+        let store = Arc::new(IdempotencyStore::new(Duration::from_secs(60)));
+        let fingerprint = IdempotencyStore::fingerprint("join", Encoding::Binary, b"request");
+        let guard = store.begin("", "key", fingerprint, Instant::now()).unwrap();
+        drop(guard);
+        assert!(matches!(
+            store.begin("", "key", fingerprint, Instant::now()),
+            Ok(Idempotency::New(_))
+        ));
+        assert!(matches!(
+            store.begin("", "", fingerprint, Instant::now()),
+            Err(CommonError::Decode(_))
+        ));
+    }
+}
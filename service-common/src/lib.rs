@@ -0,0 +1,30 @@
+pub mod encoding;
+pub use encoding::*;
+
+pub mod error;
+pub use error::*;
+
+pub mod idempotency;
+pub use idempotency::*;
+
+pub mod limits;
+pub use limits::*;
+
+pub mod process;
+pub use process::*;
+
+pub mod shutdown;
+pub use shutdown::*;
+
+pub mod wire;
+pub use wire::*;
+
+pub mod workers;
+pub use workers::*;
+
+use snarkvm::circuit::AleoV0;
+use snarkvm::prelude::{FromBytes, Network, Testnet3, ToBytes};
+
+/// The network that both services are built for.
+pub type CurrentNetwork = Testnet3;
+pub type CurrentAleo = AleoV0;
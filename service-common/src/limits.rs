@@ -0,0 +1,289 @@
+use super::*;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+// The number of buckets above which the full buckets are evicted.
+const MAX_BUCKETS: usize = 10_000;
+
+/// The rate and concurrency limits, loaded from a TOML file.
+///
+/// ```toml
+/// requests_per_second = 5.0
+/// burst = 10
+/// ip_requests_per_second = 20.0
+/// ip_burst = 40
+/// max_body_bytes = 1024
+///
+/// [max_in_flight]
+/// authorize = 8
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LimitsConfig {
+    /// The rate at which each client may make requests, on average.
+    /// If unset, requests are not rate limited.
+    #[serde(default)]
+    pub requests_per_second: Option<f64>,
+    /// The number of requests that each client may make at once, after being idle.
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+    /// The rate at which each IP address may make requests, on average, which is checked before a request is authenticated,
+    /// so that unauthenticated requests cannot make the service verify credentials without limit.
+    /// If unset, requests are not rate limited by IP address.
+    #[serde(default)]
+    pub ip_requests_per_second: Option<f64>,
+    /// The number of requests that each IP address may make at once, after being idle.
+    #[serde(default = "default_burst")]
+    pub ip_burst: u32,
+    /// The maximum number of requests in flight, by route name.
+    /// Routes that are not listed are not limited.
+    #[serde(default)]
+    pub max_in_flight: HashMap<String, usize>,
+    /// The maximum size of a request body, in bytes.
+    /// If unset, each service uses its own default.
+    #[serde(default)]
+    pub max_body_bytes: Option<u64>,
+}
+
+fn default_burst() -> u32 {
+    10
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: None,
+            burst: default_burst(),
+            ip_requests_per_second: None,
+            ip_burst: default_burst(),
+            max_in_flight: HashMap::new(),
+            max_body_bytes: None,
+        }
+    }
+}
+
+/// A slot of a route with a concurrency limit, which is released when every clone of the permit is dropped.
+/// A request clones its permit into its job on the worker pool, so that the slot stays taken
+/// while the job runs, even after the request times out.
+#[derive(Clone)]
+pub struct Permit {
+    _permit: Option<Arc<OwnedSemaphorePermit>>,
+}
+
+// The token bucket of a client.
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+// A token bucket per client, which refills at a rate up to a burst.
+struct RateLimiter {
+    rate: Option<f64>,
+    burst: u32,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    fn new(rate: Option<f64>, burst: u32) -> Self {
+        Self {
+            rate,
+            burst,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Takes a token from the bucket of the client, or returns `TooManyRequests` if it is empty.
+    fn check(&self, client: &str, now: Instant) -> Result<(), CommonError> {
+        let rate = match self.rate {
+            Some(rate) if rate > 0.0 => rate,
+            _ => return Ok(()),
+        };
+        let burst = f64::from(self.burst.max(1));
+        let mut buckets = self
+            .buckets
+            .lock()
+            .map_err(|_| CommonError::Internal("The rate limiter is poisoned".to_string()))?;
+
+        // Evict the buckets that have refilled, as they are equivalent to new buckets.
+        if buckets.len() >= MAX_BUCKETS {
+            buckets.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.updated_at).as_secs_f64() * rate < burst
+            });
+        }
+
+        // Refill the bucket, and take a token.
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket {
+            tokens: burst,
+            updated_at: now,
+        });
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.updated_at = now;
+        if bucket.tokens < 1.0 {
+            let retry_after = ((1.0 - bucket.tokens) / rate).ceil() as u64;
+            return Err(CommonError::TooManyRequests(
+                "The rate limit was exceeded".to_string(),
+                retry_after.max(1),
+            ));
+        }
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+}
+
+/// Enforces a `LimitsConfig`, with token buckets per client and per IP address, and a semaphore per route.
+pub struct Limits {
+    config: LimitsConfig,
+    clients: RateLimiter,
+    ips: RateLimiter,
+    semaphores: HashMap<String, Arc<Semaphore>>,
+}
+
+impl Limits {
+    /// Returns limits that let every request through.
+    pub fn unlimited() -> Self {
+        Self::new(LimitsConfig::default())
+    }
+
+    /// Returns the limits for the given configuration.
+    pub fn new(config: LimitsConfig) -> Self {
+        let semaphores = config
+            .max_in_flight
+            .iter()
+            .map(|(route, max)| (route.clone(), Arc::new(Semaphore::new(*max))))
+            .collect();
+        Self {
+            clients: RateLimiter::new(config.requests_per_second, config.burst),
+            ips: RateLimiter::new(config.ip_requests_per_second, config.ip_burst),
+            config,
+            semaphores,
+        }
+    }
+
+    /// Returns the maximum size of a request body, in bytes, if it is configured.
+    pub fn max_body_bytes(&self) -> Option<u64> {
+        self.config.max_body_bytes
+    }
+
+    /// Loads the limits from a TOML configuration file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        let config = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse '{}'", path.display()))?;
+        Ok(Self::new(config))
+    }
+
+    /// Takes a token from the bucket of the client.
+    /// Returns `TooManyRequests` if the bucket is empty.
+    pub fn check_rate(&self, client: &str, now: Instant) -> Result<(), CommonError> {
+        self.clients.check(client, now)
+    }
+
+    /// Takes a token from the bucket of the IP address, before the request is authenticated.
+    /// Requests without a remote address share one bucket.
+    /// Returns `TooManyRequests` if the bucket is empty.
+    pub fn check_ip_rate(&self, ip: Option<IpAddr>, now: Instant) -> Result<(), CommonError> {
+        match ip {
+            Some(ip) => self.ips.check(&ip.to_string(), now),
+            None => self.ips.check("unknown", now),
+        }
+    }
+
+    /// Acquires a slot of the named route.
+    /// Returns `TooManyRequests` if the route is at its concurrency limit.
+    pub fn acquire(&self, route: &str) -> Result<Permit, CommonError> {
+        match self.semaphores.get(route) {
+            Some(semaphore) => match semaphore.clone().try_acquire_owned() {
+                Ok(permit) => Ok(Permit {
+                    _permit: Some(Arc::new(permit)),
+                }),
+                Err(_) => Err(CommonError::TooManyRequests(
+                    format!("Too many requests to '{route}' are in flight"),
+                    1,
+                )),
+            },
+            None => Ok(Permit { _permit: None }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_rate_limit() {
+        let limits = Limits::new(LimitsConfig {
+            requests_per_second: Some(1.0),
+            burst: 2,
+            ..LimitsConfig::default()
+        });
+        let now = Instant::now();
+
+        // The burst is allowed, and the next request is limited.
+        limits.check_rate("alice", now).unwrap();
+        limits.check_rate("alice", now).unwrap();
+        let error = limits.check_rate("alice", now).unwrap_err();
+        assert!(matches!(error, CommonError::TooManyRequests(_, 1)));
+
+        // Other clients have their own bucket.
+        limits.check_rate("bob", now).unwrap();
+
+        // The bucket refills over time.
+        limits
+            .check_rate("alice", now + Duration::from_secs(1))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_ip_rate_limit() {
+        let limits = Limits::new(LimitsConfig {
+            ip_requests_per_second: Some(1.0),
+            ip_burst: 1,
+            ..LimitsConfig::default()
+        });
+        let now = Instant::now();
+        let ip = Some(IpAddr::from([10, 0, 0, 1]));
+
+        // The IP addresses have their own buckets, apart from the clients.
+        limits.check_ip_rate(ip, now).unwrap();
+        assert!(matches!(
+            limits.check_ip_rate(ip, now),
+            Err(CommonError::TooManyRequests(_, 1))
+        ));
+        limits
+            .check_ip_rate(Some(IpAddr::from([10, 0, 0, 2])), now)
+            .unwrap();
+        limits.check_rate("10.0.0.1", now).unwrap();
+        limits
+            .check_ip_rate(ip, now + Duration::from_secs(1))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_concurrency_limit() {
+        let limits = Limits::new(LimitsConfig {
+            max_in_flight: [("authorize".to_string(), 1)].into_iter().collect(),
+            ..LimitsConfig::default()
+        });
+
+        // The second request is limited while the first one is in flight.
+        let permit = limits.acquire("authorize").unwrap();
+        assert!(limits.acquire("authorize").is_err());
+        drop(permit);
+        limits.acquire("authorize").unwrap();
+
+        // Routes that are not listed are not limited.
+        let _first = limits.acquire("other").unwrap();
+        let _second = limits.acquire("other").unwrap();
+    }
+}
@@ -0,0 +1,41 @@
+use super::*;
+
+use anyhow::Result;
+use snarkvm::prelude::Process;
+use std::sync::{Arc, OnceLock};
+
+/// The `Process`, which is shared by every request once it is loaded.
+/// The service is live before the process is loaded, and ready after.
+#[derive(Clone, Default)]
+pub struct SharedProcess(Arc<OnceLock<Arc<Process<CurrentNetwork>>>>);
+
+impl SharedProcess {
+    /// Loads the process. This blocks for as long as `Process::load` does.
+    pub fn load(&self) -> Result<()> {
+        self.load_with(|| ())
+    }
+
+    /// Loads the process, and then runs `warm`, before the process is shared.
+    /// The service is not ready until both complete, so `warm` may load keys that the first request would otherwise wait for.
+    pub fn load_with(&self, warm: impl FnOnce()) -> Result<()> {
+        // Load the process.
+        let process = Process::load()?;
+        warm();
+        // Share the process. If it was already loaded, the new one is dropped.
+        let _ = self.0.set(Arc::new(process));
+        Ok(())
+    }
+
+    /// Returns `true` if the process is loaded.
+    pub fn is_ready(&self) -> bool {
+        self.0.get().is_some()
+    }
+
+    /// Returns the process, or `NotReady` if it is not loaded yet.
+    /// The process is shared, so that it can be moved into jobs on the worker pool.
+    pub fn get(&self) -> Result<Arc<Process<CurrentNetwork>>, CommonError> {
+        self.0.get().cloned().ok_or_else(|| {
+            CommonError::NotReady("The service is still loading the process".to_string())
+        })
+    }
+}
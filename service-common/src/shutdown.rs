@@ -0,0 +1,231 @@
+use prometheus::{IntGauge, IntGaugeVec};
+use std::convert::Infallible;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::watch;
+use warp::path::FullPath;
+use warp::{Filter, Reply};
+
+/// Tracks the shutdown of the service, which begins when the process receives SIGTERM or Ctrl-C.
+#[derive(Clone, Debug)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    /// Listens for SIGTERM and Ctrl-C.
+    pub fn listen() -> Self {
+        let (sender, receiver) = watch::channel(false);
+        tokio::spawn(async move {
+            wait_for_signal().await;
+            let _ = sender.send(true);
+        });
+        Self(receiver)
+    }
+
+    /// Returns `true` if the shutdown has begun.
+    pub fn has_begun(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Completes once the shutdown has begun.
+    pub async fn begun(mut self) {
+        let _ = self.0.wait_for(|begun| *begun).await;
+    }
+}
+
+// Completes when the process receives SIGTERM or Ctrl-C.
+async fn wait_for_signal() {
+    let ctrl_c = async {
+        if let Err(error) = tokio::signal::ctrl_c().await {
+            log::error!("Failed to listen for Ctrl-C: {error}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(error) => {
+                log::error!("Failed to listen for SIGTERM: {error}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => log::info!("Received Ctrl-C"),
+        () = terminate => log::info!("Received SIGTERM"),
+    }
+}
+
+/// Marks a request as in flight while it is held.
+pub struct InFlight(IntGauge);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+/// Counts the requests that the filter is handling in the gauge, by the route label of their path.
+/// This must wrap the recovered routes, so that every request is counted until its response is ready.
+pub fn track_in_flight<F, T>(
+    gauge: IntGaugeVec,
+    route_label: fn(&str) -> &'static str,
+    filter: F,
+) -> impl Filter<Extract = (T,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (T,), Error = Infallible> + Clone + Send,
+    T: Reply,
+{
+    warp::path::full()
+        .map(move |path: FullPath| {
+            let gauge = gauge.with_label_values(&[route_label(path.as_str())]);
+            gauge.inc();
+            InFlight(gauge)
+        })
+        .and(filter)
+        .map(|_in_flight: InFlight, reply: T| reply)
+}
+
+/// Runs a server that was bound with `shutdown` as its graceful shutdown signal.
+/// Once the shutdown begins, the server stops accepting requests, and the requests in flight are given until the deadline to complete.
+/// Returns the number of requests in flight at the deadline, which were dropped.
+pub async fn serve_until_drained(
+    server: impl Future<Output = ()>,
+    shutdown: Shutdown,
+    deadline: Duration,
+    requests_in_flight: impl Fn() -> i64,
+) -> i64 {
+    tokio::pin!(server);
+    tokio::select! {
+        () = &mut server => return 0,
+        () = shutdown.begun() => {}
+    }
+    log::info!(
+        "Shutting down, waiting up to {} seconds for {} requests in flight",
+        deadline.as_secs(),
+        requests_in_flight()
+    );
+    match tokio::time::timeout(deadline, server).await {
+        Ok(()) => 0,
+        Err(_) => requests_in_flight(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use prometheus::Opts;
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio::sync::Notify;
+    use warp::Rejection;
+
+    #[tokio::test]
+    async fn test_drain() {
+        // A server whose requests complete within the deadline drops none.
+        let (sender, receiver) = watch::channel(false);
+        let shutdown = Shutdown(receiver);
+        let in_flight = Arc::new(AtomicI64::new(1));
+        let server = {
+            let in_flight = in_flight.clone();
+            let mut shutdown = shutdown.clone();
+            async move {
+                let _ = shutdown.0.wait_for(|begun| *begun).await;
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                in_flight.store(0, Ordering::SeqCst);
+            }
+        };
+        assert!(!shutdown.has_begun());
+        sender.send(true).unwrap();
+        let dropped = serve_until_drained(server, shutdown, Duration::from_secs(10), || {
+            in_flight.load(Ordering::SeqCst)
+        })
+        .await;
+        assert_eq!(dropped, 0);
+
+        // A server whose requests outlast the deadline reports them as dropped.
+        let (sender, receiver) = watch::channel(false);
+        sender.send(true).unwrap();
+        let dropped = serve_until_drained(
+            std::future::pending(),
+            Shutdown(receiver),
+            Duration::from_millis(10),
+            || 2,
+        )
+        .await;
+        assert_eq!(dropped, 2);
+    }
+
+    #[tokio::test]
+    async fn test_server_drains_the_requests_in_flight() {
+        let in_flight = IntGaugeVec::new(
+            Opts::new("in_flight_requests", "The number of requests in flight"),
+            &["route"],
+        )
+        .unwrap();
+        let gauge = in_flight.with_label_values(&["slow"]);
+
+        // A route that completes once it is released.
+        let release = Arc::new(Notify::new());
+        let route = warp::path("slow")
+            .and_then({
+                let release = release.clone();
+                move || {
+                    let release = release.clone();
+                    async move {
+                        release.notified().await;
+                        Ok::<_, Rejection>("done")
+                    }
+                }
+            })
+            .recover(|_| async { Ok::<_, Infallible>(warp::http::StatusCode::NOT_FOUND) });
+        let routes = track_in_flight(in_flight, |_| "slow", route);
+
+        let (sender, receiver) = watch::channel(false);
+        let shutdown = Shutdown(receiver);
+        let (address, server) = warp::serve(routes)
+            .try_bind_with_graceful_shutdown(([127, 0, 0, 1], 0), shutdown.clone().begun())
+            .unwrap();
+        // The address is taken, so a second server fails to bind, instead of panicking.
+        assert!(warp::serve(warp::any().map(warp::reply))
+            .try_bind_with_graceful_shutdown(address, std::future::pending())
+            .is_err());
+
+        let client = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            stream
+                .write_all(b"GET /slow HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        });
+        // Once the request is in flight, begin the shutdown, and then let the request complete.
+        tokio::spawn({
+            let gauge = gauge.clone();
+            async move {
+                while gauge.get() == 0 {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                sender.send(true).unwrap();
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                release.notify_one();
+            }
+        });
+        let dropped =
+            serve_until_drained(server, shutdown, Duration::from_secs(10), || gauge.get()).await;
+        assert_eq!(dropped, 0);
+        assert!(client.await.unwrap().starts_with("HTTP/1.1 200"));
+        assert_eq!(gauge.get(), 0);
+    }
+}
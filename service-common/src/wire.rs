@@ -0,0 +1,294 @@
+use super::*;
+
+use snarkvm::prelude::IoResult;
+use std::io::{Chain, Cursor, Error, ErrorKind, Read, Write};
+
+/// The magic bytes that begin every binary request and response.
+pub const WIRE_MAGIC: [u8; 4] = *b"ALEO";
+/// The version of the binary format.
+/// Messages of the kinds that predate the header may be sent without it, and are then read as version 0.
+/// In version 0, a request is signed by a bare private key, rather than by a tagged signer.
+pub const WIRE_VERSION: u16 = 1;
+/// The length of the header, in bytes.
+pub const WIRE_HEADER_LENGTH: usize = 9;
+
+/// The kinds of binary messages, which are recorded in their headers so that one kind is never read as another.
+/// The kinds are unique across the services.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WireKind {
+    AuthorizeRequest = 1,
+    TransferPrivateRequest = 2,
+    TransferPrivateToPublicRequest = 3,
+    TransferPublicToPrivateRequest = 4,
+    JoinRequest = 5,
+    SplitRequest = 6,
+    BondPublicRequest = 7,
+    UnbondPublicRequest = 8,
+    UnbondDelegatorAsValidatorRequest = 9,
+    ClaimUnbondPublicRequest = 10,
+    AuthorizeResponse = 11,
+    ExecuteRequest = 12,
+}
+
+impl WireKind {
+    const ALL: [Self; 12] = [
+        Self::AuthorizeRequest,
+        Self::TransferPrivateRequest,
+        Self::TransferPrivateToPublicRequest,
+        Self::TransferPublicToPrivateRequest,
+        Self::JoinRequest,
+        Self::SplitRequest,
+        Self::BondPublicRequest,
+        Self::UnbondPublicRequest,
+        Self::UnbondDelegatorAsValidatorRequest,
+        Self::ClaimUnbondPublicRequest,
+        Self::AuthorizeResponse,
+        Self::ExecuteRequest,
+    ];
+
+    /// Returns `true` for the kinds that were sent before the header was introduced,
+    /// so that a message of one of these kinds without a header is read as version 0.
+    /// The other kinds were introduced with the header, so they must always have one.
+    pub fn predates_header(self) -> bool {
+        matches!(
+            self,
+            Self::AuthorizeRequest | Self::AuthorizeResponse | Self::ExecuteRequest
+        )
+    }
+
+    /// Returns the kind with the given tag, if any.
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| *kind as u8 == tag)
+    }
+}
+
+/// A binary message, which begins with a header of the magic bytes, the version of the format, the network ID and its kind.
+pub trait WireMessage {
+    /// The kind of the message.
+    const WIRE_KIND: WireKind;
+}
+
+/// The reader of a message after its header.
+pub type WireReader<R> = Chain<Cursor<Vec<u8>>, R>;
+
+/// Writes the header of a message of the given kind, for the network.
+pub fn write_wire_header<N: Network, W: Write>(kind: WireKind, mut writer: W) -> IoResult<()> {
+    writer.write_all(&WIRE_MAGIC)?;
+    WIRE_VERSION.write_le(&mut writer)?;
+    N::ID.write_le(&mut writer)?;
+    (kind as u8).write_le(&mut writer)
+}
+
+/// Reads the header of a message of the given kind, and returns its version and the reader of the rest of the message.
+/// Messages without a header, of a kind that predates the header, are read whole, as version 0,
+/// and the caller reads their fields in the layout of version 0.
+/// A message without a header that happens to begin with the magic bytes is misread as having one.
+/// Messages without a header of the other kinds, and messages of a newer version, of another network or of another kind are rejected.
+pub fn read_wire_header<N: Network, R: Read>(
+    kind: WireKind,
+    mut reader: R,
+) -> IoResult<(u16, WireReader<R>)> {
+    let mut magic = Vec::with_capacity(WIRE_MAGIC.len());
+    (&mut reader)
+        .take(WIRE_MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+    if magic != WIRE_MAGIC {
+        if !kind.predates_header() {
+            return Err(invalid_data(format!(
+                "A message of kind {kind:?} must begin with the wire header"
+            )));
+        }
+        return Ok((0, Cursor::new(magic).chain(reader)));
+    }
+
+    let version = u16::read_le(&mut reader)?;
+    if version == 0 || version > WIRE_VERSION {
+        return Err(invalid_data(format!(
+            "Unsupported wire format version {version}, expected at most {WIRE_VERSION}"
+        )));
+    }
+    let network_id = u16::read_le(&mut reader)?;
+    if network_id != N::ID {
+        return Err(invalid_data(format!(
+            "The message is for network {network_id}, but this build is for network {}",
+            N::ID
+        )));
+    }
+    let tag = u8::read_le(&mut reader)?;
+    if tag != kind as u8 {
+        return Err(invalid_data(match WireKind::from_tag(tag) {
+            Some(found) => format!("Expected a message of kind {kind:?}, found {found:?}"),
+            None => format!("Expected a message of kind {kind:?}, found unknown kind {tag}"),
+        }));
+    }
+    Ok((version, Cursor::new(Vec::new()).chain(reader)))
+}
+
+/// Returns the version of a binary message, which is 0 for messages without a header.
+pub fn wire_version(bytes: &[u8]) -> u16 {
+    match bytes.strip_prefix(&WIRE_MAGIC) {
+        Some([low, high, ..]) => u16::from_le_bytes([*low, *high]),
+        _ => 0,
+    }
+}
+
+/// Writes a message for a client that sent a message of the given version.
+/// Clients of version 0 predate the header, so the message is written without it.
+/// This is only correct for messages whose fields have the same layout in every version, such as responses.
+pub fn to_bytes_for_version<T: WireMessage + ToBytes>(
+    message: &T,
+    version: u16,
+) -> anyhow::Result<Vec<u8>> {
+    let bytes = message.to_bytes_le()?;
+    match version {
+        0 => Ok(bytes[WIRE_HEADER_LENGTH..].to_vec()),
+        _ => Ok(bytes),
+    }
+}
+
+/// Returns a description of the header of a message of the given kind, for the OpenAPI document.
+pub fn wire_header_encoding(kind: WireKind) -> String {
+    let header = format!(
+        "The magic bytes 'ALEO', then the u16 version {WIRE_VERSION}, the u16 network ID, and the u8 kind {}",
+        kind as u8
+    );
+    match kind.predates_header() {
+        true => format!("{header}. Messages without the header are read as version 0"),
+        false => format!("{header}. Messages without the header are rejected"),
+    }
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Returns a header with the given version, network ID and kind.
+    fn header(version: u16, network_id: u16, kind: u8) -> Vec<u8> {
+        let mut bytes = WIRE_MAGIC.to_vec();
+        bytes.extend(version.to_le_bytes());
+        bytes.extend(network_id.to_le_bytes());
+        bytes.push(kind);
+        bytes
+    }
+
+    // Reads the header, and returns the rest of the message.
+    fn read(kind: WireKind, bytes: &[u8]) -> IoResult<Vec<u8>> {
+        let mut rest = Vec::new();
+        let (_, mut reader) = read_wire_header::<CurrentNetwork, _>(kind, bytes)?;
+        reader.read_to_end(&mut rest)?;
+        Ok(rest)
+    }
+
+    #[test]
+    fn test_wire_header() {
+        // The header is read back, and the rest of the message follows it.
+        let mut bytes = Vec::new();
+        write_wire_header::<CurrentNetwork, _>(WireKind::JoinRequest, &mut bytes).unwrap();
+        assert_eq!(
+            bytes,
+            header(
+                WIRE_VERSION,
+                CurrentNetwork::ID,
+                WireKind::JoinRequest as u8
+            )
+        );
+        bytes.extend([1, 2, 3]);
+        assert_eq!(bytes.len(), WIRE_HEADER_LENGTH + 3);
+        assert_eq!(read(WireKind::JoinRequest, &bytes).unwrap(), [1, 2, 3]);
+        assert_eq!(wire_version(&bytes), WIRE_VERSION);
+
+        // Messages without a header are read whole, as version 0, however short.
+        for legacy in [&[0u8, 1, 2, 3, 4, 5][..], &[1], &[]] {
+            let (version, _) =
+                read_wire_header::<CurrentNetwork, _>(WireKind::AuthorizeRequest, legacy).unwrap();
+            assert_eq!(version, 0);
+            assert_eq!(wire_version(legacy), 0);
+            assert_eq!(read(WireKind::AuthorizeRequest, legacy).unwrap(), legacy);
+        }
+    }
+
+    #[test]
+    fn test_messages_without_a_header() {
+        // Only the kinds that predate the header may be sent without it.
+        for kind in WireKind::ALL {
+            let result = read(kind, &[0, 1, 2, 3, 4, 5]);
+            match kind {
+                WireKind::AuthorizeRequest
+                | WireKind::AuthorizeResponse
+                | WireKind::ExecuteRequest => {
+                    assert_eq!(result.unwrap(), [0, 1, 2, 3, 4, 5])
+                }
+                _ => {
+                    let error = result.unwrap_err();
+                    assert_eq!(error.kind(), ErrorKind::InvalidData);
+                    assert_eq!(
+                        error.to_string(),
+                        format!("A message of kind {kind:?} must begin with the wire header")
+                    );
+                }
+            }
+            assert!(
+                wire_header_encoding(kind).ends_with(match kind.predates_header() {
+                    true => "read as version 0",
+                    false => "rejected",
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_bytes_for_version() {
+        struct Message;
+        impl WireMessage for Message {
+            const WIRE_KIND: WireKind = WireKind::AuthorizeResponse;
+        }
+        impl ToBytes for Message {
+            fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()>
+            where
+                Self: Sized,
+            {
+                write_wire_header::<CurrentNetwork, _>(Self::WIRE_KIND, &mut writer)?;
+                writer.write_all(&[1, 2, 3])
+            }
+        }
+
+        // Clients of version 0 get the message without the header, and others get it whole.
+        assert_eq!(to_bytes_for_version(&Message, 0).unwrap(), [1, 2, 3]);
+        let bytes = to_bytes_for_version(&Message, WIRE_VERSION).unwrap();
+        assert_eq!(bytes, Message.to_bytes_le().unwrap());
+        assert_eq!(
+            read(WireKind::AuthorizeResponse, &bytes).unwrap(),
+            [1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_wire_header_mismatches() {
+        let id = CurrentNetwork::ID;
+        let kind = WireKind::AuthorizeResponse as u8;
+        for (bytes, expected) in [
+            (
+                header(WIRE_VERSION + 1, id, kind),
+                "Unsupported wire format version",
+            ),
+            (header(0, id, kind), "Unsupported wire format version"),
+            (
+                header(WIRE_VERSION, id.wrapping_add(1), kind),
+                "is for network",
+            ),
+            (header(WIRE_VERSION, id, 1), "found AuthorizeRequest"),
+            (header(WIRE_VERSION, id, 200), "unknown kind 200"),
+        ] {
+            let error = read(WireKind::AuthorizeResponse, &bytes).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+            assert!(error.to_string().contains(expected), "{error}");
+        }
+        // A truncated header is an error, not a message without a header.
+        let bytes = header(WIRE_VERSION, id, kind);
+        assert!(read(WireKind::AuthorizeResponse, &bytes[..6]).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use super::*;
+
+use anyhow::{bail, Result};
+use prometheus::IntGauge;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+// The number of jobs that may wait for a worker, above which new jobs are rejected.
+const MAX_QUEUED_JOBS: usize = 1024;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Marks a job as cancelled, once it times out or its request is dropped.
+/// A cancelled job is skipped if it is still queued. A running job is not interrupted:
+/// a call into snarkVM runs to completion, so a job only stops early if it checks this between its steps,
+/// and it keeps its worker until then.
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    /// Cancels the job.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if the job was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Returns an error if the job was cancelled.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            bail!("The job was cancelled");
+        }
+        Ok(())
+    }
+}
+
+// Cancels the job when the request that is waiting for it is dropped.
+struct CancelOnDrop(Cancellation);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// A pool of threads that runs CPU-heavy work, such as authorizations and proofs, off the async runtime.
+pub struct WorkerPool {
+    sender: SyncSender<Job>,
+    threads: usize,
+    timeout: Duration,
+    queue_depth: IntGauge,
+}
+
+impl WorkerPool {
+    /// Starts a pool with the given number of threads, or one per CPU if zero.
+    /// Jobs that do not complete within the timeout are cancelled.
+    pub fn new(threads: usize, timeout: Duration, queue_depth: IntGauge) -> Result<Self> {
+        let threads = match threads {
+            0 => std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+            threads => threads,
+        };
+        let (sender, receiver) = mpsc::sync_channel::<Job>(MAX_QUEUED_JOBS);
+        let receiver = Arc::new(Mutex::new(receiver));
+        for index in 0..threads {
+            let receiver = receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("worker-{index}"))
+                .spawn(move || work(&receiver))?;
+        }
+        Ok(Self {
+            sender,
+            threads,
+            timeout,
+            queue_depth,
+        })
+    }
+
+    /// Returns the number of threads in the pool.
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Runs the job on a worker, and waits for its result.
+    /// Returns `Timeout` if the job does not complete within the timeout, and `TooManyRequests` if the queue is full.
+    /// After a timeout, the job is cancelled, but it may keep running on its worker until its current step returns.
+    pub async fn run<T, F>(&self, job: F) -> Result<T, CommonError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Cancellation) -> T + Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let cancellation = Cancellation::default();
+        let _cancel_on_drop = CancelOnDrop(cancellation.clone());
+
+        // Queue the job.
+        let queue_depth = self.queue_depth.clone();
+        let job: Job = {
+            let cancellation = cancellation.clone();
+            Box::new(move || {
+                queue_depth.dec();
+                // Skip the job if it was cancelled while it was queued.
+                if cancellation.is_cancelled() {
+                    return;
+                }
+                let _ = sender.send(catch_unwind(AssertUnwindSafe(|| job(&cancellation))));
+            })
+        };
+        self.queue_depth.inc();
+        if let Err(error) = self.sender.try_send(job) {
+            self.queue_depth.dec();
+            return Err(match error {
+                TrySendError::Full(_) => {
+                    CommonError::TooManyRequests("The worker queue is full".to_string(), 1)
+                }
+                TrySendError::Disconnected(_) => {
+                    CommonError::Internal("The worker pool has stopped".to_string())
+                }
+            });
+        }
+
+        // Wait for the result. If the wait times out, the job is cancelled on drop.
+        match tokio::time::timeout(self.timeout, receiver).await {
+            Ok(Ok(Ok(output))) => Ok(output),
+            Ok(Ok(Err(_))) => Err(CommonError::Internal("The job panicked".to_string())),
+            Ok(Err(_)) => Err(CommonError::Internal("The job was dropped".to_string())),
+            Err(_) => Err(CommonError::Timeout(format!(
+                "The job did not complete within {} seconds",
+                self.timeout.as_secs_f64()
+            ))),
+        }
+    }
+}
+
+// Runs queued jobs until the pool is dropped.
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+        match job {
+            Ok(job) => job(),
+            Err(_) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_timed_out_jobs_are_cancelled() {
+        let queue_depth = IntGauge::new("queue_depth", "The number of queued jobs").unwrap();
+        let workers = WorkerPool::new(1, Duration::from_millis(100), queue_depth).unwrap();
+
+        // The job runs until it is cancelled, and reports that it was.
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let error = workers
+            .run(move |cancellation| {
+                while !cancellation.is_cancelled() {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                sender.send(cancellation.check().is_err()).unwrap();
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(error, CommonError::Timeout(_)));
+        assert!(receiver.recv_timeout(Duration::from_secs(1)).unwrap());
+
+        // The worker is free for the next job.
+        assert_eq!(workers.run(|_| 1 + 1).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_jobs_hold_their_permits() {
+        let queue_depth = IntGauge::new("queue_depth", "The number of queued jobs").unwrap();
+        let workers = WorkerPool::new(1, Duration::from_millis(100), queue_depth).unwrap();
+        let limits = Limits::new(LimitsConfig {
+            max_in_flight: [("job".to_string(), 1)].into_iter().collect(),
+            ..LimitsConfig::default()
+        });
+
+        // The job ignores its cancellation, like a proof, and holds a clone of the permit of its request.
+        let permit = limits.acquire("job").unwrap();
+        let job_permit = permit.clone();
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        let (stopped_sender, stopped) = std::sync::mpsc::channel();
+        let error = workers
+            .run(move |_| {
+                let _permit = job_permit;
+                let _ = released.recv();
+                stopped_sender.send(()).unwrap();
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(error, CommonError::Timeout(_)));
+
+        // The request has completed, but its slot is taken until the job stops.
+        drop(permit);
+        assert!(limits.acquire("job").is_err());
+        release.send(()).unwrap();
+        stopped.recv_timeout(Duration::from_secs(1)).unwrap();
+        while limits.acquire("job").is_err() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[test]
+    fn test_jobs_run_off_the_runtime() {
+        let queue_depth = IntGauge::new("queue_depth", "The number of queued jobs").unwrap();
+        let workers = WorkerPool::new(1, Duration::from_secs(10), queue_depth).unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            // The job blocks its thread, as long as the timer below and more.
+            let job = tokio::spawn(async move {
+                workers
+                    .run(|_| std::thread::sleep(Duration::from_millis(500)))
+                    .await
+            });
+            // The runtime has a single thread, so the timer only fires on time if the job is off the runtime.
+            let start = std::time::Instant::now();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            assert!(start.elapsed() < Duration::from_millis(400));
+            assert!(!job.is_finished());
+            job.await.unwrap().unwrap();
+        });
+    }
+}
@@ -1,50 +1,33 @@
-use authorize_service::*;
-use execute_service::*;
+use authorize_service::{AuthorizeRequest, Signer};
+use execute_service::ExecuteRequest;
+use service_client::{AuthorizeClient, ExecuteClient};
 use std::str::FromStr;
 
 use anyhow::{bail, Result};
 use reqwest::Client;
-use snarkvm::ledger::block::Transaction;
-use snarkvm::prelude::{
-    Address, Field, FromBytes, Network, PrivateKey, Testnet3, ToBytes, Uniform, U64,
-};
+use snarkvm::prelude::{Address, Network, PrivateKey, Testnet3, U64};
 
-const KEYGEN_URL: &str = "http://localhost:8080/keygen";
-const AUTHORIZE_URL: &str = "http://localhost:8080/authorize";
-const EXECUTE_URL: &str = "http://localhost:8081/execute";
+const AUTHORIZE_SERVICE_URL: &str = "http://localhost:8080";
+const EXECUTE_SERVICE_URL: &str = "http://localhost:8081";
 
 const BROADCAST_URL: &str = "http://localhost:3033/testnet3/transaction/broadcast";
 const STATE_ROOT_URL: &str = "http://localhost:3033/testnet3/stateRoot/latest";
 
+const DEFAULT_PRIORITY_FEE_IN_MICROCREDITS: u64 = 10;
+
 const DEVNET_PRIVATE_KEY: &str = "APrivateKey1zkp8CZNn3yeCseEtxuVPbDCwSyhGW6yZKUYKfgXmcpoGPWH";
 
 type CurrentNetwork = Testnet3;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Create a `Client` instance.
+    // Create the clients of the services, and of the node.
+    let authorize_client = AuthorizeClient::new(AUTHORIZE_SERVICE_URL);
+    let execute_client = ExecuteClient::new(EXECUTE_SERVICE_URL);
     let client = Client::new();
 
-    // Initialize a random number generator.
-    let rng = &mut rand::thread_rng();
-
-    // Generate a seed.
-    let seed = Field::<CurrentNetwork>::rand(rng);
-
-    // Construct the url for the keygen request.
-    let url = format!("{}/{}", KEYGEN_URL, *seed);
-
-    // Send the request.
-    let response = client.get(url).send().await?;
-
-    // If the request was successful, deserialize the response bytes as a `PrivateKey`.
-    let _private_key = match response.status().is_success() {
-        true => {
-            let bytes = response.bytes().await?;
-            PrivateKey::<CurrentNetwork>::from_bytes_le(&bytes)?
-        }
-        false => bail!("Keygen request failed with status: {}", response.status()),
-    };
+    // Generate an account. Without a seed, the key is generated from the server's entropy.
+    let _private_key = authorize_client.keygen(None).await?.private_key;
 
     // Use the `DEVNET_PRIVATE_KEY`, if desired.
     let private_key = PrivateKey::<CurrentNetwork>::from_str(DEVNET_PRIVATE_KEY)?;
@@ -56,36 +39,26 @@ async fn main() -> Result<()> {
         Address::from_str("aleo16y9l270rdyun3tpfqjppj7hmvtwc03tl852q4v7fddfrus9ansrqsv35x7")?;
     // Construct the amount.
     let amount_in_microcredits = U64::new(1000);
-    // Construct the priority fee.
-    let priority_fee_in_microcredits = U64::new(10);
+    // Construct the priority fee, from the fees paid in recent blocks if the service suggests them.
+    let priority_fee_in_microcredits =
+        match authorize_client.suggest_fee(Some("transfer_public")).await {
+            Ok(suggestion) => U64::new(suggestion.medium),
+            Err(error) => {
+                println!("Using the default priority fee, as no fee was suggested: {error}");
+                U64::new(DEFAULT_PRIORITY_FEE_IN_MICROCREDITS)
+            }
+        };
 
     // Construct an `AuthorizeRequest`.
     let authorize_request = AuthorizeRequest {
-        private_key,
+        signer: Signer::PrivateKey(private_key),
         recipient,
         amount_in_microcredits,
         priority_fee_in_microcredits,
     };
 
     // Send the request.
-    let response = client
-        .post(AUTHORIZE_URL)
-        .body(authorize_request.to_bytes_le()?)
-        .header("Content-Type", "application/octet-stream")
-        .send()
-        .await?;
-
-    // If the request was successful, deserialize the response bytes as an `AuthorizeResponse`.
-    let authorize_response = match response.status().is_success() {
-        true => {
-            let bytes = response.bytes().await?;
-            AuthorizeResponse::<CurrentNetwork>::from_bytes_le(&bytes)?
-        }
-        false => bail!(
-            "Authorization request failed with status: {}",
-            response.status()
-        ),
-    };
+    let authorize_response = authorize_client.authorize(&authorize_request).await?;
 
     // Get the latest state root.
     let response = client.get(STATE_ROOT_URL).send().await?;
@@ -114,24 +87,7 @@ async fn main() -> Result<()> {
     };
 
     // Send the request.
-    let response = client
-        .post(EXECUTE_URL)
-        .body(execute_request.to_bytes_le()?)
-        .header("Content-Type", "application/octet-stream")
-        .send()
-        .await?;
-
-    // If the request was successful, deserialize the response bytes as a `Transaction`.
-    let transaction = match response.status().is_success() {
-        true => {
-            let bytes = response.bytes().await?;
-            Transaction::<CurrentNetwork>::from_bytes_le(&bytes)?
-        }
-        false => bail!(
-            "Execution request failed with status: {}",
-            response.status()
-        ),
-    };
+    let transaction = execute_client.execute(&execute_request).await?;
 
     // Send the transaction as a broadcast request as JSON.
     let response = client.post(BROADCAST_URL).json(&transaction).send().await?;